@@ -1,10 +1,17 @@
 use eframe::egui;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use image::metadata::Orientation;
 
-use crate::icons::{icon, icon_text};
-use crate::image_splitter::{ImageSplitter, SplitConfig};
+use crate::dialogs::{DialogKind, PendingDialog, IMAGE_EXTENSIONS};
+use crate::icons::{icon, icon_text, Icon};
+use crate::image_splitter::{BatchPlan, BatchReport, CropRegion, ImageSplitter, LineImport, LineMode, NamingScheme, orientation_label, OutputFormat, OutputLocation, PngCompression, PngFilter, Rotation, SplitConfig, TileOrder, DEFAULT_JPEG_QUALITY, LINE_EPSILON, MIN_USEFUL_TILE_PX, padded_index, PlanWarning, render_file_template};
+use crate::keybindings::{Action, KeyBinding};
+use crate::settings::{AppSettings, OutputDefaults, DEFAULT_SNAP_STEP, SETTINGS_KEY};
+use crate::sidecar::{self, SidecarSync};
+use crate::thumbnail_cache::{self, ThumbnailLoader, ThumbnailTextures};
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 enum LineType {
@@ -21,6 +28,112 @@ enum UpdateStatus {
     Error(String),
 }
 
+/// 后台批处理的状态
+#[derive(Debug, Clone)]
+enum BatchStatus {
+    Idle,
+    Running { done: usize, total: usize },
+    Finished(BatchReport),
+    Error(String),
+}
+
+/// 批处理范围
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum BatchScope {
+    /// 整个列表（或搜索结果）
+    All,
+    /// 只处理当前图片，使用其正在编辑的配置
+    Current,
+}
+
+/// 后台解码完成的当前图片
+struct DecodedImage {
+    /// 发起解码时的请求编号，不是最近一次请求的结果直接丢弃
+    request: u64,
+    result: Result<DecodedPreview, String>,
+}
+
+/// 已按配置校正方向的预览图片及上传纹理用的像素
+struct DecodedPreview {
+    image: image::DynamicImage,
+    pixels: egui::ColorImage,
+    orientation: Orientation,
+    cmyk_converted: bool,
+}
+
+/// 等待后台解码的当前图片
+struct DecodeJob {
+    request: u64,
+    path: PathBuf,
+    config: SplitConfig,
+    ctx: egui::Context,
+}
+
+/// 当前图片的后台解码：一个常驻线程按顺序处理请求
+///
+/// 开始解码前已有更新请求的任务直接丢弃，快速连续切换图片时最多只有一张过期图片在解码。
+struct ImageLoader {
+    jobs: std::sync::mpsc::Sender<DecodeJob>,
+    results: std::sync::mpsc::Receiver<DecodedImage>,
+    /// 最近一次请求的编号
+    latest: Arc<std::sync::atomic::AtomicU64>,
+    /// 解码线程未能启动的原因
+    spawn_error: Option<String>,
+}
+
+impl Default for ImageLoader {
+    fn default() -> Self {
+        let (jobs, job_receiver) = std::sync::mpsc::channel::<DecodeJob>();
+        let (result_sender, results) = std::sync::mpsc::channel();
+        let latest = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let worker_latest = Arc::clone(&latest);
+        let spawned = std::thread::Builder::new().name("image-decoder".to_string()).spawn(move || {
+            // 界面关闭、发送端释放后退出
+            while let Ok(job) = job_receiver.recv() {
+                if job.request != worker_latest.load(Ordering::Acquire) {
+                    continue;
+                }
+                let result = decode_preview(&job.path, &job.config);
+                if result_sender.send(DecodedImage { request: job.request, result }).is_err() {
+                    break;
+                }
+                job.ctx.request_repaint();
+            }
+        });
+        let spawn_error = spawned.err().map(|e| format!("无法启动图片解码线程: {}", e));
+        Self { jobs, results, latest, spawn_error }
+    }
+}
+
+impl ImageLoader {
+    /// 请求在后台解码图片，返回请求编号；之前尚未开始的请求随之作废。解码线程不可用时返回原因
+    fn request(&self, ctx: &egui::Context, path: &std::path::Path, config: SplitConfig) -> Result<u64, String> {
+        let request = self.latest.fetch_add(1, Ordering::AcqRel) + 1;
+        let job = DecodeJob { request, path: path.to_path_buf(), config, ctx: ctx.clone() };
+        self.jobs.send(job).map(|()| request).map_err(|_| {
+            self.spawn_error.clone().unwrap_or_else(|| "图片解码线程不可用".to_string())
+        })
+    }
+
+    /// 取出编号为 `request` 的解码结果，其余（过期的）结果被丢弃
+    fn finished(&self, request: Option<u64>) -> Option<DecodedImage> {
+        self.results.try_iter().filter(|decoded| Some(decoded.request) == request).last()
+    }
+}
+
+/// 解码图片并按配置校正方向、旋转和翻转，同时准备上传纹理用的像素
+fn decode_preview(path: &std::path::Path, config: &SplitConfig) -> Result<DecodedPreview, String> {
+    let loaded = ImageSplitter::open_image_detailed(path).map_err(|e| e.to_string())?;
+    let mut img = loaded.image;
+    if config.apply_exif_orientation {
+        img.apply_orientation(loaded.orientation);
+    }
+    let img = config.transform_image(img);
+    let size = [img.width() as usize, img.height() as usize];
+    let pixels = egui::ColorImage::from_rgba_unmultiplied(size, img.to_rgba8().as_raw());
+    Ok(DecodedPreview { image: img, pixels, orientation: loaded.orientation, cmyk_converted: loaded.cmyk_converted })
+}
+
 #[derive(Deserialize, Debug)]
 struct GithubRelease {
     tag_name: String,
@@ -31,38 +144,100 @@ pub struct BatchImageSplitterApp {
     // 图片列表
     image_paths: Vec<PathBuf>,
     current_index: usize,
+    // 图片列表搜索框（只筛选显示，不修改 image_paths）
+    search_query: String,
+    // 批量处理时只处理搜索筛选出的图片
+    batch_only_filtered: bool,
     
     // 当前显示的图片
     current_texture: Option<egui::TextureHandle>,
     current_image: Option<image::DynamicImage>,
+    // 当前图片 EXIF 中记录的方向，非正常方向时在预览中提示
+    current_orientation: Orientation,
+    // 正在后台解码的当前图片 (请求编号, 路径)，完成前预览显示缩略图
+    loading_image: Option<(u64, PathBuf)>,
+    image_loader: ImageLoader,
     
     // 分割配置
     config: SplitConfig,
     saved_config: Option<SplitConfig>,
+    // 自动网格的目标分块数
+    auto_grid_target: usize,
     
     // 每张图片的独立配置覆盖 (索引 -> 配置)
     config_overrides: std::collections::HashMap<usize, SplitConfig>,
+    // 上次保存时的独立配置快照，用于判断是否有未保存的修改
+    saved_overrides: std::collections::HashMap<usize, SplitConfig>,
+    // 独立配置旁车文件的写入状态
+    sidecars: SidecarSync,
     
-    // 缩略图缓存
-    thumbnails: std::collections::HashMap<usize, egui::TextureHandle>,
+    // 缩略图缓存，超出设置中的预算时淘汰最久未显示的
+    thumbnails: ThumbnailTextures,
+    // 在后台生成尚未加载的缩略图
+    thumbnail_loader: ThumbnailLoader,
+    // 本次运行累计淘汰的缩略图数量，显示在性能面板中
+    evicted_thumbnails: usize,
     
     // 交互状态
     selected_lines: Vec<(LineType, usize)>, // (类型, 索引)
+    // Ctrl+点击选中的分块 (行, 列)，用于合并
+    selected_cells: Vec<(usize, usize)>,
     dragging_line: Option<(LineType, usize)>,
+    // 拖动中的分割线当前吸附在步长的整数倍上
+    drag_snapped: bool,
+    // 开始拖动分割线前的配置快照，松开后记入撤销记录
+    drag_snapshot: Option<LineSnapshot>,
+    // 分割线修改的撤销 / 重做记录
+    undo_stack: Vec<LineSnapshot>,
+    redo_stack: Vec<LineSnapshot>,
+    // 右键菜单对应的分块 (行, 列)
+    context_cell: Option<(usize, usize)>,
+    // 按百分比添加分割线的输入框内容
+    h_line_input: String,
+    v_line_input: String,
+    // 原始分辨率的单块预览 (窗口标题, 纹理)
+    tile_preview: Option<(String, egui::TextureHandle)>,
     is_selecting: bool,
     selection_start: Option<egui::Pos2>,
     selection_end: Option<egui::Pos2>,
+    // 松开框选后待确认的裁剪区域 (归一化坐标)
+    pending_crop: Option<egui::Rect>,
+    // 拖动裁剪区域角点时固定的对角 (归一化坐标)
+    crop_drag_anchor: Option<egui::Pos2>,
+    // 吸管模式：单击预览图吸取背景参考色
+    eyedropper_active: bool,
+    // 以带间隙的方式预览分割结果（仅用于查看，不改变分割线）
+    show_gapped_preview: bool,
+    // 性能面板及最近若干帧的耗时（秒），只在面板打开时记录
+    show_debug_hud: bool,
+    frame_times: std::collections::VecDeque<f32>,
     
     // 图片显示区域
     image_rect: Option<egui::Rect>,
     // 图片实际显示尺寸（用于坐标转换）
     image_display_scale: f32,
+    // 预览缩放倍数（1 为适应窗口）及图片中心相对预览区中心的平移
+    zoom: f32,
+    pan_offset: egui::Vec2,
     
     // 状态信息
     status_message: String,
+    // 批处理进行中时显示进度条，进度为 0-1
     show_progress: bool,
     progress: f32,
     
+    // 偏好设置
+    settings: AppSettings,
+    show_settings: bool,
+    // 正在录入新快捷键的操作
+    capturing_binding: Option<Action>,
+    // 快捷键录入的冲突提示
+    binding_message: Option<String>,
+    // 等待确认移除的图片索引
+    pending_remove: Option<usize>,
+    // 有未保存的修改时，打开项目前等待确认
+    confirm_open_project: bool,
+    
     // 关于窗口
     show_about: bool,
     about_icon: Option<egui::TextureHandle>,
@@ -72,10 +247,107 @@ pub struct BatchImageSplitterApp {
     obfuscated_repo_label: String,
     obfuscated_repo_url: String,
     
+    // 已扫描的输入文件夹
+    input_dirs: Vec<PathBuf>,
+    // 输出目录位于输入目录内时等待确认 (输出目录, 输入目录, 处理范围)
+    output_conflict: Option<(PathBuf, PathBuf, BatchScope)>,
+    // 正在打开的文件对话框
+    pending_dialog: Option<PendingDialog>,
+    // 新选择的图片替换当前列表（否则追加）
+    replace_on_add: bool,
+    // 点击「清除」前的图片列表，在提示条消失前可撤销一次
+    cleared_images: Option<ClearedImages>,
+    
+    // 批处理前的确认信息（预估输出）
+    batch_confirm: Option<BatchPlan>,
+    // 后台批处理的进度与结果
+    batch_status: Arc<Mutex<BatchStatus>>,
+    // 置位后批处理不再开始新的图片
+    batch_cancel: Arc<AtomicBool>,
+    
+    // 配置校验结果 (配置名称, 问题列表)
+    validation_report: Option<Vec<(String, Vec<String>)>>,
+    
+    // 复制分块图像用的剪贴板，保持存活以便其他程序读取
+    clipboard: Option<arboard::Clipboard>,
+    // 复制链接的反馈信息
+    copied_link: Option<String>,
+    
     // 更新状态
     update_status: Arc<Mutex<UpdateStatus>>,
 }
 
+/// 「清除」前的图片列表状态
+struct ClearedImages {
+    image_paths: Vec<PathBuf>,
+    input_dirs: Vec<PathBuf>,
+    config_overrides: std::collections::HashMap<usize, SplitConfig>,
+    saved_overrides: std::collections::HashMap<usize, SplitConfig>,
+    current_index: usize,
+    cleared_at: std::time::Instant,
+}
+
+/// 分割线修改前的配置快照，用于撤销 / 重做
+///
+/// `index` 为 `None` 时对应全局配置，否则对应该图片的独立配置；`config` 为 `None` 表示当时该图片没有独立配置。
+struct LineSnapshot {
+    index: Option<usize>,
+    config: Option<SplitConfig>,
+}
+
+/// 项目文件：图片列表、全局配置及每张图片的独立配置
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct Project {
+    config: SplitConfig,
+    saved_config: Option<SplitConfig>,
+    images: Vec<ProjectImage>,
+}
+
+/// 项目中的一张图片，路径保存为绝对路径
+#[derive(Serialize, Deserialize, Debug)]
+struct ProjectImage {
+    path: PathBuf,
+    #[serde(default)]
+    config: Option<SplitConfig>,
+}
+
+/// 分割线撤销记录的最大条数
+const UNDO_DEPTH: usize = 50;
+
+/// 递归扫描文件夹时进入子文件夹的最大层数
+const MAX_SCAN_DEPTH: usize = 32;
+
+/// 清除图片列表后撤销提示的显示时长
+const UNDO_CLEAR_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(8);
+
+/// 抓取分割线的命中距离（图片像素），显示时按缩放比例换算
+const LINE_GRAB_IMAGE_PX: f32 = 5.0;
+/// 换算后命中距离的下限和上限（屏幕像素）
+const LINE_GRAB_MIN_PX: f32 = 3.0;
+const LINE_GRAB_MAX_PX: f32 = 8.0;
+
+/// 预览的缩放范围（相对适应窗口）
+const MIN_PREVIEW_ZOOM: f32 = 0.1;
+const MAX_PREVIEW_ZOOM: f32 = 8.0;
+/// 按钮和快捷键每次缩放的倍率
+const PREVIEW_ZOOM_STEP: f32 = 1.25;
+
+/// 透明背景棋盘格每格对应的图片像素，显示时按缩放比例换算
+const CHECKER_IMAGE_PX: f32 = 8.0;
+/// 换算后棋盘格边长的下限和上限（屏幕像素）
+const CHECKER_MIN_PX: f32 = 6.0;
+const CHECKER_MAX_PX: f32 = 24.0;
+
+/// 在尺子上右键删除分割线时的命中距离（屏幕像素）
+const RULER_REMOVE_TOLERANCE: f32 = 10.0;
+
+/// 分块数超过该值时在设置卡片中标红提醒
+const CELL_COUNT_WARNING: usize = 256;
+
+/// 性能面板统计帧率所用的帧数
+const HUD_FRAME_SAMPLES: usize = 60;
+
 // 简单的 XOR 混淆/解密函数
 fn xor_cipher(data: &[u8], key: u8) -> String {
     let xored: Vec<u8> = data.iter().map(|&b| b ^ key).collect();
@@ -102,110 +374,358 @@ const REPO_URL: &[u8] = &[
 ];
 
 impl BatchImageSplitterApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // 在初始化时解密
         let info1 = xor_cipher(INFO_PART1, 0x5A);
         let info2 = xor_cipher(INFO_PART2, 0x5A);
         let repo_label = xor_cipher(REPO_LABEL, 0x5A);
         let repo_url = xor_cipher(REPO_URL, 0x5A);
+
+        let settings = AppSettings::load(cc.storage);
+        let mut config = SplitConfig::new(1, 1);
+        thumbnail_cache::prune_in_background();
+        settings.output.apply_to(&mut config);
         
         Self {
             image_paths: Vec::new(),
             current_index: 0,
             current_texture: None,
             current_image: None,
-            config: SplitConfig::new(1, 1),
-            saved_config: None,
+            current_orientation: Orientation::NoTransforms,
+            loading_image: None,
+            image_loader: ImageLoader::default(),
+            // 启动时的配置视为已保存，避免尚未编辑就提示有未保存的修改
+            saved_config: Some(config.clone()),
+            config,
+            auto_grid_target: 12,
             config_overrides: std::collections::HashMap::new(),
-            thumbnails: std::collections::HashMap::new(),
+            saved_overrides: std::collections::HashMap::new(),
+            sidecars: SidecarSync::default(),
+            thumbnails: ThumbnailTextures::default(),
+            thumbnail_loader: ThumbnailLoader::default(),
+            evicted_thumbnails: 0,
             selected_lines: Vec::new(),
+            selected_cells: Vec::new(),
             dragging_line: None,
+            drag_snapped: false,
+            drag_snapshot: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            context_cell: None,
+            h_line_input: String::new(),
+            v_line_input: String::new(),
+            tile_preview: None,
             is_selecting: false,
             selection_start: None,
             selection_end: None,
+            pending_crop: None,
+            crop_drag_anchor: None,
+            eyedropper_active: false,
+            show_gapped_preview: false,
+            show_debug_hud: false,
+            frame_times: std::collections::VecDeque::new(),
             image_rect: None,
             image_display_scale: 1.0,
+            zoom: 1.0,
+            pan_offset: egui::Vec2::ZERO,
             status_message: "请选择图片文件".to_string(),
             show_progress: false,
             progress: 0.0,
+            settings,
+            show_settings: false,
+            capturing_binding: None,
+            binding_message: None,
+            pending_remove: None,
+            confirm_open_project: false,
             show_about: false,
             about_icon: None,
             obfuscated_info_label: info1,
             obfuscated_info_url: info2,
             obfuscated_repo_label: repo_label,
             obfuscated_repo_url: repo_url,
+            input_dirs: Vec::new(),
+            output_conflict: None,
+            pending_dialog: None,
+            replace_on_add: false,
+            cleared_images: None,
+            search_query: String::new(),
+            batch_only_filtered: false,
+            batch_confirm: None,
+            batch_status: Arc::new(Mutex::new(BatchStatus::Idle)),
+            batch_cancel: Arc::new(AtomicBool::new(false)),
+            validation_report: None,
+            clipboard: None,
+            copied_link: None,
             update_status: Arc::new(Mutex::new(UpdateStatus::Idle)),
         }
     }
 
-    fn add_line(&mut self, line_type: LineType, pos: f32) {
-        // 如果当前图片有独立配置，则修改独立配置；否则修改全局配置
-        if let Some(config) = self.config_overrides.get_mut(&self.current_index) {
-            match line_type {
-                LineType::Horizontal => {
-                    config.h_lines.push(pos);
-                    config.h_lines.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                    config.rows = config.h_lines.len() + 1;
-                    if let Some(idx) = config.h_lines.iter().position(|&p| p == pos) {
-                        self.selected_lines.clear();
-                        self.selected_lines.push((LineType::Horizontal, idx));
-                    }
-                }
-                LineType::Vertical => {
-                    config.v_lines.push(pos);
-                    config.v_lines.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                    config.cols = config.v_lines.len() + 1;
-                    if let Some(idx) = config.v_lines.iter().position(|&p| p == pos) {
-                        self.selected_lines.clear();
-                        self.selected_lines.push((LineType::Vertical, idx));
-                    }
-                }
-            }
+    /// 当前正在编辑的配置：有独立配置时为独立配置，否则为全局配置
+    fn active_config_mut(&mut self) -> &mut SplitConfig {
+        match self.config_overrides.get_mut(&self.current_index) {
+            Some(config) => config,
+            None => &mut self.config,
+        }
+    }
+
+    /// 没有独立配置的图片批处理时共用的分割线配置：已保存的全局配置，未保存过时为当前全局配置
+    fn shared_config(&self) -> &SplitConfig {
+        self.saved_config.as_ref().unwrap_or(&self.config)
+    }
+
+    /// 第 `idx` 张图片批处理时实际使用的分割线配置
+    ///
+    /// 缩略图上的分割线、「已调 / 共享」标记和批处理都以此为准，保证三者一致。
+    fn effective_config(&self, idx: usize) -> &SplitConfig {
+        self.config_overrides.get(&idx).unwrap_or_else(|| self.shared_config())
+    }
+
+    /// 当前图片的独立配置已锁定
+    fn current_locked(&self) -> bool {
+        self.config_overrides.get(&self.current_index).is_some_and(|c| c.locked)
+    }
+
+    /// 修改分割线前调用：已锁定时提示并返回 `false`
+    fn ensure_unlocked(&mut self) -> bool {
+        if self.current_locked() {
+            self.status_message = format!("{} 当前图片已锁定，解锁后才能修改分割线", icon::LOCK);
+            return false;
+        }
+        true
+    }
+
+    /// 锁定或解锁当前图片；锁定时若没有独立配置则从全局配置复制一份，之后修改全局配置也不影响它
+    fn toggle_current_lock(&mut self) {
+        if self.image_paths.is_empty() {
+            return;
+        }
+        let config = self.config_overrides.entry(self.current_index)
+            .or_insert_with(|| self.config.clone());
+        config.locked = !config.locked;
+        self.status_message = if config.locked {
+            "已锁定当前图片的分割线".to_string()
         } else {
-            // 修改全局配置
-            match line_type {
-                LineType::Horizontal => {
-                    self.config.h_lines.push(pos);
-                    self.config.h_lines.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                    self.config.rows = self.config.h_lines.len() + 1;
-                    if let Some(idx) = self.config.h_lines.iter().position(|&p| p == pos) {
-                        self.selected_lines.clear();
-                        self.selected_lines.push((LineType::Horizontal, idx));
-                    }
-                }
-                LineType::Vertical => {
-                    self.config.v_lines.push(pos);
-                    self.config.v_lines.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                    self.config.cols = self.config.v_lines.len() + 1;
-                    if let Some(idx) = self.config.v_lines.iter().position(|&p| p == pos) {
-                        self.selected_lines.clear();
-                        self.selected_lines.push((LineType::Vertical, idx));
-                    }
+            "已解锁当前图片".to_string()
+        };
+        if config.locked {
+            self.dragging_line = None;
+        }
+    }
+
+    /// 当前图片正在使用的配置（独立配置或全局配置）的快照
+    fn active_snapshot(&self) -> LineSnapshot {
+        match self.config_overrides.get(&self.current_index) {
+            Some(config) => LineSnapshot { index: Some(self.current_index), config: Some(config.clone()) },
+            None => LineSnapshot { index: None, config: Some(self.config.clone()) },
+        }
+    }
+
+    /// 总是写入当前图片独立配置的修改（如导入分割线）使用的快照，撤销时可还原为没有独立配置
+    fn override_snapshot(&self) -> LineSnapshot {
+        LineSnapshot {
+            index: Some(self.current_index),
+            config: self.config_overrides.get(&self.current_index).cloned(),
+        }
+    }
+
+    /// 快照对应的配置已被修改时记入撤销记录并清空重做记录，超过上限时丢弃最早的记录
+    fn commit_undo(&mut self, before: LineSnapshot) {
+        let current = match before.index {
+            Some(index) => self.config_overrides.get(&index),
+            None => Some(&self.config),
+        };
+        if current == before.config.as_ref() {
+            return;
+        }
+        self.undo_stack.push(before);
+        if self.undo_stack.len() > UNDO_DEPTH {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    /// 把快照写回对应的配置，返回写回前的状态；全局配置的输出选项保持当前值
+    fn restore_snapshot(&mut self, snapshot: LineSnapshot) -> LineSnapshot {
+        match snapshot.index {
+            Some(index) => {
+                let current = match snapshot.config {
+                    Some(config) => self.config_overrides.insert(index, config),
+                    None => self.config_overrides.remove(&index),
+                };
+                LineSnapshot { index: Some(index), config: current }
+            }
+            None => {
+                let current = self.config.clone();
+                if let Some(mut config) = snapshot.config {
+                    config.copy_output_options_from(&current);
+                    self.config = config;
                 }
+                LineSnapshot { index: None, config: Some(current) }
+            }
+        }
+    }
+
+    /// 撤销（`undo` 为 false 时重做）一次分割线修改；修改属于其他图片时切换到该图片
+    fn step_line_history(&mut self, ctx: &egui::Context, undo: bool) {
+        let action = if undo { "撤销" } else { "重做" };
+        let Some(snapshot) = (if undo { self.undo_stack.pop() } else { self.redo_stack.pop() }) else {
+            self.status_message = format!("没有可{}的分割线修改", action);
+            return;
+        };
+        let index = snapshot.index;
+        if index.and_then(|i| self.config_overrides.get(&i)).is_some_and(|c| c.locked) {
+            self.status_message = format!("{} 该图片已锁定，解锁后才能{}", icon::LOCK, action);
+            if undo { self.undo_stack.push(snapshot) } else { self.redo_stack.push(snapshot) }
+            return;
+        }
+        // 撤销整图旋转、翻转时需按新的方向重新加载图片
+        let target = index.unwrap_or(self.current_index);
+        let transform = |app: &Self| {
+            let c = app.config_overrides.get(&target).unwrap_or(&app.config);
+            (c.apply_exif_orientation, c.image_rotation, c.image_flip_h, c.image_flip_v)
+        };
+        let old_transform = transform(self);
+        let reverse = self.restore_snapshot(snapshot);
+        if undo { self.redo_stack.push(reverse) } else { self.undo_stack.push(reverse) }
+        self.selected_lines.clear();
+        self.selected_cells.clear();
+        self.dragging_line = None;
+        let transformed = transform(self) != old_transform;
+        if transformed {
+            self.forget_thumbnail(target);
+            self.pending_crop = None;
+        }
+        if target != self.current_index || transformed {
+            if let Some(path) = self.image_paths.get(target).cloned() {
+                self.current_index = target;
+                self.load_image(ctx, &path);
             }
         }
+        self.status_message = format!("已{}分割线修改", action);
+    }
+
+    fn add_line(&mut self, line_type: LineType, pos: f32) {
+        if !self.ensure_unlocked() {
+            return;
+        }
+        let before = self.active_snapshot();
+        // 如果当前图片有独立配置，则修改独立配置；否则修改全局配置
+        let horizontal = line_type == LineType::Horizontal;
+        let config = self.active_config_mut();
+        if horizontal {
+            config.h_lines.push(pos);
+        } else {
+            config.v_lines.push(pos);
+        }
+        // 新线被限制到有效范围内，与已有的线重叠时被合并掉，因此选中离点击位置最近的线
+        config.normalize();
+        let lines = if horizontal { &config.h_lines } else { &config.v_lines };
+        let nearest = lines
+            .iter()
+            .enumerate()
+            .min_by(|a, b| (a.1 - pos).abs().total_cmp(&(b.1 - pos).abs()))
+            .map(|(idx, _)| idx);
+        if let Some(idx) = nearest {
+            self.selected_lines.clear();
+            self.selected_lines.push((line_type, idx));
+        }
+        self.commit_undo(before);
+    }
+
+    /// 按输入框中的百分比 (0-100) 添加分割线，超出范围或与已有的线重叠时在状态栏提示
+    fn add_line_at_percent(&mut self, line_type: LineType) {
+        let horizontal = line_type == LineType::Horizontal;
+        let name = if horizontal { "水平" } else { "垂直" };
+        let input = if horizontal { &self.h_line_input } else { &self.v_line_input };
+        let Ok(percent) = input.trim().trim_end_matches('%').trim_end().parse::<f32>() else {
+            self.status_message = format!("{}分割线位置无效: 请输入 0 到 100 之间的数字", name);
+            return;
+        };
+        if !(percent > 0.0 && percent < 100.0) {
+            self.status_message = format!("{}分割线位置 {}% 超出范围，应大于 0 且小于 100", name, percent);
+            return;
+        }
+        let pos = percent / 100.0;
+        let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+        let lines = if horizontal { &active.h_lines } else { &active.v_lines };
+        if let Some(existing) = lines.iter().find(|&&p| (p - pos).abs() < LINE_EPSILON) {
+            self.status_message = format!("已存在位于 {:.2}% 的{}分割线", existing * 100.0, name);
+            return;
+        }
+        let before = lines.len();
+        self.add_line(line_type, pos);
+        let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+        let after = if horizontal { active.h_lines.len() } else { active.v_lines.len() };
+        if after > before {
+            self.status_message = format!("已添加{}分割线: {}%", name, percent);
+        }
+        // 焦点仍在输入框附近，不选中新线，以免随后的 Delete 和方向键作用到它
+        self.selected_lines.clear();
     }
 
+    /// 删除距 `pos` 最近且在 `tolerance` 以内的分割线（均为相对位置），用于尺子右键
+    fn remove_nearest_line(&mut self, line_type: LineType, pos: f32, tolerance: f32) {
+        if !self.ensure_unlocked() {
+            return;
+        }
+        let horizontal = line_type == LineType::Horizontal;
+        let before = self.active_snapshot();
+        let config = self.active_config_mut();
+        let lines = if horizontal { &config.h_lines } else { &config.v_lines };
+        let nearest = lines
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| (i, (p - pos).abs()))
+            .filter(|&(_, distance)| distance <= tolerance)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        let Some((idx, _)) = nearest else {
+            return;
+        };
+        config.remove_line(horizontal, idx);
+        config.rows = config.h_lines.len() + 1;
+        config.cols = config.v_lines.len() + 1;
+        self.commit_undo(before);
+        self.selected_lines.clear();
+        self.status_message = format!("已删除{}分割线 {}", if horizontal { "水平" } else { "垂直" }, idx + 1);
+    }
+
+    /// 以预览区中心为基准按固定倍率缩放预览，并在状态栏显示缩放比例
+    fn step_zoom(&mut self, zoom_in: bool) {
+        let zoom = if zoom_in { self.zoom * PREVIEW_ZOOM_STEP } else { self.zoom / PREVIEW_ZOOM_STEP };
+        let zoom = zoom.clamp(MIN_PREVIEW_ZOOM, MAX_PREVIEW_ZOOM);
+        self.pan_offset *= zoom / self.zoom;
+        self.zoom = zoom;
+        self.status_message = format!("缩放: {:.0}%", zoom * 100.0);
+    }
+
+    /// 绘制尺子，`rect` 对应分割线的整个 0-100% 范围，放大时只显示落在 `view` 范围内的部分
     fn draw_ruler(
         &self,
         ui: &mut egui::Ui,
         rect: egui::Rect,
+        view: egui::Rect,
         vertical: bool,
     ) -> egui::Response {
-        let response = ui.interact(rect, ui.id().with(if vertical { "left_ruler" } else { "top_ruler" }), egui::Sense::click());
+        let visible = if vertical {
+            egui::Rect::from_x_y_ranges(rect.x_range(), rect.top().max(view.top())..=rect.bottom().min(view.bottom()))
+        } else {
+            egui::Rect::from_x_y_ranges(rect.left().max(view.left())..=rect.right().min(view.right()), rect.y_range())
+        };
+        let response = ui.interact(visible, ui.id().with(if vertical { "left_ruler" } else { "top_ruler" }), egui::Sense::click())
+            .on_hover_text("左键添加分割线，右键删除最近的分割线");
         
-        let painter = ui.painter();
+        let painter = ui.painter().with_clip_rect(visible);
         
         // 绘制背景
         painter.rect_filled(
-            rect,
+            visible,
             2.0,
             egui::Color32::from_rgb(229, 231, 235), // Gray 200
         );
         
         // 绘制边框
         painter.rect_stroke(
-            rect,
+            visible,
             2.0,
             egui::Stroke::new(1.0, egui::Color32::from_rgb(209, 213, 219)), // Gray 300
         );
@@ -262,7 +782,7 @@ impl BatchImageSplitterApp {
         
         // 鼠标悬停时的指示器
         if let Some(pos) = ui.ctx().pointer_latest_pos() {
-            if rect.contains(pos) {
+            if visible.contains(pos) {
                 let color = egui::Color32::from_rgb(19, 78, 74).linear_multiply(0.5);
                 if vertical {
                     painter.line_segment(
@@ -335,30 +855,182 @@ impl BatchImageSplitterApp {
         }
     }
 
-    fn load_image(&mut self, ctx: &egui::Context, path: &PathBuf) {
-        match ImageSplitter::open_image(path) {
-            Ok(img) => {
-                let size = [img.width() as usize, img.height() as usize];
-                let rgba = img.to_rgba8();
-                let pixels = rgba.as_raw();
-                
-                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, pixels);
-                let texture = ctx.load_texture(
-                    "current_image",
-                    color_image,
-                    egui::TextureOptions::default(),
-                );
-                
-                self.current_texture = Some(texture);
-                self.current_image = Some(img);
-                self.status_message = format!("已加载: {}", path.file_name().unwrap_or_default().to_string_lossy());
+    /// 切换到图片并在后台解码原图
+    ///
+    /// 解码完成前预览显示该图片的缩略图（还没有缩略图时显示同宽高比的占位），分割线照常可见；
+    /// 完成后由 [`Self::poll_image_load`] 换上原图。期间又切换了图片时，先前的结果被丢弃。
+    fn load_image(&mut self, ctx: &egui::Context, path: &std::path::Path) {
+        let config = self.config_overrides.get(&self.current_index).unwrap_or(&self.config).clone();
+        // 选中的是线的下标：开启保留选择且新图片有这些线时沿用，便于在一组图片上微调同一条线
+        let compatible = self.selected_lines.iter().all(|&(line_type, idx)| match line_type {
+            LineType::Horizontal => idx < config.h_lines.len(),
+            LineType::Vertical => idx < config.v_lines.len(),
+        });
+        if !(self.settings.keep_selection_on_switch && compatible) {
+            self.selected_lines.clear();
+        }
+        self.selected_cells.clear();
+        self.pending_crop = None;
+        self.current_image = None;
+        self.current_orientation = Orientation::NoTransforms;
+        self.current_texture = self.thumbnails.get(self.current_index).cloned().or_else(|| {
+            ImageSplitter::oriented_dimensions(path, &config).map(|size| placeholder_texture(ctx, size))
+        });
+
+        match self.image_loader.request(ctx, path, config) {
+            Ok(request) => {
+                self.loading_image = Some((request, path.to_path_buf()));
+                self.status_message = loading_message(path);
+            }
+            Err(e) => {
+                self.loading_image = None;
+                self.status_message = format!("加载失败: {}", e);
+            }
+        }
+    }
+
+    /// 取回后台解码的当前图片，只接受最近一次请求的结果
+    ///
+    /// 状态栏仍是「正在加载」时换成加载结果，期间已显示其他消息时保留该消息，加载失败总是提示。
+    fn poll_image_load(&mut self, ctx: &egui::Context) {
+        let request = self.loading_image.as_ref().map(|(request, _)| *request);
+        // 没有进行中的请求时也取一次，丢弃残留结果
+        let Some(decoded) = self.image_loader.finished(request) else {
+            return;
+        };
+        let Some((_, path)) = self.loading_image.take() else {
+            return;
+        };
+        let loading = loading_message(&path);
+        match decoded.result {
+            Ok(preview) => {
+                let config = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+                let mut message = format!("已加载: {}", path.file_name().unwrap_or_default().to_string_lossy());
+                if preview.cmyk_converted {
+                    message.push_str(" (CMYK 图片已转换为 RGB)");
+                }
+                if !ImageSplitter::fits_grid(preview.image.width(), preview.image.height(), config) {
+                    message.push_str(" (图片过小，部分分割线将被合并)");
+                }
+                self.status_message = self.status_message.replace(&loading, &message);
+                self.current_orientation = preview.orientation;
+                self.current_texture = Some(ctx.load_texture("current_image", preview.pixels, egui::TextureOptions::default()));
+                self.current_image = Some(preview.image);
             }
             Err(e) => {
+                self.current_texture = None;
                 self.status_message = format!("加载失败: {}", e);
             }
         }
     }
 
+    /// 预览区的 EXIF 方向提示：默认按 EXIF 校正，可对当前图片改用原始像素，结果记在其独立配置中
+    fn draw_orientation_hint(&mut self, ui: &mut egui::Ui, applied: bool) {
+        ui.horizontal(|ui| {
+            let label = orientation_label(self.current_orientation);
+            let clicked = if applied {
+                ui.label(egui::RichText::new(format!("{} 已按 EXIF 校正 ({})", icon::ROTATE_RIGHT, label))
+                    .color(egui::Color32::from_rgb(19, 78, 74)));
+                ui.small_button("使用原始像素")
+                    .on_hover_text("不按 EXIF 方向旋转，按文件中的原始像素分割，仅对当前图片生效")
+                    .clicked()
+            } else {
+                ui.label(egui::RichText::new(format!("{} EXIF 方向: {}", icon::ROTATE_RIGHT, label))
+                    .color(egui::Color32::from_rgb(180, 83, 9)));
+                ui.small_button("按 EXIF 旋转")
+                    .on_hover_text("分割前按 EXIF 方向旋转图片，仅对当前图片生效")
+                    .clicked()
+            };
+            if clicked && self.ensure_unlocked() {
+                let config = self.config_overrides.entry(self.current_index)
+                    .or_insert_with(|| self.config.clone());
+                config.apply_exif_orientation = !applied;
+                self.forget_thumbnail(self.current_index);
+                if let Some(path) = self.image_paths.get(self.current_index).cloned() {
+                    self.load_image(ui.ctx(), &path);
+                }
+                self.status_message = if applied {
+                    "已恢复原始像素方向".to_string()
+                } else {
+                    format!("已按 EXIF 方向校正: {}", label)
+                };
+            }
+        });
+    }
+
+    /// 整图旋转或翻转后，按行列记录的分块旋转与合并不再对应原来的画面内容，一并清除
+    ///
+    /// 返回是否有设置被清除。
+    fn clear_cell_settings(config: &mut SplitConfig) -> bool {
+        let had = !config.cell_rotations.is_empty() || !config.cell_merges.is_empty();
+        config.cell_rotations.clear();
+        config.cell_merges.clear();
+        had
+    }
+
+    /// 将当前图片旋转 90°，结果记在其独立配置中，批处理按同样的方向分割
+    fn rotate_current_image(&mut self, ctx: &egui::Context, clockwise: bool) {
+        if self.current_image.is_none() || !self.ensure_unlocked() {
+            return;
+        }
+        let before = self.override_snapshot();
+        let config = self.config_overrides.entry(self.current_index)
+            .or_insert_with(|| self.config.clone());
+        config.rotate_image(clockwise);
+        let degrees = config.image_rotation.map_or(0, Rotation::degrees);
+        let cleared = Self::clear_cell_settings(config);
+        self.commit_undo(before);
+        self.selected_cells.clear();
+        self.forget_thumbnail(self.current_index);
+        if let Some(img) = self.current_image.take() {
+            let img = if clockwise { img.rotate90() } else { img.rotate270() };
+            self.current_texture = Some(current_image_texture(ctx, &img));
+            self.current_image = Some(img);
+        }
+        self.pending_crop = None;
+        self.pan_offset = egui::Vec2::ZERO;
+        self.status_message = if degrees == 0 {
+            "已恢复原始方向".to_string()
+        } else {
+            format!("当前图片已顺时针旋转 {}°，仅对当前图片生效", degrees)
+        };
+        if cleared {
+            self.status_message.push_str("，已清除分块旋转与合并");
+        }
+    }
+
+    /// 左右或上下翻转当前图片，结果记在其独立配置中，批处理按同样的方向分割
+    fn flip_current_image(&mut self, ctx: &egui::Context, horizontal: bool) {
+        if self.current_image.is_none() || !self.ensure_unlocked() {
+            return;
+        }
+        let before = self.override_snapshot();
+        let config = self.config_overrides.entry(self.current_index)
+            .or_insert_with(|| self.config.clone());
+        let flipped = if horizontal { &mut config.image_flip_h } else { &mut config.image_flip_v };
+        *flipped = !*flipped;
+        let flipped = *flipped;
+        let cleared = Self::clear_cell_settings(config);
+        self.commit_undo(before);
+        self.selected_cells.clear();
+        self.forget_thumbnail(self.current_index);
+        if let Some(img) = self.current_image.take() {
+            let img = if horizontal { img.fliph() } else { img.flipv() };
+            self.current_texture = Some(current_image_texture(ctx, &img));
+            self.current_image = Some(img);
+        }
+        self.pending_crop = None;
+        let axis = if horizontal { "左右" } else { "上下" };
+        self.status_message = if flipped {
+            format!("当前图片已{}翻转，仅对当前图片生效", axis)
+        } else {
+            format!("已取消{}翻转", axis)
+        };
+        if cleared {
+            self.status_message.push_str("，已清除分块旋转与合并");
+        }
+    }
+
     fn show_previous_image(&mut self, ctx: &egui::Context) {
         if self.current_index > 0 {
             self.current_index -= 1;
@@ -379,56 +1051,1207 @@ impl BatchImageSplitterApp {
         }
     }
 
-    fn save_config(&mut self) {
-        self.saved_config = Some(self.config.clone());
-        self.status_message = format!("已保存: {}行 x {}列", self.config.rows, self.config.cols);
-    }
+    /// 从列表中移除一张图片，同步调整独立配置、缩略图和当前索引
+    fn remove_image(&mut self, ctx: &egui::Context, idx: usize) {
+        if idx >= self.image_paths.len() {
+            return;
+        }
+        let removed = self.image_paths.remove(idx);
+
+        self.remap_indices(|i| match i.cmp(&idx) {
+            std::cmp::Ordering::Less => Some(i),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(i - 1),
+        });
+        self.selected_lines.clear();
 
-    fn start_batch_process(&mut self) {
         if self.image_paths.is_empty() {
+            self.current_index = 0;
+            self.current_texture = None;
+            self.current_image = None;
+            self.loading_image = None;
+        } else {
+            if self.current_index > idx || self.current_index >= self.image_paths.len() {
+                self.current_index = self.current_index.saturating_sub(1);
+            }
+            if let Some(path) = self.image_paths.get(self.current_index).cloned() {
+                self.load_image(ctx, &path);
+            }
+        }
+        self.status_message = format!("已移除: {}", removed.file_name().unwrap_or_default().to_string_lossy());
+    }
+
+    /// 把第 `from` 张图片移到移动前第 `slot` 张之前（`slot` 等于列表长度时移到末尾），
+    /// 独立配置、缩略图和撤销记录随图片一起移动
+    fn move_image(&mut self, from: usize, slot: usize) {
+        let len = self.image_paths.len();
+        if from >= len || slot > len {
+            return;
+        }
+        let to = if slot > from { slot - 1 } else { slot };
+        if to == from {
             return;
         }
+        let path = self.image_paths.remove(from);
+        self.image_paths.insert(to, path.clone());
 
-        // 在主线程中打开文件对话框
-        if let Some(output_dir) = rfd::FileDialog::new().pick_folder() {
-            let global_config = self.saved_config.clone().unwrap_or_else(|| self.config.clone());
-            let overrides = self.config_overrides.clone();
-            let paths = self.image_paths.clone();
+        let map = move |i: usize| {
+            if i == from {
+                to
+            } else if from < to && (from + 1..=to).contains(&i) {
+                i - 1
+            } else if to < from && (to..from).contains(&i) {
+                i + 1
+            } else {
+                i
+            }
+        };
+        self.remap_indices(|i| Some(map(i)));
+        self.current_index = map(self.current_index);
+        self.pending_remove = self.pending_remove.map(map);
+        self.status_message = format!(
+            "已将 {} 移到第 {} 张",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            to + 1
+        );
+    }
 
-            std::thread::spawn(move || {
-                match ImageSplitter::batch_process(&paths, &global_config, &overrides, &output_dir, |current, total| {
-                    let progress = current as f32 / total as f32;
-                    println!("进度: {:.1}%", progress * 100.0);
-                }) {
-                    Ok((processed, failed)) => {
-                        println!("处理完成: {} 成功, {} 失败", processed, failed);
-                    }
-                    Err(e) => {
-                        eprintln!("批量处理失败: {}", e);
-                    }
-                }
-            });
+    /// 按映射函数重排以索引为键的独立配置和缩略图，返回 None 的条目被丢弃
+    fn remap_indices(&mut self, map: impl Fn(usize) -> Option<usize>) {
+        self.config_overrides = std::mem::take(&mut self.config_overrides)
+            .into_iter()
+            .filter_map(|(i, c)| map(i).map(|i| (i, c)))
+            .collect();
+        self.saved_overrides = std::mem::take(&mut self.saved_overrides)
+            .into_iter()
+            .filter_map(|(i, c)| map(i).map(|i| (i, c)))
+            .collect();
+        self.thumbnails.remap(&map);
+        self.thumbnail_loader.invalidate();
+        for stack in [&mut self.undo_stack, &mut self.redo_stack] {
+            *stack = std::mem::take(stack)
+                .into_iter()
+                .filter_map(|snapshot| match snapshot.index {
+                    Some(i) => map(i).map(|i| LineSnapshot { index: Some(i), ..snapshot }),
+                    None => Some(snapshot),
+                })
+                .collect();
         }
     }
 
-    fn check_for_updates(&self, ctx: egui::Context) {
-        let repo_url = self.obfuscated_repo_url.clone();
-        let current_version = env!("CARGO_PKG_VERSION").to_string();
-        let update_status = self.update_status.clone();
-        
-        // 设置状态为正在检查
+    /// 复制当前图片条目，副本拥有独立的配置槽，可对同一文件定义另一种分割方式
+    fn duplicate_current_image(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.image_paths.get(self.current_index).cloned() else {
+            return;
+        };
+        let new_idx = self.current_index + 1;
+        self.remap_indices(|i| Some(if i >= new_idx { i + 1 } else { i }));
+        self.image_paths.insert(new_idx, path.clone());
+
+        let config = self.config_overrides.get(&self.current_index).cloned().unwrap_or_else(|| self.config.clone());
+        self.config_overrides.insert(new_idx, config);
+
+        self.current_index = new_idx;
+        self.selected_lines.clear();
+        self.load_image(ctx, &path);
+        self.status_message = format!("已复制条目: {} (副本 {})", path.file_name().unwrap_or_default().to_string_lossy(), self.duplicate_number(new_idx));
+    }
+
+    /// 同一路径在列表中出现的序号（1 表示首次出现）
+    fn duplicate_number(&self, idx: usize) -> usize {
+        self.image_paths[..=idx].iter().filter(|p| **p == self.image_paths[idx]).count()
+    }
+
+    /// 保存完整的当前状态（全局配置及所有独立配置）作为已保存基线
+    fn save_config(&mut self) {
+        self.saved_config = Some(self.config.clone());
+        self.saved_overrides = self.config_overrides.clone();
+        self.status_message = format!(
+            "已保存: 全局 {}行 x {}列, 共 {} 张图片 (其中 {} 张使用独立配置)",
+            self.config.rows,
+            self.config.cols,
+            self.image_paths.len(),
+            self.config_overrides.len()
+        );
+    }
+
+    /// 把当前图片正在使用的配置导出为 JSON 文件，输出选项取当前设置
+    fn export_config_file(&mut self, path: &std::path::Path) {
+        let mut config = self.config_overrides.get(&self.current_index).unwrap_or(&self.config).clone();
+        config.copy_output_options_from(&self.config);
+        self.status_message = match config.save_to_file(path) {
+            Ok(()) => format!("已导出配置: {}", path.display()),
+            Err(e) => format!("导出配置失败: {}", e),
+        };
+    }
+
+    /// 从 JSON 文件导入配置到当前图片正在使用的配置（独立配置或全局配置），同时恢复其中的输出选项
+    fn import_config_file(&mut self, path: &std::path::Path) {
+        if !self.ensure_unlocked() {
+            return;
+        }
+        let config = match SplitConfig::load_from_file(path) {
+            Ok(config) => config,
+            Err(e) => {
+                self.status_message = format!("导入配置失败: {}", e);
+                return;
+            }
+        };
+        self.config.copy_output_options_from(&config);
+        let (rows, cols) = (config.rows, config.cols);
+        let before = self.active_snapshot();
+        let target = self.active_config_mut();
+        // 锁定状态属于这张图片，不随导入的文件改变
+        let locked = target.locked;
+        *target = SplitConfig { locked, ..config };
+        self.commit_undo(before);
+        self.selected_lines.clear();
+        self.selected_cells.clear();
+        self.status_message = format!("已导入配置: {}行 x {}列", rows, cols);
+    }
+
+    /// 把图片列表、全局配置和所有独立配置保存为项目文件
+    fn save_project(&mut self, path: &std::path::Path) {
+        let project = Project {
+            config: self.config.clone(),
+            saved_config: self.saved_config.clone(),
+            images: self
+                .image_paths
+                .iter()
+                .enumerate()
+                .map(|(idx, image)| ProjectImage {
+                    path: std::path::absolute(image).unwrap_or_else(|_| image.clone()),
+                    config: self.config_overrides.get(&idx).cloned(),
+                })
+                .collect(),
+        };
+        let result = serde_json::to_string_pretty(&project)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(path, json));
+        self.status_message = match result {
+            Ok(()) => format!(
+                "已保存项目: {} 张图片，{} 个独立配置",
+                project.images.len(),
+                self.config_overrides.len()
+            ),
+            Err(e) => format!("保存项目失败: {}", e),
+        };
+    }
+
+    /// 打开项目文件，替换当前图片列表；已不存在的图片连同其独立配置一起丢弃
+    ///
+    /// 打开后的内容记为已保存状态。
+    fn open_project(&mut self, ctx: &egui::Context, path: &std::path::Path) {
+        let project: Project = match std::fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Ok(serde_json::from_str(&text)?))
         {
-            if let Ok(mut status) = update_status.lock() {
-                *status = UpdateStatus::Checking;
+            Ok(project) => project,
+            Err(e) => {
+                self.status_message = format!("打开项目失败: {}", e);
+                return;
+            }
+        };
+        self.clear_images();
+        self.cleared_images = None;
+        self.config = project.config;
+        self.config.normalize();
+        self.saved_config = project.saved_config;
+        if let Some(saved) = self.saved_config.as_mut() {
+            saved.normalize();
+        }
+        let total = project.images.len();
+        for image in project.images.into_iter().filter(|image| image.path.is_file()) {
+            if let Some(mut config) = image.config {
+                config.normalize();
+                self.config_overrides.insert(self.image_paths.len(), config);
             }
+            self.image_paths.push(image.path);
         }
+        self.saved_overrides = self.config_overrides.clone();
+        if let Some(first) = self.image_paths.first().cloned() {
+            self.load_image(ctx, &first);
+        }
+        let missing = total - self.image_paths.len();
+        self.status_message = format!(
+            "已打开项目: {} 张图片，恢复 {} 个独立配置",
+            self.image_paths.len(),
+            self.config_overrides.len()
+        );
+        if missing > 0 {
+            self.status_message.push_str(&format!("，{} 张图片已不存在", missing));
+        }
+    }
 
-        // 转换 GitHub URL 到 API URL
-        let api_url = if repo_url.starts_with("http") {
-            repo_url.replace("github.com", "api.github.com/repos")
-        } else {
-            format!("https://{}", repo_url.replace("github.com", "api.github.com/repos"))
-        } + "/releases/latest";
+    /// 恢复到上次保存的全局配置和独立配置，输出选项保持当前值
+    fn revert_to_saved(&mut self) {
+        let Some(saved) = self.saved_config.clone() else {
+            return;
+        };
+        let output = self.config.clone();
+        self.config = saved;
+        self.config.copy_output_options_from(&output);
+        self.config_overrides = self.saved_overrides.clone();
+        self.selected_lines.clear();
+        self.status_message = "已恢复到上次保存的分割线位置".to_string();
+    }
+
+    /// 全局配置（不含输出选项）或任一独立配置与上次保存时不同
+    fn has_unsaved_changes(&self) -> bool {
+        let global_changed = match &self.saved_config {
+            Some(saved) => {
+                let mut saved = saved.clone();
+                saved.copy_output_options_from(&self.config);
+                saved != self.config
+            }
+            None => true,
+        };
+        global_changed || self.saved_overrides != self.config_overrides
+    }
+
+    /// 指定图片的独立配置与上次保存时不同
+    fn image_has_unsaved_changes(&self, idx: usize) -> bool {
+        self.config_overrides.get(&idx) != self.saved_overrides.get(&idx)
+    }
+
+    /// 校验全局配置与所有独立配置，结果显示在校验窗口中
+    fn validate_configs(&mut self) {
+        let mut report = Vec::new();
+        if !self.config.is_valid() {
+            report.push(("全局配置".to_string(), self.config.issues()));
+        }
+        if let Some(saved) = self.saved_config.as_ref().filter(|c| !c.is_valid()) {
+            report.push(("已保存配置".to_string(), saved.issues()));
+        }
+        let mut indices: Vec<usize> = self.config_overrides.keys().copied().collect();
+        indices.sort_unstable();
+        for idx in indices {
+            let config = &self.config_overrides[&idx];
+            if !config.is_valid() {
+                let issues = config.issues();
+                let name = self.image_paths.get(idx)
+                    .map(|p| p.file_name().unwrap_or_default().to_string_lossy().to_string())
+                    .unwrap_or_else(|| format!("#{}", idx + 1));
+                report.push((name, issues));
+            }
+        }
+        self.status_message = if report.is_empty() {
+            format!("校验通过: 全局配置及 {} 个独立配置均有效", self.config_overrides.len())
+        } else {
+            format!("校验发现 {} 个配置存在问题", report.len())
+        };
+        self.validation_report = Some(report);
+    }
+
+    /// 自动修复所有配置
+    fn fix_all_configs(&mut self) {
+        let mut fixed = 0;
+        if self.config.normalize() { fixed += 1; }
+        if let Some(saved) = self.saved_config.as_mut() {
+            if saved.normalize() { fixed += 1; }
+        }
+        for config in self.config_overrides.values_mut() {
+            if config.normalize() { fixed += 1; }
+        }
+        self.selected_lines.clear();
+        self.status_message = format!("已修复 {} 个配置", fixed);
+    }
+
+    /// 文件名与搜索框匹配的图片下标及匹配字符位置，搜索框为空时返回全部
+    fn filtered_images(&self) -> Vec<(usize, Vec<usize>)> {
+        self.image_paths
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, path)| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                fuzzy_match(&self.search_query, &name).map(|matched| (idx, matched))
+            })
+            .collect()
+    }
+
+    /// 批处理只处理筛选结果
+    fn batch_uses_filter(&self) -> bool {
+        self.batch_only_filtered && !self.search_query.trim().is_empty()
+    }
+
+    /// 批处理使用的图片、全局配置与独立配置，输出选项统一取自当前设置
+    ///
+    /// 只处理筛选结果时，独立配置的下标按筛选后的顺序重新编号。
+    fn batch_configs(&self) -> (Vec<PathBuf>, SplitConfig, std::collections::HashMap<usize, SplitConfig>) {
+        let mut global_config = self.shared_config().clone();
+        global_config.copy_output_options_from(&self.config);
+        global_config.detect_background = self.settings.background_color;
+        let indices: Vec<usize> = if self.batch_uses_filter() {
+            self.filtered_images().into_iter().map(|(idx, _)| idx).collect()
+        } else {
+            (0..self.image_paths.len()).collect()
+        };
+        let mut paths = Vec::with_capacity(indices.len());
+        let mut overrides = std::collections::HashMap::new();
+        for (new_idx, &idx) in indices.iter().enumerate() {
+            paths.push(self.image_paths[idx].clone());
+            if let Some(config) = self.config_overrides.get(&idx) {
+                let mut config = config.clone();
+                config.copy_output_options_from(&self.config);
+                overrides.insert(new_idx, config);
+            }
+        }
+        (paths, global_config, overrides)
+    }
+
+    /// 预估输出并弹出批处理确认窗口
+    fn request_batch_process(&mut self) {
+        if self.image_paths.is_empty() {
+            return;
+        }
+        if matches!(self.batch_status.lock().as_deref(), Ok(BatchStatus::Running { .. })) {
+            self.status_message = "批量处理正在进行中".to_string();
+            return;
+        }
+        if let Some(e) = self.template_error() {
+            self.status_message = format!("文件名模板有误: {}", e);
+            return;
+        }
+        let (paths, global_config, overrides) = self.batch_configs();
+        if paths.is_empty() {
+            self.status_message = "没有与搜索匹配的图片".to_string();
+            return;
+        }
+        self.batch_confirm = Some(ImageSplitter::plan_batch(&paths, &global_config, &overrides));
+    }
+
+    /// 输出到记住的文件夹；没有记住的文件夹或它已不存在时先选择
+    fn start_batch_process(&mut self, ctx: &egui::Context) {
+        if self.image_paths.is_empty() {
+            return;
+        }
+        // 输出到源图片旁的子文件夹，扫描文件夹时不会进入子文件夹，无需检查冲突
+        if self.settings.output_alongside_source {
+            self.run_batch_process(ctx, OutputLocation::AlongsideSource, BatchScope::All);
+            return;
+        }
+
+        match self.settings.last_output_dir.clone().filter(|dir| dir.is_dir()) {
+            Some(output_dir) => self.begin_batch_in(ctx, output_dir, BatchScope::All),
+            None => self.open_dialog(DialogKind::OutputFolder),
+        }
+    }
+
+    /// 检查输出目录后开始批处理
+    fn begin_batch_in(&mut self, ctx: &egui::Context, output_dir: PathBuf, scope: BatchScope) {
+        // 输出目录位于输入目录内时，重新扫描会把生成的分块当作新图片
+        if let Some(input_dir) = self.conflicting_input_dir(&output_dir) {
+            self.output_conflict = Some((output_dir, input_dir, scope));
+            return;
+        }
+        self.run_batch_process(ctx, OutputLocation::Chosen(output_dir), scope);
+    }
+
+    /// 使用自定义模板命名且模板无效时返回错误说明
+    fn template_error(&self) -> Option<anyhow::Error> {
+        (self.config.naming_scheme == NamingScheme::Template)
+            .then(|| self.config.validate_template().err())
+            .flatten()
+    }
+
+    /// 用当前图片正在编辑的配置只处理这一张，输出到上次选择的文件夹，便于调整网格时快速查看结果
+    fn process_current_image(&mut self, ctx: &egui::Context) {
+        if self.image_paths.is_empty() {
+            return;
+        }
+        if matches!(self.batch_status.lock().as_deref(), Ok(BatchStatus::Running { .. })) {
+            self.status_message = "批量处理正在进行中".to_string();
+            return;
+        }
+        if let Some(e) = self.template_error() {
+            self.status_message = format!("文件名模板有误: {}", e);
+            return;
+        }
+        if self.settings.output_alongside_source {
+            self.run_batch_process(ctx, OutputLocation::AlongsideSource, BatchScope::Current);
+        } else if let Some(output_dir) = self.settings.last_output_dir.clone() {
+            self.begin_batch_in(ctx, output_dir, BatchScope::Current);
+        } else {
+            self.open_dialog(DialogKind::CurrentImageOutputFolder);
+        }
+    }
+
+    /// 从文本文件导入分割线位置到当前图片的独立配置
+    fn import_line_positions(&mut self, path: &std::path::Path) {
+        if !self.ensure_unlocked() {
+            return;
+        }
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                self.status_message = format!("读取分割线文件失败: {}", e);
+                return;
+            }
+        };
+        let import = LineImport::parse(&text);
+        if import.h_lines.is_none() && import.v_lines.is_none() {
+            self.status_message = match import.errors.first() {
+                Some(error) => format!("未导入任何分割线 ({} 行有误，{})", import.errors.len(), error),
+                None => "文件中没有分割线位置".to_string(),
+            };
+            return;
+        }
+        let before = self.override_snapshot();
+        let config = self.config_overrides.entry(self.current_index)
+            .or_insert_with(|| self.config.clone());
+        import.apply_to(config);
+        let (rows, cols) = (config.rows, config.cols);
+        self.commit_undo(before);
+        self.selected_lines.clear();
+        self.status_message = format!("已导入分割线: {}行 x {}列 (独立配置)", rows, cols);
+        if let Some(error) = import.errors.first() {
+            self.status_message.push_str(&format!("，跳过 {} 行有误的条目 ({})", import.errors.len(), error));
+        }
+    }
+
+    /// 打开非阻塞文件对话框，已有对话框打开时忽略；选择输出文件夹时从上次的文件夹开始
+    fn open_dialog(&mut self, kind: DialogKind) {
+        if self.pending_dialog.is_none() {
+            let directory = match kind {
+                DialogKind::OutputFolder | DialogKind::CurrentImageOutputFolder | DialogKind::ChangeOutputFolder => {
+                    self.settings.last_output_dir.as_deref()
+                }
+                _ => None,
+            };
+            self.pending_dialog = Some(PendingDialog::open(kind, directory));
+        }
+    }
+
+    /// 轮询文件对话框，关闭后按用途处理所选路径
+    fn poll_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = self.pending_dialog.as_mut() else {
+            return;
+        };
+        let std::task::Poll::Ready(paths) = dialog.poll(ctx) else {
+            return;
+        };
+        let kind = dialog.kind;
+        self.pending_dialog = None;
+        if paths.is_empty() {
+            return;
+        }
+        if self.replace_on_add && matches!(kind, DialogKind::ImageFiles | DialogKind::InputFolder) {
+            self.clear_images();
+        }
+        match kind {
+            DialogKind::ImageFiles => self.add_image_files(ctx, paths),
+            DialogKind::InputFolder => self.add_folder(ctx, paths[0].clone()),
+            DialogKind::LinePositions => self.import_line_positions(&paths[0]),
+            DialogKind::ImportConfig => self.import_config_file(&paths[0]),
+            DialogKind::ExportConfig => self.export_config_file(&paths[0]),
+            DialogKind::OpenProject => self.open_project(ctx, &paths[0]),
+            DialogKind::SaveProject => self.save_project(&paths[0]),
+            DialogKind::CurrentImageOutputFolder => {
+                self.settings.last_output_dir = Some(paths[0].clone());
+                self.process_current_image(ctx);
+            }
+            DialogKind::ChangeOutputFolder => {
+                self.settings.last_output_dir = Some(paths[0].clone());
+                self.status_message = format!("输出文件夹: {}", paths[0].display());
+            }
+            DialogKind::OutputFolder => {
+                let output_dir = paths[0].clone();
+                self.settings.last_output_dir = Some(output_dir.clone());
+                self.begin_batch_in(ctx, output_dir, BatchScope::All);
+            }
+        }
+    }
+
+    /// 从原图裁出指定分块，与导出结果一致（含旋转设置）
+    fn crop_cell(&self, config: &SplitConfig, row: usize, col: usize) -> Option<image::DynamicImage> {
+        let img = self.current_image.as_ref()?;
+        // 合并区域内的任一分块都对应整个合并区域
+        let (row, col) = config.merge_at(row, col).map_or((row, col), |m| (m.row, m.col));
+        let region = ImageSplitter::compute_regions(img.width(), img.height(), config)
+            .into_iter()
+            .find(|r| r.row == row && r.col == col)?;
+        let mut part = img.crop_imm(region.x, region.y, region.width, region.height);
+        if let Some(rotation) = config.cell_rotation(row, col) {
+            part = rotation.apply(&part);
+        }
+        Some(part)
+    }
+
+    /// 以原始像素在预览窗口中显示指定分块
+    fn preview_tile(&mut self, ctx: &egui::Context, config: &SplitConfig, row: usize, col: usize) {
+        let Some(part) = self.crop_cell(config, row, col) else {
+            return;
+        };
+        let rgba = part.to_rgba8();
+        let color_image = egui::ColorImage::from_rgba_unmultiplied(
+            [rgba.width() as usize, rgba.height() as usize],
+            rgba.as_raw(),
+        );
+        let texture = ctx.load_texture("tile_preview", color_image, egui::TextureOptions::NEAREST);
+        let title = format!("分块 ({}, {}) - {} x {} 像素", row + 1, col + 1, part.width(), part.height());
+        self.tile_preview = Some((title, texture));
+    }
+
+    /// 按当前行列数均分当前图片的分割线，保证分块不小于最小单元尺寸
+    fn equalize_lines(&mut self) {
+        if !self.ensure_unlocked() {
+            return;
+        }
+        let Some((width, height)) = self.current_image.as_ref().map(|img| (img.width(), img.height())) else {
+            return;
+        };
+        let min_cell = self.settings.min_cell_size;
+        let before = self.active_snapshot();
+        let config = self.active_config_mut();
+        let reduced = config.equalize_with_min_cell(width, height, min_cell);
+        let (rows, cols) = (config.rows, config.cols);
+        self.commit_undo(before);
+        self.selected_lines.clear();
+        self.status_message = match reduced {
+            Some((old_rows, old_cols)) => format!(
+                "图片过小 ({}x{})，为保证单元 ≥ {} px，网格由 {}行 x {}列 减为 {}行 x {}列",
+                width, height, min_cell, old_rows, old_cols, rows, cols
+            ),
+            None => format!("已均分分割线: {}行 x {}列", rows, cols),
+        };
+    }
+
+    /// 把当前图片正在使用的配置改为给定行列数的平均分割
+    fn set_grid_size(&mut self, rows: usize, cols: usize) -> bool {
+        if !self.ensure_unlocked() {
+            return false;
+        }
+        let before = self.active_snapshot();
+        let config = self.active_config_mut();
+        config.rows = rows;
+        config.cols = cols;
+        config.reset_to_default();
+        self.commit_undo(before);
+        self.selected_lines.clear();
+        true
+    }
+
+    /// 按当前图片正在使用的配置的行列数恢复均匀网格
+    fn make_even_grid(&mut self) {
+        if !self.ensure_unlocked() {
+            return;
+        }
+        let before = self.active_snapshot();
+        let config = self.active_config_mut();
+        let (rows, cols) = (config.h_lines.len() + 1, config.v_lines.len() + 1);
+        config.make_even_grid(rows, cols);
+        self.commit_undo(before);
+        self.selected_lines.clear();
+        self.status_message = format!("已恢复均匀网格: {}行 x {}列", rows, cols);
+    }
+
+    /// 以最后选中的分割线为参考，把其余选中的另一方向分割线移到相同比例位置
+    ///
+    /// 同方向的线对齐后会重合，因此跳过。
+    fn align_selected_lines(&mut self) {
+        if !self.ensure_unlocked() {
+            return;
+        }
+        let Some(&(ref_type, ref_idx)) = self.selected_lines.last() else {
+            return;
+        };
+        let selected = self.selected_lines.clone();
+        let before = self.active_snapshot();
+        let config = self.active_config_mut();
+        let lines = |config: &SplitConfig, t: LineType| -> Vec<f32> {
+            match t {
+                LineType::Horizontal => config.h_lines.clone(),
+                LineType::Vertical => config.v_lines.clone(),
+            }
+        };
+        let Some(&target) = lines(config, ref_type).get(ref_idx) else {
+            return;
+        };
+        // 记录选中线的位置，排序后据此恢复选中状态
+        let mut positions: Vec<(LineType, f32)> = Vec::new();
+        let mut aligned = 0;
+        for &(line_type, idx) in &selected {
+            let Some(&pos) = lines(config, line_type).get(idx) else {
+                continue;
+            };
+            if line_type == ref_type {
+                positions.push((line_type, pos));
+                continue;
+            }
+            match line_type {
+                LineType::Horizontal => config.h_lines[idx] = target,
+                LineType::Vertical => config.v_lines[idx] = target,
+            }
+            positions.push((line_type, target));
+            aligned += 1;
+        }
+        // 同方向的多条线对齐到同一位置时只保留一条
+        config.normalize();
+        let config = config.clone();
+        self.commit_undo(before);
+
+        self.selected_lines = positions
+            .into_iter()
+            .filter_map(|(t, pos)| lines(&config, t).iter().position(|&p| p == pos).map(|i| (t, i)))
+            .collect();
+        self.status_message = if aligned == 0 {
+            "请同时选中另一方向的分割线".to_string()
+        } else {
+            format!("已将 {} 条分割线对齐到 {:.1}%", aligned, target * 100.0)
+        };
+    }
+
+    /// 清空图片列表及与索引关联的独立配置和缩略图
+    fn clear_images(&mut self) {
+        self.image_paths.clear();
+        self.input_dirs.clear();
+        self.config_overrides.clear();
+        self.saved_overrides.clear();
+        self.sidecars.clear();
+        self.thumbnails.clear();
+        self.thumbnail_loader.invalidate();
+        self.undo_stack.retain(|snapshot| snapshot.index.is_none());
+        self.redo_stack.retain(|snapshot| snapshot.index.is_none());
+        self.selected_lines.clear();
+        self.pending_crop = None;
+        self.current_index = 0;
+        self.current_texture = None;
+        self.current_image = None;
+        self.loading_image = None;
+    }
+
+    /// 「清除」按钮：先保存列表状态再清空，提示条消失前可撤销
+    fn clear_images_with_undo(&mut self) {
+        if self.image_paths.is_empty() {
+            return;
+        }
+        self.cleared_images = Some(ClearedImages {
+            image_paths: self.image_paths.clone(),
+            input_dirs: self.input_dirs.clone(),
+            config_overrides: self.config_overrides.clone(),
+            saved_overrides: self.saved_overrides.clone(),
+            current_index: self.current_index,
+            cleared_at: std::time::Instant::now(),
+        });
+        self.clear_images();
+    }
+
+    /// 恢复上次清除的图片列表（清除后新加入的图片保留在后面）
+    fn undo_clear_images(&mut self, ctx: &egui::Context) {
+        let Some(cleared) = self.cleared_images.take() else {
+            return;
+        };
+        let added = std::mem::replace(&mut self.image_paths, cleared.image_paths);
+        let offset = self.image_paths.len();
+        let overrides = std::mem::replace(&mut self.config_overrides, cleared.config_overrides);
+        let saved = std::mem::replace(&mut self.saved_overrides, cleared.saved_overrides);
+        self.config_overrides.extend(overrides.into_iter().map(|(i, c)| (i + offset, c)));
+        self.saved_overrides.extend(saved.into_iter().map(|(i, c)| (i + offset, c)));
+        self.image_paths.extend(added);
+        for dir in cleared.input_dirs.into_iter().rev() {
+            if !self.input_dirs.contains(&dir) {
+                self.input_dirs.insert(0, dir);
+            }
+        }
+        self.thumbnails.clear();
+        self.thumbnail_loader.invalidate();
+        self.selected_lines.clear();
+        self.current_index = cleared.current_index;
+        if let Some(path) = self.image_paths.get(self.current_index).cloned() {
+            self.load_image(ctx, &path);
+        }
+        self.status_message = format!("已撤销清除，恢复 {} 张图片", offset);
+    }
+
+    /// 清除后的撤销提示条，超时后放弃保存的列表
+    fn draw_undo_clear_toast(&mut self, ctx: &egui::Context) {
+        let Some(cleared) = &self.cleared_images else {
+            return;
+        };
+        let elapsed = cleared.cleared_at.elapsed();
+        if elapsed >= UNDO_CLEAR_TIMEOUT {
+            self.cleared_images = None;
+            return;
+        }
+        ctx.request_repaint_after(UNDO_CLEAR_TIMEOUT - elapsed);
+        let count = cleared.image_paths.len();
+        let mut undo = false;
+        egui::Area::new(egui::Id::new("undo_clear_toast"))
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -24.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::none()
+                    .fill(egui::Color32::from_rgb(31, 41, 55))
+                    .rounding(8.0)
+                    .inner_margin(egui::Margin::symmetric(14.0, 8.0))
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(format!("已清除 {} 张图片", count)).color(egui::Color32::WHITE));
+                            ui.add_space(8.0);
+                            undo = ui.button(egui::RichText::new("撤销").strong()).clicked();
+                        });
+                    });
+            });
+        if undo {
+            self.undo_clear_images(ctx);
+        }
+    }
+
+    /// 添加图片，跳过列表中已有的文件（按规范化路径比较，同一文件经不同路径写法添加也算重复）
+    ///
+    /// 「复制条目」得到的同文件条目不受影响，只是不会因重复添加而再多出一个。
+    fn add_image_files(&mut self, ctx: &egui::Context, paths: Vec<PathBuf>) {
+        let canonical = |p: &std::path::Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+        let mut known: std::collections::HashSet<PathBuf> = self.image_paths.iter().map(|p| canonical(p)).collect();
+        let first_new = self.image_paths.len();
+        let mut skipped = 0;
+        for path in paths {
+            if known.insert(canonical(&path)) {
+                self.image_paths.push(path);
+            } else {
+                skipped += 1;
+            }
+        }
+        let added = self.image_paths.len() - first_new;
+        let sidecars = self.load_sidecars(first_new);
+
+        let mut summary = format!("已添加 {} 张图片", added);
+        if skipped > 0 {
+            summary.push_str(&format!("，跳过 {} 张已在列表中的图片", skipped));
+        }
+        if sidecars > 0 {
+            summary.push_str(&format!("，从旁车文件恢复 {} 张图片的独立配置", sidecars));
+        }
+        if self.current_texture.is_none() && !self.image_paths.is_empty() {
+            self.load_image(ctx, &self.image_paths[0].clone());
+            summary = format!("{}；{}", summary, self.status_message);
+        }
+        self.status_message = summary;
+    }
+
+    fn add_folder(&mut self, ctx: &egui::Context, folder: PathBuf) {
+        if !self.input_dirs.contains(&folder) {
+            self.input_dirs.push(folder.clone());
+        }
+        let mut images = Vec::new();
+        let mut too_deep = false;
+        if self.settings.scan_subfolders {
+            too_deep = collect_images(&folder, self.settings.ignore_hidden_files, 0, &mut images);
+        } else if let Ok(entries) = std::fs::read_dir(&folder) {
+            for entry in entries.flatten() {
+                if self.settings.ignore_hidden_files && is_hidden(&entry) {
+                    continue;
+                }
+                let path = entry.path();
+                if is_image_path(&path) {
+                    images.push(path);
+                }
+            }
+        }
+        self.add_image_files(ctx, images);
+        if too_deep {
+            self.status_message.push_str(&format!("，超过 {} 层的子文件夹未扫描", MAX_SCAN_DEPTH));
+        }
+    }
+
+    /// 添加拖放到窗口上的图片和文件夹，文件夹包括其子文件夹
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped: Vec<PathBuf> = ctx.input(|i| i.raw.dropped_files.iter().filter_map(|f| f.path.clone()).collect());
+        if dropped.is_empty() {
+            return;
+        }
+        let mut images = Vec::new();
+        let mut ignored = 0;
+        let mut too_deep = false;
+        for path in dropped {
+            if path.is_dir() {
+                too_deep |= collect_images(&path, self.settings.ignore_hidden_files, 0, &mut images);
+                if !self.input_dirs.contains(&path) {
+                    self.input_dirs.push(path);
+                }
+            } else if is_image_path(&path) {
+                images.push(path);
+            } else {
+                ignored += 1;
+            }
+        }
+        self.add_image_files(ctx, images);
+        if ignored > 0 {
+            self.status_message.push_str(&format!("，忽略 {} 个不支持的文件", ignored));
+        }
+        if too_deep {
+            self.status_message.push_str(&format!("，超过 {} 层的子文件夹未扫描", MAX_SCAN_DEPTH));
+        }
+    }
+
+    /// 有文件拖到窗口上方时覆盖一层提示
+    fn draw_drop_overlay(&self, ctx: &egui::Context) {
+        let hovered = ctx.input(|i| i.raw.hovered_files.len());
+        if hovered == 0 {
+            return;
+        }
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_overlay")));
+        let screen = ctx.screen_rect();
+        painter.rect_filled(screen, 0.0, egui::Color32::from_rgba_unmultiplied(19, 78, 74, 160));
+        painter.rect_stroke(screen.shrink(12.0), 12.0, egui::Stroke::new(2.0, egui::Color32::WHITE));
+        painter.text(
+            screen.center(),
+            egui::Align2::CENTER_CENTER,
+            format!("{} 松开以添加图片或文件夹", icon::FOLDER_OPEN),
+            egui::FontId::proportional(22.0),
+            egui::Color32::WHITE,
+        );
+    }
+
+    /// 为从 `first_new` 开始新添加的图片读取旁车文件中的独立配置（需在设置中开启）
+    ///
+    /// 读取的配置同时记为已保存状态，不会显示为未保存的修改。返回读取到独立配置的图片数。
+    fn load_sidecars(&mut self, first_new: usize) -> usize {
+        if !self.settings.sidecar_overrides {
+            return 0;
+        }
+        let mut loaded = 0;
+        for idx in first_new..self.image_paths.len() {
+            let path = &self.image_paths[idx];
+            // 同一文件重复添加时，旁车文件只属于第一个条目
+            if self.image_paths[..idx].contains(path) {
+                continue;
+            }
+            if let Some(config) = sidecar::load(path) {
+                self.sidecars.mark_loaded(path, &config);
+                self.saved_overrides.insert(idx, config.clone());
+                self.config_overrides.insert(idx, config);
+                loaded += 1;
+            }
+        }
+        loaded
+    }
+
+    /// 查找包含输出目录（或与之相同）的输入目录
+    fn conflicting_input_dir(&self, output_dir: &std::path::Path) -> Option<PathBuf> {
+        let canonical = |p: &std::path::Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+        let output_dir = canonical(output_dir);
+        let image_dirs = self.image_paths.iter().filter_map(|p| p.parent());
+        self.input_dirs.iter()
+            .map(|d| d.as_path())
+            .chain(image_dirs)
+            .map(canonical)
+            .find(|input_dir| output_dir.starts_with(input_dir))
+    }
+
+    /// 在后台线程中处理，进度和结果经 `batch_status` 显示
+    ///
+    /// 只处理当前图片时仍传入整个列表，使输出文件名与批量处理一致。
+    fn run_batch_process(&mut self, ctx: &egui::Context, location: OutputLocation, scope: BatchScope) {
+        let (paths, global_config, overrides) = self.batch_configs();
+        let current = match scope {
+            BatchScope::All => None,
+            BatchScope::Current => {
+                let mut config = self.config_overrides.get(&self.current_index).unwrap_or(&self.config).clone();
+                config.copy_output_options_from(&self.config);
+                Some((self.image_paths.clone(), self.current_index, config))
+            }
+        };
+        let batch_status = self.batch_status.clone();
+        if let Ok(mut status) = batch_status.lock() {
+            let total = if current.is_some() { 1 } else { paths.len() };
+            *status = BatchStatus::Running { done: 0, total };
+        }
+        self.batch_cancel.store(false, Ordering::Relaxed);
+        let cancel = self.batch_cancel.clone();
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            let result = match current {
+                Some((paths, index, config)) => ImageSplitter::process_image(&paths, index, &config, &location),
+                None => ImageSplitter::batch_process(&paths, &global_config, &overrides, &location, &cancel, |done, total| {
+                    if let Ok(mut status) = batch_status.lock() {
+                        *status = BatchStatus::Running { done, total };
+                    }
+                    ctx.request_repaint();
+                }),
+            };
+            if let Ok(mut status) = batch_status.lock() {
+                *status = match result {
+                    Ok(report) => BatchStatus::Finished(report),
+                    Err(e) => BatchStatus::Error(e.to_string()),
+                };
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// 丢弃图片的缩略图，按其当前配置重新生成
+    fn forget_thumbnail(&mut self, idx: usize) {
+        self.thumbnails.remove(idx);
+        self.thumbnail_loader.invalidate();
+    }
+
+    /// 取回后台生成好的缩略图并上传为纹理，期间列表已变化的结果被丢弃；超出预算时淘汰最久未显示的
+    fn poll_thumbnails(&mut self, ctx: &egui::Context) {
+        let frame = ctx.frame_nr();
+        for finished in self.thumbnail_loader.finished() {
+            if self.image_paths.get(finished.index) != Some(&finished.path) {
+                continue;
+            }
+            let idx = finished.index;
+            let texture = match finished.image {
+                Some(image) => ctx.load_texture(format!("thumb_{}", idx), image, egui::TextureOptions::default()),
+                // 加载失败时使用默认空纹理或错误提示
+                None => ctx.load_texture(format!("thumb_err_{}", idx), egui::ColorImage::example(), egui::TextureOptions::default()),
+            };
+            self.thumbnails.insert(idx, texture, frame);
+        }
+        let max_bytes = u64::from(self.settings.thumbnail_memory_mb) * 1024 * 1024;
+        self.evicted_thumbnails += self.thumbnails.evict(self.settings.max_thumbnails, max_bytes, frame);
+    }
+
+    /// 同步后台批处理的进度，结束时隐藏进度条并显示完成信息
+    fn poll_batch_status(&mut self) {
+        let Ok(status) = self.batch_status.lock() else {
+            return;
+        };
+        match &*status {
+            BatchStatus::Idle => {}
+            BatchStatus::Running { done, total } => {
+                self.show_progress = true;
+                self.progress = if *total == 0 { 0.0 } else { *done as f32 / *total as f32 };
+                self.status_message = format!("正在批量处理: {}/{}", done, total);
+            }
+            BatchStatus::Finished(report) if self.show_progress => {
+                self.show_progress = false;
+                self.progress = 1.0;
+                self.status_message = if report.cancelled > 0 {
+                    format!(
+                        "已取消批量处理: 取消前处理了 {} 张 ({} 成功, {} 失败)，{} 张未处理",
+                        report.processed + report.failed,
+                        report.processed,
+                        report.failed,
+                        report.cancelled,
+                    )
+                } else {
+                    format!("批量处理完成: {} 成功, {} 失败", report.processed, report.failed)
+                };
+            }
+            BatchStatus::Error(e) if self.show_progress => {
+                self.show_progress = false;
+                self.status_message = format!("批量处理失败: {}", e);
+            }
+            BatchStatus::Finished(_) | BatchStatus::Error(_) => {}
+        }
+    }
+
+    /// 批处理结果窗口：汇总统计并列出失败的图片及原因
+    fn draw_batch_result(&mut self, ctx: &egui::Context) {
+        let status = match self.batch_status.lock() {
+            Ok(status) => status.clone(),
+            Err(_) => return,
+        };
+        let (report, error) = match status {
+            BatchStatus::Idle | BatchStatus::Running { .. } => return,
+            BatchStatus::Finished(report) => (Some(report), None),
+            BatchStatus::Error(e) => (None, Some(e)),
+        };
+
+        let mut open = true;
+        let mut dismissed = false;
+        egui::Window::new("批量处理结果")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.set_min_width(320.0);
+                ui.set_max_width(480.0);
+                if let Some(e) = &error {
+                    ui.label(egui::RichText::new(format!("{} 批量处理失败: {}", icon::WARNING, e)).color(egui::Color32::RED));
+                }
+                if let Some(report) = &report {
+                    ui.label(format!("处理完成: {} 成功, {} 失败", report.processed, report.failed));
+                    if report.cancelled > 0 {
+                        ui.label(egui::RichText::new(format!("{} 已取消，{} 张图片未处理", icon::WARNING, report.cancelled))
+                            .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                    }
+                    ui.label(format!("写入分块: {}", report.written_tiles));
+                    if report.resumed() {
+                        ui.label(format!("跳过已存在的分块: {} (其中 {} 张图片无需解码)", report.skipped_tiles, report.skipped_images));
+                    }
+                    if report.collapsed_images > 0 {
+                        ui.label(egui::RichText::new(format!("{} {} 张图片尺寸过小，部分分割线已合并", icon::WARNING, report.collapsed_images))
+                            .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                    }
+                    if report.empty_tiles > 0 {
+                        ui.label(egui::RichText::new(format!("{} 跳过 {} 个宽或高为 0 的分块", icon::WARNING, report.empty_tiles))
+                            .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                    }
+                    if !report.detected_images.is_empty() || report.detection_fallbacks > 0 {
+                        ui.label(egui::RichText::new(format!(
+                            "自动网格: {} 张按检测结果分割, {} 张未检测到网格, 使用全局分割线",
+                            report.detected_images.len(),
+                            report.detection_fallbacks,
+                        )).size(12.0).color(egui::Color32::GRAY));
+                        if !report.detected_images.is_empty() {
+                            egui::CollapsingHeader::new(format!("按检测结果分割的图片 ({})", report.detected_images.len()))
+                                .id_source("detected_images")
+                                .show(ui, |ui| {
+                                    egui::ScrollArea::vertical().id_source("detected_images_list").max_height(120.0).show(ui, |ui| {
+                                        for path in &report.detected_images {
+                                            ui.label(egui::RichText::new(path.display().to_string()).size(12.0).color(egui::Color32::from_rgb(107, 114, 128)));
+                                        }
+                                    });
+                                });
+                        }
+                    }
+                    if !report.format_fallbacks.is_empty() {
+                        ui.add_space(6.0);
+                        ui.label(egui::RichText::new(format!("{} 改存为 PNG 的文件:", icon::WARNING)).size(13.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                        egui::ScrollArea::vertical().id_source("format_fallbacks").max_height(120.0).show(ui, |ui| {
+                            for (path, reason) in &report.format_fallbacks {
+                                ui.label(egui::RichText::new(path.display().to_string()).size(12.0).strong());
+                                ui.label(egui::RichText::new(reason).size(12.0).color(egui::Color32::from_rgb(107, 114, 128)));
+                                ui.add_space(4.0);
+                            }
+                        });
+                    }
+                    if !report.failures.is_empty() {
+                        ui.add_space(6.0);
+                        ui.label(egui::RichText::new("失败的图片:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                        egui::ScrollArea::vertical().max_height(220.0).show(ui, |ui| {
+                            for (path, message) in &report.failures {
+                                ui.label(egui::RichText::new(path.display().to_string()).size(12.0).strong())
+                                    .on_hover_text(path.display().to_string());
+                                ui.label(egui::RichText::new(message).size(12.0).color(egui::Color32::RED));
+                                ui.add_space(4.0);
+                            }
+                        });
+                    }
+                }
+                ui.add_space(8.0);
+                if ui.button("知道了").clicked() {
+                    dismissed = true;
+                }
+            });
+        if !open || dismissed {
+            if let Ok(mut status) = self.batch_status.lock() {
+                *status = BatchStatus::Idle;
+            }
+        }
+    }
+
+    /// 把分块以 RGBA 像素复制到剪贴板，PNG 来源的透明通道得以保留
+    ///
+    /// arboard 会按平台写入保留透明度的格式：Windows 同时写入 PNG 和 CF_DIBV5，
+    /// Linux 写入 image/png，macOS 写入带透明通道的 NSImage。
+    /// 平台限制：Windows 上只读取 CF_BITMAP/CF_DIB 的旧程序（如系统画图）会丢失透明度；
+    /// Linux 上剪贴板内容由本程序提供，没有剪贴板管理器时退出程序后内容即失效。
+    fn copy_tile_to_clipboard(&mut self, config: &SplitConfig, row: usize, col: usize) {
+        let Some(part) = self.crop_cell(config, row, col) else {
+            return;
+        };
+        let rgba = part.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let image = arboard::ImageData {
+            width: width as usize,
+            height: height as usize,
+            bytes: std::borrow::Cow::Owned(rgba.into_raw()),
+        };
+        // 保留剪贴板实例：Linux 上实例销毁后其他程序可能无法再读取内容
+        let result = match self.clipboard.as_mut() {
+            Some(clipboard) => clipboard.set_image(image),
+            None => arboard::Clipboard::new().and_then(|mut clipboard| {
+                let result = clipboard.set_image(image);
+                self.clipboard = Some(clipboard);
+                result
+            }),
+        };
+        self.status_message = match result {
+            Ok(()) if part.color().has_alpha() => format!("已复制分块 ({}, {}) 到剪贴板 (含透明通道)", row + 1, col + 1),
+            Ok(()) => format!("已复制分块 ({}, {}) 到剪贴板", row + 1, col + 1),
+            Err(e) => format!("复制失败: {}", e),
+        };
+    }
+
+    /// 性能面板：帧率、纹理数量与估算显存、已解码图片占用，用于判断卡顿来自纹理还是解码
+    fn draw_debug_hud(&mut self, ctx: &egui::Context) {
+        self.frame_times.push_back(ctx.input(|i| i.unstable_dt));
+        while self.frame_times.len() > HUD_FRAME_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        let frame_time = self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32;
+
+        let thumbnail_bytes = self.thumbnails.bytes();
+        let other_textures: Vec<&egui::TextureHandle> = self
+            .current_texture
+            .iter()
+            .chain(self.tile_preview.as_ref().map(|(_, t)| t))
+            .chain(self.about_icon.as_ref())
+            .collect();
+        let texture_count = self.thumbnails.len() + other_textures.len();
+        let gpu_bytes = thumbnail_bytes + other_textures.iter().map(|t| thumbnail_cache::texture_bytes(t)).sum::<u64>();
+        let decoded_bytes = self.current_image.as_ref().map_or(0, |img| img.as_bytes().len() as u64);
+
+        let lines = [
+            format!("帧率    {:>6.1} fps ({:.1} ms)", 1.0 / frame_time.max(1e-6), frame_time * 1000.0),
+            format!("纹理    {:>6} 个, 约 {}", texture_count, format_bytes(gpu_bytes)),
+            format!(
+                "缩略图  {:>6} 个, 约 {} (上限 {} 个 / {} MB)",
+                self.thumbnails.len(),
+                format_bytes(thumbnail_bytes),
+                self.settings.max_thumbnails,
+                self.settings.thumbnail_memory_mb
+            ),
+            format!("已淘汰  {:>6} 个", self.evicted_thumbnails),
+            format!("已解码  {:>6} 张, 约 {}", usize::from(self.current_image.is_some()), format_bytes(decoded_bytes)),
+            format!("图片列表 {:>5} 张", self.image_paths.len()),
+        ];
+        egui::Area::new(egui::Id::new("debug_hud"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+            .interactable(false)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::none()
+                    .fill(egui::Color32::from_rgba_unmultiplied(17, 24, 39, 210))
+                    .rounding(6.0)
+                    .inner_margin(8.0)
+                    .show(ui, |ui| {
+                        for line in lines {
+                            ui.label(egui::RichText::new(line).monospace().size(12.0).color(egui::Color32::WHITE));
+                        }
+                        ui.label(egui::RichText::new(format!("{} 关闭", self.settings.key_bindings.get(Action::ToggleDebugHud)))
+                            .size(11.0).color(egui::Color32::GRAY));
+                    });
+            });
+        // 面板打开时持续重绘，帧率才有意义
+        ctx.request_repaint();
+    }
+
+    /// 将链接复制到剪贴板（浏览器无法打开时使用）
+    fn copy_link(&mut self, url: &str) {
+        let result = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(url.to_owned()));
+        self.copied_link = Some(match result {
+            Ok(()) => format!("已复制: {}", url),
+            Err(e) => format!("复制失败: {}", e),
+        });
+    }
+
+    fn check_for_updates(&self, ctx: egui::Context) {
+        let repo_url = self.obfuscated_repo_url.clone();
+        let current_version = env!("CARGO_PKG_VERSION").to_string();
+        let update_status = self.update_status.clone();
+        
+        // 设置状态为正在检查
+        {
+            if let Ok(mut status) = update_status.lock() {
+                *status = UpdateStatus::Checking;
+            }
+        }
+
+        // 转换 GitHub URL 到 API URL
+        let api_url = if repo_url.starts_with("http") {
+            repo_url.replace("github.com", "api.github.com/repos")
+        } else {
+            format!("https://{}", repo_url.replace("github.com", "api.github.com/repos"))
+        } + "/releases/latest";
 
         std::thread::spawn(move || {
             let agent = ureq::AgentBuilder::new()
@@ -476,11 +2299,265 @@ impl BatchImageSplitterApp {
     }
 }
 
+/// 状态栏中的加载提示，加载完成后按原文替换为结果
+fn loading_message(path: &std::path::Path) -> String {
+    format!("正在加载: {}", path.file_name().unwrap_or_default().to_string_lossy())
+}
+
+/// 原图解码完成前的占位纹理：与（校正方向后的）图片同宽高比的浅灰色小图
+fn placeholder_texture(ctx: &egui::Context, (width, height): (u32, u32)) -> egui::TextureHandle {
+    let scale = 64.0 / width.max(height).max(1) as f32;
+    let size = [
+        ((width as f32 * scale).round() as usize).max(1),
+        ((height as f32 * scale).round() as usize).max(1),
+    ];
+    let pixels = egui::ColorImage::new(size, egui::Color32::from_rgb(229, 231, 235));
+    ctx.load_texture("current_image", pixels, egui::TextureOptions::default())
+}
+
+/// 将预览图片上传为纹理
+fn current_image_texture(ctx: &egui::Context, img: &image::DynamicImage) -> egui::TextureHandle {
+    let size = [img.width() as usize, img.height() as usize];
+    let color_image = egui::ColorImage::from_rgba_unmultiplied(size, img.to_rgba8().as_raw());
+    ctx.load_texture("current_image", color_image, egui::TextureOptions::default())
+}
+
+/// 根据相对坐标确定所在分块 (行, 列)，分割线需已排序；落在间隔带中时返回 None
+fn cell_at(config: &SplitConfig, rel_x: f32, rel_y: f32) -> Option<(usize, usize)> {
+    let index = |lines: &[f32], rel: f32| {
+        config.line_mode.spans(lines).iter().position(|&(start, end)| rel >= start && rel <= end)
+    };
+    Some((index(&config.h_lines, rel_y)?, index(&config.v_lines, rel_x)?))
+}
+
+/// 归一化矩形与屏幕矩形互相换算
+fn norm_to_screen(image_rect: egui::Rect, norm: egui::Rect) -> egui::Rect {
+    egui::Rect::from_min_max(
+        image_rect.min + norm.min.to_vec2() * image_rect.size(),
+        image_rect.min + norm.max.to_vec2() * image_rect.size(),
+    )
+}
+
+fn screen_to_norm(image_rect: egui::Rect, pos: egui::Pos2) -> egui::Pos2 {
+    let rel = (pos - image_rect.min) / image_rect.size();
+    egui::pos2(rel.x.clamp(0.0, 1.0), rel.y.clamp(0.0, 1.0))
+}
+
+/// 去掉外边距后的内容区域在屏幕上的矩形，分割线和分块都相对于它换算
+fn content_screen_rect(image_rect: egui::Rect, config: &SplitConfig, image_size: Option<(u32, u32)>) -> egui::Rect {
+    let Some((width, height)) = image_size.filter(|&(w, h)| w > 0 && h > 0) else {
+        return image_rect;
+    };
+    let (x, y, w, h) = config.content_rect(width, height);
+    let (width, height) = (width as f32, height as f32);
+    norm_to_screen(
+        image_rect,
+        egui::Rect::from_min_size(
+            egui::pos2(x as f32 / width, y as f32 / height),
+            egui::vec2(w as f32 / width, h as f32 / height),
+        ),
+    )
+}
+
+/// 分块在屏幕上的矩形区域，行列超出范围时返回 None
+fn cell_rect(image_rect: egui::Rect, config: &SplitConfig, row: usize, col: usize) -> Option<egui::Rect> {
+    let (top, bottom) = config.line_mode.spans(&config.h_lines).get(row).copied()?;
+    let (left, right) = config.line_mode.spans(&config.v_lines).get(col).copied()?;
+    Some(egui::Rect::from_min_max(
+        image_rect.min + egui::vec2(image_rect.width() * left, image_rect.height() * top),
+        image_rect.min + egui::vec2(image_rect.width() * right, image_rect.height() * bottom),
+    ))
+}
+
+/// 把相对位置吸附到最近的 `step` 整数倍，返回 (位置, 是否吸附)；步长无效时不吸附
+///
+/// 吸附到 0 或 1 时保持原位置，避免分割线落在图片边缘上。
+fn snap_position(pos: f32, step: Option<f32>) -> (f32, bool) {
+    match step.filter(|s| *s > 0.0 && *s < 1.0) {
+        Some(step) => {
+            let snapped = (pos / step).round() * step;
+            if snapped > 0.0 && snapped < 1.0 {
+                (snapped, true)
+            } else {
+                (pos, false)
+            }
+        }
+        None => (pos, false),
+    }
+}
+
+/// 抓取分割线的命中距离（屏幕像素）
+///
+/// 按图片像素计为固定距离，再随显示比例缩放，并限制在可操作的范围内。
+fn line_grab_tolerance(display_scale: f32) -> f32 {
+    (LINE_GRAB_IMAGE_PX * display_scale).clamp(LINE_GRAB_MIN_PX, LINE_GRAB_MAX_PX)
+}
+
+/// 在 `rect` 中绘制棋盘格，用于显示图片的透明区域
+///
+/// 格子从 `rect` 左上角对齐，只绘制与 `visible` 相交的部分。
+fn paint_checkerboard(painter: &egui::Painter, rect: egui::Rect, visible: egui::Rect, cell: f32) {
+    let area = rect.intersect(visible);
+    if area.width() <= 0.0 || area.height() <= 0.0 || cell <= 0.0 {
+        return;
+    }
+    painter.rect_filled(area, 0.0, egui::Color32::WHITE);
+    let dark = egui::Color32::from_rgb(214, 217, 222);
+    let first_col = ((area.left() - rect.left()) / cell).floor() as i64;
+    let first_row = ((area.top() - rect.top()) / cell).floor() as i64;
+    let last_col = ((area.right() - rect.left()) / cell).ceil() as i64;
+    let last_row = ((area.bottom() - rect.top()) / cell).ceil() as i64;
+    for row in first_row..last_row {
+        for col in first_col..last_col {
+            if (row + col) % 2 == 0 {
+                continue;
+            }
+            let min = rect.min + egui::vec2(col as f32 * cell, row as f32 * cell);
+            let square = egui::Rect::from_min_size(min, egui::vec2(cell, cell)).intersect(area);
+            painter.rect_filled(square, 0.0, dark);
+        }
+    }
+}
+
+/// 距指针最近且在命中距离内的分割线
+fn nearest_line(rect: egui::Rect, config: &SplitConfig, pointer: egui::Pos2, tolerance: f32) -> Option<(LineType, usize)> {
+    let horizontal = config.h_lines.iter().enumerate().map(|(i, &pos)| {
+        ((LineType::Horizontal, i), (pointer.y - (rect.top() + rect.height() * pos)).abs())
+    });
+    let vertical = config.v_lines.iter().enumerate().map(|(i, &pos)| {
+        ((LineType::Vertical, i), (pointer.x - (rect.left() + rect.width() * pos)).abs())
+    });
+    horizontal
+        .chain(vertical)
+        .filter(|&(_, distance)| distance < tolerance)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(line, _)| line)
+}
+
+/// 同方向相邻分割线在屏幕上的最小间距，少于两条线时为 `None`
+fn min_line_spacing(lines: &[f32], length: f32) -> Option<f32> {
+    lines.windows(2).map(|w| (w[1] - w[0]).abs() * length).min_by(|a, b| a.partial_cmp(b).unwrap())
+}
+
+/// 模糊匹配：忽略大小写和空白，查询字符按顺序出现在文本中即匹配
+///
+/// 返回匹配字符在文本中的下标（按字符计），用于高亮。
+fn fuzzy_match(query: &str, text: &str) -> Option<Vec<usize>> {
+    let mut query = query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).peekable();
+    let mut matched = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        let Some(&q) = query.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(std::iter::once(q)) {
+            matched.push(i);
+            query.next();
+        }
+    }
+    query.peek().is_none().then_some(matched)
+}
+
+/// 把匹配到的字符加粗着色，其余字符正常显示
+///
+/// 普通字符使用占位颜色，由控件按选中/悬停状态决定实际颜色。
+fn highlight_matches(text: &str, matched: &[usize], font_id: egui::FontId) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let normal = egui::TextFormat::simple(font_id, egui::Color32::PLACEHOLDER);
+    let highlight = egui::TextFormat {
+        color: egui::Color32::from_rgb(13, 148, 136),
+        underline: egui::Stroke::new(1.0, egui::Color32::from_rgb(13, 148, 136)),
+        ..normal.clone()
+    };
+    let mut buf = [0u8; 4];
+    for (i, c) in text.chars().enumerate() {
+        let format = if matched.contains(&i) { highlight.clone() } else { normal.clone() };
+        job.append(c.encode_utf8(&mut buf), 0.0, format);
+    }
+    job
+}
+
+/// 扩展名属于支持的图片格式（与文件对话框的过滤一致）
+fn is_image_path(path: &std::path::Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+}
+
+/// 递归收集文件夹中的图片，按路径排序，`depth` 为 `dir` 相对扫描起点的层数
+///
+/// 跳过「输出到源图片旁」为同一文件夹中的图片生成的 `{名称}_split` 文件夹，避免把已导出的分块当作新图片；
+/// 其他以 `_split` 结尾的文件夹照常扫描。不进入指向文件夹的符号链接，以免重复收集或成环。
+/// 超过 [`MAX_SCAN_DEPTH`] 层的子文件夹不再进入，此时返回 `true`。
+fn collect_images(dir: &std::path::Path, ignore_hidden: bool, depth: usize, images: &mut Vec<PathBuf>) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    let mut entries: Vec<_> = entries.flatten().filter(|e| !(ignore_hidden && is_hidden(e))).collect();
+    entries.sort_by_key(|e| e.path());
+    let sources: Vec<PathBuf> = entries.iter().map(|e| e.path()).filter(|path| path.is_file() && is_image_path(path)).collect();
+    let output_dirs: Vec<String> = OutputLocation::AlongsideSource
+        .base_names(&sources)
+        .into_iter()
+        .map(|name| format!("{}_split", name))
+        .collect();
+    let mut too_deep = false;
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            if entry.file_type().is_ok_and(|t| t.is_symlink()) {
+                continue;
+            }
+            if output_dirs.iter().any(|name| entry.file_name() == name.as_str()) {
+                continue;
+            }
+            if depth >= MAX_SCAN_DEPTH {
+                too_deep = true;
+            } else {
+                too_deep |= collect_images(&path, ignore_hidden, depth + 1, images);
+            }
+        } else if is_image_path(&path) {
+            images.push(path);
+        }
+    }
+    too_deep
+}
+
+/// 以点开头或带有系统隐藏属性的文件
+fn is_hidden(entry: &std::fs::DirEntry) -> bool {
+    if entry.file_name().to_string_lossy().starts_with('.') {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if let Ok(metadata) = entry.metadata() {
+            return metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0;
+        }
+    }
+    false
+}
+
+/// 格式化字节数为易读的字符串
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
 /// 绘制卡片风格的容器
 fn draw_card<R>(
     ui: &mut egui::Ui,
     title: &str,
-    icon: &str,
+    icon: Icon,
     add_contents: impl FnOnce(&mut egui::Ui) -> R
 ) -> R {
     egui::Frame::none()
@@ -500,28 +2577,83 @@ fn draw_card<R>(
 }
 
 impl eframe::App for BatchImageSplitterApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.settings.output = OutputDefaults::capture(&self.config);
+        eframe::set_value(storage, SETTINGS_KEY, &self.settings);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_dialog(ctx);
+        self.poll_batch_status();
+        self.poll_image_load(ctx);
+        self.poll_thumbnails(ctx);
+        self.handle_dropped_files(ctx);
+
+        // 独立配置写入旁车文件，拖动分割线期间不写
+        if self.settings.sidecar_overrides && self.dragging_line.is_none() {
+            let errors = self.sidecars.sync(ctx, &self.image_paths, &self.config_overrides);
+            if let Some(error) = errors.first() {
+                self.status_message = format!("写入旁车文件失败 ({} 个): {}", errors.len(), error);
+            }
+        }
+
         // 快捷键处理
         let mut should_prev = false;
         let mut should_next = false;
         let mut should_open = false;
         let mut should_save = false;
         let mut should_process = false;
+        let mut should_process_current = false;
         let mut should_delete = false;
+        let mut should_toggle_hud = false;
+        let mut should_undo = false;
+        let mut should_redo = false;
+        let mut should_zoom_in = false;
+        let mut should_zoom_out = false;
         let mut h_adjust: Vec<(usize, f32)> = Vec::new();
         let mut v_adjust: Vec<(usize, f32)> = Vec::new();
         
+        let bindings = self.settings.key_bindings.clone();
+        let nudging = !self.selected_lines.is_empty();
+        // 录入快捷键期间不触发任何操作
+        let capturing = self.capturing_binding.is_some();
+        // 输入框有焦点时按键留给文本编辑，只保留打开和保存
+        let typing = ctx.wants_keyboard_input();
         ctx.input(|i| {
-            if i.key_pressed(egui::Key::Delete) {
-                should_delete = true;
-            }
-            if i.modifiers.ctrl {
-                if i.key_pressed(egui::Key::ArrowLeft) { should_prev = true; }
-                if i.key_pressed(egui::Key::ArrowRight) { should_next = true; }
-                if i.key_pressed(egui::Key::O) { should_open = true; }
-                if i.key_pressed(egui::Key::S) { should_save = true; }
-                if i.key_pressed(egui::Key::Enter) { should_process = true; }
-            } else if !self.selected_lines.is_empty() && !i.modifiers.ctrl {
+            if capturing { return; }
+            for action in Action::ALL {
+                let binding = bindings.get(action);
+                // 选中分割线时，无修饰方向键优先用于微调
+                if nudging && binding.is_plain_arrow() { continue; }
+                if typing && !matches!(action, Action::OpenFiles | Action::SaveConfig) { continue; }
+                if !binding.pressed(i) { continue; }
+                match action {
+                    Action::OpenFiles => should_open = true,
+                    Action::SaveConfig => should_save = true,
+                    Action::StartBatch => should_process = true,
+                    Action::ProcessCurrent => should_process_current = true,
+                    Action::PreviousImage => should_prev = true,
+                    Action::NextImage => should_next = true,
+                    Action::Delete => should_delete = true,
+                    Action::ToggleDebugHud => should_toggle_hud = true,
+                    Action::Undo => should_undo = true,
+                    Action::Redo => should_redo = true,
+                    Action::ZoomIn => should_zoom_in = true,
+                    Action::ZoomOut => should_zoom_out = true,
+                }
+            }
+            if typing { return; }
+            // Ctrl+= 作为放大的备用快捷键（英文键盘上 + 需要按 Shift）
+            let alt_zoom_in = KeyBinding::ctrl(egui::Key::Equals);
+            if alt_zoom_in.pressed(i) && Action::ALL.iter().all(|&a| bindings.get(a) != alt_zoom_in) {
+                should_zoom_in = true;
+            }
+            // Ctrl+Shift+Z 作为重做的备用快捷键（未被其他操作占用时）
+            let alt_redo = KeyBinding { shift: true, ..KeyBinding::ctrl(egui::Key::Z) };
+            if alt_redo.pressed(i) && Action::ALL.iter().all(|&a| bindings.get(a) != alt_redo) {
+                should_redo = true;
+            }
+            if nudging && !i.modifiers.ctrl {
                 let step = if i.modifiers.shift { 0.005 } else { 0.001 };
                 for (line_type, index) in &self.selected_lines {
                     match line_type {
@@ -540,23 +2672,27 @@ impl eframe::App for BatchImageSplitterApp {
         
         if should_prev { self.show_previous_image(ctx); }
         if should_next { self.show_next_image(ctx); }
-        if should_open {
-            if let Some(paths) = rfd::FileDialog::new()
-                .add_filter("图片", &["jpg", "jpeg", "png", "bmp", "gif"])
-                .pick_files()
-            {
-                for path in paths { self.image_paths.push(path); }
-                if self.current_texture.is_none() && !self.image_paths.is_empty() {
-                    self.load_image(ctx, &self.image_paths[0].clone());
-                }
-            }
-        }
+        if should_open { self.open_dialog(DialogKind::ImageFiles); }
         if should_save { self.save_config(); }
-        if should_process { self.start_batch_process(); }
+        if should_toggle_hud {
+            self.show_debug_hud = !self.show_debug_hud;
+            self.frame_times.clear();
+        }
+        if should_process { self.request_batch_process(); }
+        if should_process_current { self.process_current_image(ctx); }
+        if should_undo { self.step_line_history(ctx, true); }
+        if should_redo { self.step_line_history(ctx, false); }
+        if self.current_texture.is_some() {
+            if should_zoom_in { self.step_zoom(true); }
+            if should_zoom_out { self.step_zoom(false); }
+        }
         
         if should_delete && !self.selected_lines.is_empty() {
+            let before = self.active_snapshot();
             // 根据是否有独立配置来选择配置源
-            if let Some(config) = self.config_overrides.get_mut(&self.current_index) {
+            if !self.ensure_unlocked() {
+                // 已锁定，不删除
+            } else if let Some(config) = self.config_overrides.get_mut(&self.current_index) {
                 // 修改独立配置
                 let mut h_to_delete: Vec<usize> = self.selected_lines.iter()
                     .filter(|(t, _)| *t == LineType::Horizontal)
@@ -566,9 +2702,9 @@ impl eframe::App for BatchImageSplitterApp {
                     .filter(|(t, _)| *t == LineType::Vertical)
                     .map(|(_, i)| *i).collect();
                 v_to_delete.sort_by(|a, b| b.cmp(a));
-                for idx in h_to_delete { if idx < config.h_lines.len() { config.h_lines.remove(idx); } }
+                for idx in h_to_delete { config.remove_line(true, idx); }
                 config.rows = config.h_lines.len() + 1;
-                for idx in v_to_delete { if idx < config.v_lines.len() { config.v_lines.remove(idx); } }
+                for idx in v_to_delete { config.remove_line(false, idx); }
                 config.cols = config.v_lines.len() + 1;
                 self.status_message = "已删除选中分割线 (独立配置)".to_string();
             } else {
@@ -581,30 +2717,45 @@ impl eframe::App for BatchImageSplitterApp {
                     .filter(|(t, _)| *t == LineType::Vertical)
                     .map(|(_, i)| *i).collect();
                 v_to_delete.sort_by(|a, b| b.cmp(a));
-                for idx in h_to_delete { if idx < self.config.h_lines.len() { self.config.h_lines.remove(idx); } }
+                for idx in h_to_delete { self.config.remove_line(true, idx); }
                 self.config.rows = self.config.h_lines.len() + 1;
-                for idx in v_to_delete { if idx < self.config.v_lines.len() { self.config.v_lines.remove(idx); } }
+                for idx in v_to_delete { self.config.remove_line(false, idx); }
                 self.config.cols = self.config.v_lines.len() + 1;
                 self.status_message = "已删除选中分割线 (共享配置已同步)".to_string();
             }
+            self.commit_undo(before);
             self.selected_lines.clear();
+        } else if should_delete && self.settings.delete_removes_image && !self.image_paths.is_empty() {
+            if self.settings.confirm_image_removal {
+                self.pending_remove = Some(self.current_index);
+            } else {
+                self.remove_image(ctx, self.current_index);
+            }
         }
         
         // 微调逻辑
+        if (!h_adjust.is_empty() || !v_adjust.is_empty()) && !self.ensure_unlocked() {
+            h_adjust.clear();
+            v_adjust.clear();
+        }
+        let before = (!h_adjust.is_empty() || !v_adjust.is_empty()).then(|| self.active_snapshot());
         for (index, delta) in h_adjust {
             if let Some(config) = self.config_overrides.get_mut(&self.current_index) {
-                if let Some(line) = config.h_lines.get_mut(index) { *line = (*line + delta).max(0.0).min(1.0); }
+                if let Some(line) = config.h_lines.get_mut(index) { *line = (*line + delta).clamp(0.0, 1.0); }
             } else {
-                if let Some(line) = self.config.h_lines.get_mut(index) { *line = (*line + delta).max(0.0).min(1.0); }
+                if let Some(line) = self.config.h_lines.get_mut(index) { *line = (*line + delta).clamp(0.0, 1.0); }
             }
         }
         for (index, delta) in v_adjust {
             if let Some(config) = self.config_overrides.get_mut(&self.current_index) {
-                if let Some(line) = config.v_lines.get_mut(index) { *line = (*line + delta).max(0.0).min(1.0); }
+                if let Some(line) = config.v_lines.get_mut(index) { *line = (*line + delta).clamp(0.0, 1.0); }
             } else {
-                if let Some(line) = self.config.v_lines.get_mut(index) { *line = (*line + delta).max(0.0).min(1.0); }
+                if let Some(line) = self.config.v_lines.get_mut(index) { *line = (*line + delta).clamp(0.0, 1.0); }
             }
         }
+        if let Some(before) = before {
+            self.commit_undo(before);
+        }
 
         // 1. 右侧控制面板
         egui::SidePanel::right("control_panel")
@@ -632,86 +2783,485 @@ impl eframe::App for BatchImageSplitterApp {
                             .rounding(8.0)
                         );
                         if file_btn.clicked() {
-                            if let Some(paths) = rfd::FileDialog::new()
-                                .add_filter("图片", &["jpg", "jpeg", "png", "bmp", "gif"])
-                                .pick_files()
+                            self.open_dialog(DialogKind::ImageFiles);
+                        }
+                        
+                        ui.add_space(8.0);
+                        
+                        // 选择文件夹按钮
+                        let folder_btn = ui.add_sized(
+                            [ui.available_width(), 40.0],
+                            egui::Button::new(
+                                egui::RichText::new(format!("{} 选择文件夹", icon::FOLDER)).size(13.0).color(egui::Color32::from_rgb(55, 65, 81))
+                            )
+                            .fill(egui::Color32::WHITE)
+                            .rounding(8.0)
+                            .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(209, 213, 219)))
+                        );
+                        if folder_btn.clicked() {
+                            self.open_dialog(DialogKind::InputFolder);
+                        }
+                        ui.checkbox(&mut self.settings.scan_subfolders, egui::RichText::new("包括子文件夹").size(12.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text(format!("选择文件夹时同时添加各级子文件夹中的图片（最多 {} 层，跳过 _split 输出文件夹）", MAX_SCAN_DEPTH));
+
+                        ui.add_space(4.0);
+                        if ui.add_enabled(
+                            !self.image_paths.is_empty(),
+                            egui::Button::new(format!("{} 导入分割线位置", icon::FILE_UPLOAD)).min_size(egui::vec2(ui.available_width(), 28.0)),
+                        )
+                        .on_hover_text("从 txt/csv 读取当前图片的分割线，每行一条，如 h:0.33 或 v,0.5（只写数值视为水平线）")
+                        .clicked()
+                        {
+                            self.open_dialog(DialogKind::LinePositions);
+                        }
+
+                        ui.add_space(4.0);
+                        ui.columns(2, |columns| {
+                            if columns[0].add(egui::Button::new(format!("{} 打开项目", icon::FOLDER_OPEN)).min_size(egui::vec2(columns[0].available_width(), 28.0)))
+                                .on_hover_text("恢复保存的图片列表、全局配置和独立配置，会替换当前列表")
+                                .clicked()
+                            {
+                                if self.has_unsaved_changes() {
+                                    self.confirm_open_project = true;
+                                } else {
+                                    self.open_dialog(DialogKind::OpenProject);
+                                }
+                            }
+                            if columns[1].add_enabled(
+                                !self.image_paths.is_empty(),
+                                egui::Button::new(format!("{} 保存项目", icon::SAVE)).min_size(egui::vec2(columns[1].available_width(), 28.0)),
+                            )
+                            .on_hover_text("保存图片列表、全局配置和每张图片的独立配置")
+                            .clicked()
                             {
-                                for path in paths { self.image_paths.push(path); }
-                                if self.current_texture.is_none() && !self.image_paths.is_empty() {
-                                    self.load_image(ctx, &self.image_paths[0].clone());
+                                self.open_dialog(DialogKind::SaveProject);
+                            }
+                        });
+
+                        ui.add_space(4.0);
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("新选择的图片:").size(12.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            ui.selectable_value(&mut self.replace_on_add, false, "追加");
+                            ui.selectable_value(&mut self.replace_on_add, true, "替换")
+                                .on_hover_text("替换时会清空当前列表及其独立配置");
+                        });
+                    });
+
+                    ui.add_space(12.0);
+
+                    // 分割设置卡片
+                    draw_card(ui, "分割设置", icon::SETTINGS, |ui| {
+                         // 行数设置
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("分割行数:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+                                let mut rows = active.rows;
+                                if ui.add(egui::DragValue::new(&mut rows).range(1..=10).speed(1)).changed() {
+                                    self.set_grid_size(rows, active.cols);
+                                }
+                            });
+                        });
+                        
+                        ui.add_space(8.0);
+                        
+                        // 列数设置
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("分割列数:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+                                let mut cols = active.cols;
+                                if ui.add(egui::DragValue::new(&mut cols).range(1..=10).speed(1)).changed() {
+                                    self.set_grid_size(active.rows, cols);
+                                }
+                            });
+                        });
+                        
+                        ui.add_space(8.0);
+
+                        // 锁定当前图片的分割线
+                        let locked = self.current_locked();
+                        let lock_text = if locked {
+                            format!("{} 已锁定，点击解锁", icon::LOCK)
+                        } else {
+                            format!("{} 锁定当前图片", icon::LOCK_OPEN)
+                        };
+                        let lock_btn = egui::Button::new(lock_text)
+                            .selected(locked)
+                            .min_size(egui::vec2(ui.available_width(), 28.0));
+                        if ui.add_enabled(!self.image_paths.is_empty(), lock_btn)
+                            .on_hover_text("锁定后拖拽、添加、删除和微调分割线都不会生效，避免误改已调好的图片")
+                            .clicked()
+                        {
+                            self.toggle_current_lock();
+                        }
+
+                        ui.add_space(4.0);
+
+                        // 配置文件导入导出
+                        ui.columns(2, |columns| {
+                            if columns[0].add(egui::Button::new(format!("{} 导入配置", icon::FILE_UPLOAD)).min_size(egui::vec2(columns[0].available_width(), 28.0)))
+                                .on_hover_text("从 JSON 文件读取分割线和输出选项，应用到当前图片正在使用的配置")
+                                .clicked()
+                            {
+                                self.open_dialog(DialogKind::ImportConfig);
+                            }
+                            if columns[1].add(egui::Button::new(format!("{} 导出配置", icon::FILE_DOWNLOAD)).min_size(egui::vec2(columns[1].available_width(), 28.0)))
+                                .on_hover_text("把当前图片正在使用的分割线和输出选项保存为 JSON 文件，便于下次复用")
+                                .clicked()
+                            {
+                                self.open_dialog(DialogKind::ExportConfig);
+                            }
+                        });
+
+                        ui.add_space(8.0);
+
+                        // 均分分割线，过小的图片自动减少行列数
+                        let equalize_btn = ui.add_enabled(
+                            self.current_image.is_some(),
+                            egui::Button::new(format!("均分分割线 (单元 ≥ {} px)", self.settings.min_cell_size))
+                                .min_size(egui::vec2(ui.available_width(), 28.0)),
+                        ).on_hover_text("按当前行列数平均分布，图片放不下时减少行列数；最小单元尺寸可在设置中修改");
+                        if equalize_btn.clicked() {
+                            self.equalize_lines();
+                        }
+
+                        ui.add_space(4.0);
+                        let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+                        let (rows, cols) = (active.h_lines.len() + 1, active.v_lines.len() + 1);
+                        if ui.add(egui::Button::new(format!("平均网格 ({}行 x {}列)", rows, cols)).min_size(egui::vec2(ui.available_width(), 28.0)))
+                            .on_hover_text("保持当前行列数，把拖动过的分割线恢复为均匀分布")
+                            .clicked()
+                        {
+                            self.make_even_grid();
+                        }
+
+                        ui.add_space(8.0);
+
+                        // 当前图片的分块数，随分割线增删实时更新
+                        let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+                        let mode = self.config.line_mode;
+                        let cell_count = mode.spans(&active.h_lines).len() * mode.spans(&active.v_lines).len();
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("分块数:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if cell_count > CELL_COUNT_WARNING {
+                                    ui.label(egui::RichText::new(format!("{} {} 块", icon::WARNING, cell_count))
+                                        .size(14.0).strong().color(egui::Color32::from_rgb(220, 38, 38)))
+                                        .on_hover_text(format!(
+                                            "分块数超过 {}，每张图片都会输出 {} 个文件，批量处理时文件数量会非常多",
+                                            CELL_COUNT_WARNING, cell_count
+                                        ));
+                                } else {
+                                    ui.label(egui::RichText::new(format!("{} 块", cell_count))
+                                        .size(14.0).strong().color(egui::Color32::from_rgb(31, 41, 55)));
                                 }
+                            });
+                        });
+
+                        ui.add_space(8.0);
+
+                        // 分割线含义
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("分割线:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            egui::ComboBox::from_id_source("line_mode")
+                                .selected_text(self.config.line_mode.label())
+                                .show_ui(ui, |ui| {
+                                    for mode in LineMode::ALL {
+                                        ui.selectable_value(&mut self.config.line_mode, mode, mode.label());
+                                    }
+                                })
+                                .response
+                                .on_hover_text("共用边界：相邻分块共享边缘、互不重叠\n裁切线 + 出血：每个分块越过内部裁切线多取出血像素，用于印刷\n成对间隔带：每两条线之间是丢弃的留白，只导出内容区域\n无损拼回：分块宽高之和恰好等于原图，余数像素分给前面的分块，可原样拼回");
+                            if self.config.line_mode == LineMode::TrimWithBleed {
+                                ui.add(egui::DragValue::new(&mut self.config.bleed_px).range(0..=500).suffix(" px"));
                             }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("分块内缩:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            ui.add(egui::DragValue::new(&mut self.config.gutter_px).range(0..=500).suffix(" px"))
+                                .on_hover_text("每个分块在与相邻分块相接的边上向内裁掉的像素，图片外边缘不裁；分块过小时至少保留 1 像素");
+                        });
+                        ui.add_space(8.0);
+
+                        // 外边距：分割前先裁掉，分割线相对于剩下的内容区域
+                        ui.label(egui::RichText::new("边距:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text("分割前先裁掉图片四周的边框（如扫描件的黑边），分割线按剩下的内容区域定位");
+                        let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+                        let mut margins = [active.margin_top, active.margin_bottom, active.margin_left, active.margin_right];
+                        let mut margins_changed = false;
+                        for pair in [[("上", 0), ("下", 1)], [("左", 2), ("右", 3)]] {
+                            ui.horizontal(|ui| {
+                                for (label, i) in pair {
+                                    ui.label(egui::RichText::new(label).size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                    margins_changed |= ui
+                                        .add(egui::DragValue::new(&mut margins[i]).range(0..=100_000).suffix(" px"))
+                                        .changed();
+                                }
+                            });
+                        }
+                        if margins_changed && self.ensure_unlocked() {
+                            let config = self.active_config_mut();
+                            [config.margin_top, config.margin_bottom, config.margin_left, config.margin_right] = margins;
                         }
-                        
                         ui.add_space(8.0);
-                        
-                        // 选择文件夹按钮
-                        let folder_btn = ui.add_sized(
-                            [ui.available_width(), 40.0],
-                            egui::Button::new(
-                                egui::RichText::new(format!("{} 选择文件夹", icon::FOLDER)).size(13.0).color(egui::Color32::from_rgb(55, 65, 81))
-                            )
-                            .fill(egui::Color32::WHITE)
-                            .rounding(8.0)
-                            .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(209, 213, 219)))
-                        );
-                        if folder_btn.clicked() {
-                            if let Some(folder) = rfd::FileDialog::new().pick_folder() {
-                                if let Ok(entries) = std::fs::read_dir(&folder) {
-                                    for entry in entries.flatten() {
-                                        let path = entry.path();
-                                        if let Some(ext) = path.extension() {
-                                            let ext = ext.to_string_lossy().to_lowercase();
-                                            if matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "bmp" | "gif") {
-                                                self.image_paths.push(path);
-                                            }
-                                        }
-                                    }
+
+                        // 单条选中分割线的标注与不透明度
+                        if let [(line_type, idx)] = self.selected_lines[..] {
+                            let horizontal = line_type == LineType::Horizontal;
+                            let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+                            let line_count = if horizontal { active.h_lines.len() } else { active.v_lines.len() };
+                            if idx < line_count {
+                                let before = active.line_style(horizontal, idx).clone();
+                                let mut style = before.clone();
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("线标注:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                    ui.add(egui::TextEdit::singleline(&mut style.label).hint_text("如 页眉、页脚").desired_width(f32::INFINITY));
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("不透明度:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                    ui.add(egui::Slider::new(&mut style.opacity, 0.1..=1.0).fixed_decimals(1))
+                                        .on_hover_text("调暗已确定的分割线，选中时仍以完全不透明显示");
+                                });
+                                if style != before {
+                                    self.active_config_mut().set_line_style(horizontal, idx, style);
                                 }
-                                if self.current_texture.is_none() && !self.image_paths.is_empty() {
-                                    self.load_image(ctx, &self.image_paths[0].clone());
+                                ui.add_space(8.0);
+                            }
+                        }
+
+                        // 对齐选中的分割线（最后选中的为参考线）
+                        if let [_, .., (ref_type, ref_idx)] = self.selected_lines[..] {
+                            let ref_name = match ref_type {
+                                LineType::Horizontal => format!("水平线 {}", ref_idx + 1),
+                                LineType::Vertical => format!("垂直线 {}", ref_idx + 1),
+                            };
+                            if ui.add(egui::Button::new(format!("对齐到 {}", ref_name)).min_size(egui::vec2(ui.available_width(), 28.0)))
+                                .on_hover_text("以最后选中的分割线为参考线，把选中的另一方向分割线移到相同的比例位置，便于制作对称网格")
+                                .clicked()
+                            {
+                                self.align_selected_lines();
+                            }
+                            ui.add_space(8.0);
+                        }
+
+                        // 自动网格：按目标分块数选择最贴合当前图片宽高比的行列数
+                        let auto_grid = self.current_image.as_ref()
+                            .map(|img| ImageSplitter::best_grid(self.auto_grid_target, img.width(), img.height(), 10));
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("目标块数:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            ui.add(egui::DragValue::new(&mut self.auto_grid_target).range(1..=100).speed(1));
+                            if let Some((rows, cols)) = auto_grid {
+                                ui.label(egui::RichText::new(format!("→ {}行 x {}列", rows, cols)).size(12.0).color(egui::Color32::GRAY));
+                            }
+                        });
+                        ui.add_space(4.0);
+                        let auto_btn = ui.add_enabled(
+                            auto_grid.is_some(),
+                            egui::Button::new(format!("自动网格 ({} 块)", self.auto_grid_target)).min_size(egui::vec2(ui.available_width(), 28.0)),
+                        ).on_hover_text("根据当前图片的宽高比选择行列数，使每个分块尽量接近正方形");
+                        if auto_btn.clicked() {
+                            if let Some((rows, cols)) = auto_grid {
+                                if self.set_grid_size(rows, cols) {
+                                    self.status_message = format!("自动网格: {}行 x {}列 (共 {} 块)", rows, cols, rows * cols);
                                 }
                             }
                         }
-                    });
 
-                    ui.add_space(12.0);
+                        ui.add_space(8.0);
 
-                    // 分割设置卡片
-                    draw_card(ui, "分割设置", icon::SETTINGS, |ui| {
-                         // 行数设置
+                        // 输出格式
                         ui.horizontal(|ui| {
-                            ui.label(egui::RichText::new("分割行数:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            ui.label(egui::RichText::new("输出格式:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
                             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                let mut rows = self.config.rows;
-                                if ui.add(egui::DragValue::new(&mut rows).range(1..=10).speed(1)).changed() {
-                                    self.config.rows = rows;
-                                    self.config.reset_to_default();
-                                }
+                                egui::ComboBox::from_id_source("output_format")
+                                    .selected_text(self.config.output_format.label())
+                                    .show_ui(ui, |ui| {
+                                        for format in OutputFormat::ALL {
+                                            ui.selectable_value(&mut self.config.output_format, format, format.label());
+                                        }
+                                    });
                             });
                         });
-                        
-                        ui.add_space(8.0);
-                        
-                        // 列数设置
+                        ui.add_space(4.0);
+                        // 只显示当前格式的参数，其余格式的参数保留不变
+                        match self.config.output_format {
+                            OutputFormat::Jpeg => {
+                                ui.add(egui::Slider::new(&mut self.config.jpeg_quality, 1..=100).text("JPEG 质量"))
+                                    .on_hover_text(format!("默认 {}，数值越高画质越好、文件越大", DEFAULT_JPEG_QUALITY));
+                            }
+                            OutputFormat::Png => {
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("PNG 压缩:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                    for level in PngCompression::ALL {
+                                        ui.selectable_value(&mut self.config.png_compression, level, level.label());
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("PNG 过滤:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                    egui::ComboBox::from_id_source("png_filter")
+                                        .selected_text(self.config.png_filter.label())
+                                        .show_ui(ui, |ui| {
+                                            for filter in PngFilter::ALL {
+                                                ui.selectable_value(&mut self.config.png_filter, filter, filter.label());
+                                            }
+                                        });
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.checkbox(&mut self.config.png_quantize, egui::RichText::new("调色板量化").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                                        .on_hover_text("保存为 8 位调色板 PNG，适合纯色素材，可明显减小体积");
+                                    ui.add_enabled(
+                                        self.config.png_quantize,
+                                        egui::DragValue::new(&mut self.config.png_max_colors).range(2..=256).suffix(" 色"),
+                                    );
+                                });
+                            }
+                            OutputFormat::Bmp => {
+                                ui.label(egui::RichText::new("无压缩，文件较大").size(12.0).color(egui::Color32::from_rgb(107, 114, 128)));
+                            }
+                            OutputFormat::WebP => {
+                                ui.checkbox(&mut self.config.webp_lossless, egui::RichText::new("无损编码").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                                    .on_hover_text("关闭后按质量有损压缩，适合发布到网页；与 JPEG 共用质量设置");
+                                if !self.config.webp_lossless {
+                                    ui.add(egui::Slider::new(&mut self.config.jpeg_quality, 1..=100).text("WebP 质量"))
+                                        .on_hover_text(format!("默认 {}，数值越高画质越好、文件越大", DEFAULT_JPEG_QUALITY));
+                                }
+                            }
+                        }
+                        ui.add_space(4.0);
+                        // 分块命名方式
                         ui.horizontal(|ui| {
-                            ui.label(egui::RichText::new("分割列数:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
-                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                let mut cols = self.config.cols;
-                                if ui.add(egui::DragValue::new(&mut cols).range(1..=10).speed(1)).changed() {
-                                    self.config.cols = cols;
-                                    self.config.reset_to_default();
+                            ui.label(egui::RichText::new("命名方式:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            egui::ComboBox::from_id_source("naming_scheme")
+                                .selected_text(self.config.naming_scheme.label())
+                                .show_ui(ui, |ui| {
+                                    for scheme in NamingScheme::ALL {
+                                        ui.selectable_value(&mut self.config.naming_scheme, scheme, scheme.label());
+                                    }
+                                })
+                                .response
+                                .on_hover_text("行_列：名称_行_列\nz/x/y 文件夹：名称/z/x/y，用于瓦片地图\nZ 序索引：名称_序号，按 Morton 顺序编号\n自定义模板：按下方模板生成\nz/x/y 与 Z 序只适用于均匀网格");
+                        });
+                        if self.config.naming_scheme == NamingScheme::Template {
+                            ui.horizontal(|ui| {
+                                ui.label(egui::RichText::new("模板:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                ui.add(egui::TextEdit::singleline(&mut self.config.filename_template).desired_width(f32::INFINITY))
+                                    .on_hover_text("占位符: {name} 源文件名, {row} 行, {col} 列, {index} 序号, {ext} 扩展名\n不含 {ext} 时自动追加扩展名");
+                            });
+                            // 以当前图片的第一个分块为例
+                            let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+                            let (rows, cols) = active.grid_size();
+                            let (name, ext) = match self.image_paths.get(self.current_index) {
+                                Some(path) => (
+                                    ImageSplitter::output_base_name(&self.image_paths, self.current_index),
+                                    self.config.output_format_for(path).1,
+                                ),
+                                None => ("image".to_string(), self.config.output_format.extension()),
+                            };
+                            let example = render_file_template(&self.config.filename_template, |token| match token {
+                                "name" => name.clone(),
+                                "row" => padded_index(1, rows),
+                                "col" => padded_index(1, cols),
+                                "index" => padded_index(1, rows * cols),
+                                _ => ext.to_string(),
+                            });
+                            let (text, color) = match example.and_then(|example| self.config.validate_template().map(|_| example)) {
+                                Ok(example) => (format!("示例: {}", example), egui::Color32::from_rgb(107, 114, 128)),
+                                Err(e) => (format!("{} {}", icon::WARNING, e), egui::Color32::from_rgb(220, 38, 38)),
+                            };
+                            ui.label(egui::RichText::new(text).size(12.0).color(color));
+                        } else if self.config.naming_scheme != NamingScheme::RowCol {
+                            let active = self.config_overrides.get(&self.current_index).unwrap_or(&self.config);
+                            if !active.is_uniform_grid() || !active.cell_merges.is_empty() {
+                                ui.label(egui::RichText::new(format!("{} 当前网格不均匀或含合并分块，将改用 行_列 命名", icon::WARNING))
+                                    .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                            }
+                        }
+                        // 编号顺序
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("编号顺序:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            for order in TileOrder::ALL {
+                                ui.radio_value(&mut self.config.tile_order, order, order.label());
+                            }
+                        })
+                        .response
+                        .on_hover_text("决定模板中 {index} 的编号方式和分块的写入顺序");
+                        ui.checkbox(&mut self.config.per_image_subfolder, egui::RichText::new("按图片分文件夹").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text("每张图片的分块写入输出目录下以图片名称命名的子文件夹，同名图片自动追加序号");
+                        ui.add_space(4.0);
+                        // 多尺寸导出
+                        ui.checkbox(&mut self.config.multi_scale, egui::RichText::new("多尺寸导出").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text("每个分块按下列倍数各保存一份，以分块像素尺寸为 1 倍，1 倍保持原文件名");
+                        if self.config.multi_scale {
+                            ui.horizontal_wrapped(|ui| {
+                                let mut remove = None;
+                                for (i, scale) in self.config.output_scales.iter_mut().enumerate() {
+                                    ui.add(egui::DragValue::new(scale).range(0.1..=10.0).speed(0.05).suffix("x"));
+                                    if ui.small_button(icon::CLOSE).on_hover_text("移除该倍数").clicked() {
+                                        remove = Some(i);
+                                    }
                                 }
+                                if let Some(i) = remove {
+                                    self.config.output_scales.remove(i);
+                                }
+                                if ui.small_button("+").on_hover_text("添加倍数").clicked() {
+                                    let next = self.config.output_scales.iter().copied().fold(0.0, f32::max) + 1.0;
+                                    self.config.output_scales.push(next);
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label(egui::RichText::new("后缀:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                ui.add(egui::TextEdit::singleline(&mut self.config.scale_suffix).desired_width(80.0))
+                                    .on_hover_text("{scale} 替换为倍数，例如 @{scale}x 得到 name@2x.png");
+                            });
+                        }
+                        ui.add_space(4.0);
+                        // 统一画布尺寸（精灵图打包用）
+                        ui.checkbox(&mut self.config.uniform_canvas, egui::RichText::new("统一画布尺寸").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text("每个分块居中放到相同尺寸的透明画布上，超出画布时等比缩小；\nJPEG 不支持透明，空白处为黑色，建议输出 PNG");
+                        if self.config.uniform_canvas {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut self.config.canvas_width).range(1..=16384).suffix(" px"));
+                                ui.label("x");
+                                ui.add(egui::DragValue::new(&mut self.config.canvas_height).range(1..=16384).suffix(" px"));
                             });
+                        }
+                        ui.add_space(4.0);
+                        // 背景参考色
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("背景色:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            ui.color_edit_button_srgb(&mut self.settings.background_color);
+                            let label = if self.eyedropper_active { "单击预览图取色…" } else { "吸管" };
+                            if ui.add_enabled(self.current_image.is_some(), egui::SelectableLabel::new(self.eyedropper_active, label))
+                                .on_hover_text("在预览图上单击一个像素，作为裁边和空白检测的背景参考色")
+                                .clicked()
+                            {
+                                self.eyedropper_active = !self.eyedropper_active;
+                            }
                         });
-                        
+                        ui.add_space(4.0);
+                        ui.checkbox(&mut self.config.skip_existing, egui::RichText::new("跳过已存在的分块").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text("用于续跑中断的批处理：输出文件已存在时不覆盖，全部分块都存在的图片不再解码");
+                        ui.checkbox(&mut self.config.embed_source_info, egui::RichText::new("写入来源信息").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text("在 JPEG/PNG 分块中记录源文件名和分块位置，例如 source=foo.png;cell=2,3");
+                        ui.checkbox(&mut self.config.keep_source_format, egui::RichText::new("保持源格式").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text("开启后每张图片的分块按源文件格式保存 (PNG 保留透明通道)，否则统一使用上面选择的输出格式");
+                        ui.checkbox(&mut self.config.export_overview, egui::RichText::new("导出标注总览图").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text("每张图片额外保存一张画出分割线的整图 (名称_grid)，便于核对分割方式");
+                        ui.add_enabled_ui(self.config.export_overview, |ui| {
+                            ui.indent("overview_labels", |ui| {
+                                ui.checkbox(&mut self.config.overview_labels, egui::RichText::new("标注行列号").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                                    .on_hover_text("在每个分块左上角写上「行,列」，分块太小放不下时省略");
+                            });
+                        });
+
                         ui.add_space(12.0);
                         
                         // 保存分割线位置按钮
                         let save_btn = ui.add_sized(
                             [ui.available_width(), 40.0],
                             egui::Button::new(
-                                egui::RichText::new(format!("{} 保存分割线位置", icon::SAVE)).size(13.0).strong().color(egui::Color32::WHITE)
+                                egui::RichText::new(format!("{} 保存分割线位置{}", icon::SAVE, if self.has_unsaved_changes() { " *" } else { "" })).size(13.0).strong().color(egui::Color32::WHITE)
                             )
                             .fill(egui::Color32::from_rgb(19, 78, 74)) // #134e4a
                             .rounding(8.0)
@@ -723,8 +3273,14 @@ impl eframe::App for BatchImageSplitterApp {
                         // 保存状态
                         ui.add_space(4.0);
                         ui.horizontal(|ui| {
-                             if let Some(ref cfg) = self.saved_config {
-                                ui.label(egui::RichText::new(format!("{} 已保存: {}行 x {}列", icon::CHECK, cfg.rows, cfg.cols))
+                             if self.saved_config.is_some() && self.has_unsaved_changes() {
+                                ui.label(egui::RichText::new(format!("{} 有未保存的修改", icon::WARNING))
+                                    .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                                if ui.small_button("恢复到已保存").clicked() {
+                                    self.revert_to_saved();
+                                }
+                            } else if let Some(ref cfg) = self.saved_config {
+                                ui.label(egui::RichText::new(format!("{} 已保存: {}行 x {}列, {} 个独立配置", icon::CHECK, cfg.rows, cfg.cols, self.saved_overrides.len()))
                                     .size(12.0).color(egui::Color32::from_rgb(34, 197, 94)));
                             } else {
                                 ui.label(egui::RichText::new(format!("{} 未保存分割线位置", icon::WARNING))
@@ -737,25 +3293,82 @@ impl eframe::App for BatchImageSplitterApp {
 
                     // 图片列表卡片
                     draw_card(ui, "图片列表", icon::PHOTO_LIBRARY, |ui| {
+                        // 搜索框：按文件名模糊筛选，回车跳到第一个结果
+                        let search = ui.add(
+                            egui::TextEdit::singleline(&mut self.search_query)
+                                .hint_text(format!("{} 搜索文件名", icon::SEARCH))
+                                .desired_width(f32::INFINITY),
+                        );
+                        let filtered = self.filtered_images();
+                        if search.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            if let Some(&(idx, _)) = filtered.first() {
+                                self.current_index = idx;
+                                let path = self.image_paths[idx].clone();
+                                self.load_image(ctx, &path);
+                            }
+                        }
+                        ui.add_space(4.0);
+
                         // 图片列表
-                        let paths_to_load: Vec<_> = self.image_paths.clone();
                         egui::Frame::none()
                             .fill(egui::Color32::from_rgb(249, 250, 251))
                             .rounding(6.0)
                             .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(229, 231, 235)))
                             .show(ui, |ui| {
+                                // 拖动排序：松开时把拖动的图片插到指针所在行的前面（上半部分）或后面（下半部分）
+                                let mut dropped = None;
                                 egui::ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
-                                    for (idx, path) in paths_to_load.iter().enumerate() {
-                                        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                                    for (idx, matched) in &filtered {
+                                        let idx = *idx;
+                                        let name = self.image_paths[idx].file_name().unwrap_or_default().to_string_lossy().to_string();
+                                        let font_id = egui::TextStyle::Body.resolve(ui.style());
+                                        let mut job = highlight_matches(&name, matched, font_id.clone());
+                                        let mut suffix = String::new();
+                                        let dup = self.duplicate_number(idx);
+                                        if dup > 1 {
+                                            suffix = format!(" (副本 {})", dup);
+                                        }
+                                        if self.config_overrides.get(&idx).is_some_and(|c| c.locked) {
+                                            suffix.push_str(&format!(" {}", icon::LOCK));
+                                        }
+                                        if self.image_has_unsaved_changes(idx) {
+                                            suffix.push_str(" *");
+                                        }
+                                        job.append(&suffix, 0.0, egui::TextFormat::simple(font_id, egui::Color32::PLACEHOLDER));
                                         let is_selected = idx == self.current_index;
-                                        let response = ui.selectable_label(is_selected, &name);
-                                        if response.clicked() {
+                                        let row = ui.dnd_drag_source(egui::Id::new(("image_list_row", idx)), idx, |ui| {
+                                            ui.selectable_label(is_selected, job)
+                                        });
+                                        if row.inner.clicked() {
                                             self.current_index = idx;
-                                            self.load_image(ctx, path);
+                                            let path = self.image_paths[idx].clone();
+                                            self.load_image(ctx, &path);
+                                        }
+
+                                        let row_rect = egui::Rect::from_x_y_ranges(ui.max_rect().x_range(), row.response.rect.y_range())
+                                            .expand2(egui::vec2(0.0, ui.spacing().item_spacing.y / 2.0));
+                                        let pointer = ui.ctx().pointer_interact_pos().filter(|p| row_rect.contains(*p));
+                                        if let (Some(pointer), Some(from)) = (pointer, egui::DragAndDrop::payload::<usize>(ui.ctx())) {
+                                            let before = pointer.y < row_rect.center().y;
+                                            let y = if before { row_rect.top() } else { row_rect.bottom() };
+                                            ui.painter().hline(row_rect.x_range(), y, egui::Stroke::new(2.0, egui::Color32::from_rgb(19, 78, 74)));
+                                            if ui.input(|i| i.pointer.any_released()) {
+                                                dropped = Some((*from, if before { idx } else { idx + 1 }));
+                                            }
                                         }
                                     }
                                 });
+                                if let Some((from, slot)) = dropped {
+                                    egui::DragAndDrop::clear_payload(ui.ctx());
+                                    self.move_image(from, slot);
+                                }
                             });
+
+                        if !self.search_query.trim().is_empty() {
+                            ui.add_space(4.0);
+                            ui.checkbox(&mut self.batch_only_filtered, format!("批量处理时只处理筛选出的 {} 张", filtered.len()))
+                                .on_hover_text("关闭时批量处理仍会处理列表中的全部图片");
+                        }
                         
                         ui.add_space(8.0);
                         
@@ -774,19 +3387,70 @@ impl eframe::App for BatchImageSplitterApp {
                         // 清除按钮和计数
                         ui.horizontal(|ui| {
                             if ui.add(egui::Button::new(format!("{} 清除", icon::DELETE)).small()).clicked() {
-                                self.image_paths.clear();
-                                self.current_index = 0;
-                                self.current_texture = None;
-                                self.current_image = None;
+                                self.clear_images_with_undo();
+                            }
+                            if ui.add_enabled(!self.image_paths.is_empty(), egui::Button::new(format!("{} 复制条目", icon::COPY)).small())
+                                .on_hover_text("复制当前图片为新条目，以便用另一种网格分割同一文件")
+                                .clicked()
+                            {
+                                self.duplicate_current_image(ctx);
                             }
                             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                ui.label(egui::RichText::new(format!("{} 张图片", self.image_paths.len())).size(12.0).color(egui::Color32::GRAY));
+                                let count = if self.search_query.trim().is_empty() {
+                                    format!("{} 张图片", self.image_paths.len())
+                                } else {
+                                    format!("{} / {} 张图片", filtered.len(), self.image_paths.len())
+                                };
+                                ui.label(egui::RichText::new(count).size(12.0).color(egui::Color32::GRAY));
                             });
                         });
                     });
 
                     ui.add_space(12.0);
+
+                    // 自动网格批处理：逐张检测背景色间隔带，代替共用的分割线
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.config.auto_detect_grid, egui::RichText::new("自动网格批处理").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                            .on_hover_text("批处理时对没有独立配置的图片逐张检测背景色间隔带并按其分割，适合风格相近但略有差异的联系表、精灵图；\n未检测到间隔带的图片使用全局分割线");
+                        ui.add_enabled_ui(self.config.auto_detect_grid, |ui| {
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                ui.add(egui::DragValue::new(&mut self.config.detect_sensitivity).range(0..=100))
+                                    .on_hover_text("越高越能容忍间隔带中的颜色偏差和杂点，过高可能把内容中的空白也当作间隔带");
+                                ui.label(egui::RichText::new("灵敏度").size(12.0).color(egui::Color32::GRAY));
+                            });
+                        });
+                    });
+                    ui.add_space(4.0);
                     
+                    // 批处理输出文件夹，选择一次后记住
+                    let alongside = self.settings.output_alongside_source;
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("输出文件夹:").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                        ui.add_enabled_ui(!alongside, |ui| ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("更改").clicked() {
+                                self.open_dialog(DialogKind::ChangeOutputFolder);
+                            }
+                            match &self.settings.last_output_dir {
+                                Some(dir) => {
+                                    let name = dir.file_name().map_or_else(|| dir.display().to_string(), |n| n.to_string_lossy().to_string());
+                                    let color = if dir.is_dir() { egui::Color32::from_rgb(75, 85, 99) } else { egui::Color32::from_rgb(251, 146, 60) };
+                                    ui.add(egui::Label::new(egui::RichText::new(name).size(12.0).color(color)).truncate())
+                                        .on_hover_text(if dir.is_dir() {
+                                            dir.display().to_string()
+                                        } else {
+                                            format!("{} (文件夹不存在，开始处理时重新选择)", dir.display())
+                                        });
+                                }
+                                None => {
+                                    ui.label(egui::RichText::new("未选择").size(12.0).color(egui::Color32::GRAY));
+                                }
+                            }
+                        }));
+                    });
+                    ui.checkbox(&mut self.settings.output_alongside_source, egui::RichText::new("输出到源图片旁").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)))
+                        .on_hover_text("每张图片的分块写入与其同目录的「名称_split」文件夹，不使用上面的输出文件夹");
+                    ui.add_space(4.0);
+
                     // 开始处理按钮
                     let process_btn = ui.add_sized(
                         [ui.available_width(), 48.0],
@@ -797,7 +3461,39 @@ impl eframe::App for BatchImageSplitterApp {
                         .rounding(10.0)
                     );
                     if process_btn.clicked() {
-                        self.start_batch_process();
+                        self.request_batch_process();
+                    }
+
+                    ui.add_space(4.0);
+                    // 只处理当前图片，输出到上次选择的文件夹
+                    ui.horizontal(|ui| {
+                        let folder_width = 32.0;
+                        let output_hint = if self.settings.output_alongside_source {
+                            "输出到源图片旁的 名称_split 文件夹".to_string()
+                        } else {
+                            match &self.settings.last_output_dir {
+                                Some(dir) => format!("输出到 {}", dir.display()),
+                                None => "首次使用时选择输出文件夹".to_string(),
+                            }
+                        };
+                        if ui.add_enabled(
+                            !self.image_paths.is_empty(),
+                            egui::Button::new(format!("{} 只处理当前图片", icon::PLAY_ARROW))
+                                .min_size(egui::vec2(ui.available_width() - folder_width - ui.spacing().item_spacing.x, 28.0)),
+                        ).on_hover_text(output_hint).clicked() {
+                            self.process_current_image(ui.ctx());
+                        }
+                        if ui.add_sized([folder_width, 28.0], egui::Button::new(icon::FOLDER_OPEN))
+                            .on_hover_text("选择输出文件夹并处理当前图片")
+                            .clicked()
+                        {
+                            self.open_dialog(DialogKind::CurrentImageOutputFolder);
+                        }
+                    });
+
+                    ui.add_space(4.0);
+                    if ui.add_sized([ui.available_width(), 28.0], egui::Button::new(format!("{} 校验配置", icon::CHECK))).clicked() {
+                        self.validate_configs();
                     }
                     
                     ui.add_space(12.0);
@@ -807,12 +3503,22 @@ impl eframe::App for BatchImageSplitterApp {
                     ui.add_space(4.0);
                     
                     let hint_color = egui::Color32::from_rgb(107, 114, 128);
-                    ui.label(egui::RichText::new("• Ctrl + O: 打开图片文件").size(11.5).color(hint_color));
-                    ui.label(egui::RichText::new("• Ctrl + S: 保存当前分割线配置").size(11.5).color(hint_color));
-                    ui.label(egui::RichText::new("• Ctrl + Enter: 开始批量处理").size(11.5).color(hint_color));
-                    ui.label(egui::RichText::new("• Ctrl + ← / →: 上一张 / 下一张").size(11.5).color(hint_color));
-                    ui.label(egui::RichText::new("• Delete: 删除选中的分割线").size(11.5).color(hint_color));
+                    let keys = &self.settings.key_bindings;
+                    for action in [Action::OpenFiles, Action::SaveConfig, Action::StartBatch, Action::ProcessCurrent] {
+                        ui.label(egui::RichText::new(format!("• {}: {}", keys.get(action), action.label())).size(11.5).color(hint_color));
+                    }
+                    ui.label(egui::RichText::new(format!(
+                        "• {} / {}: 上一张 / 下一张",
+                        keys.get(Action::PreviousImage),
+                        keys.get(Action::NextImage),
+                    )).size(11.5).color(hint_color));
+                    ui.label(egui::RichText::new(format!("• {}: 删除选中的分割线 (可在设置中启用移除图片)", keys.get(Action::Delete))).size(11.5).color(hint_color));
                     ui.label(egui::RichText::new("• 方向键: 微调选中分割线 (加Shift加速)").size(11.5).color(hint_color));
+                    ui.label(egui::RichText::new(format!(
+                        "• {} / {}: 放大 / 缩小预览",
+                        keys.get(Action::ZoomIn),
+                        keys.get(Action::ZoomOut),
+                    )).size(11.5).color(hint_color));
                     
                     ui.add_space(12.0);
                     
@@ -823,13 +3529,38 @@ impl eframe::App for BatchImageSplitterApp {
                         ui.label(egui::RichText::new(format!("{} 状态:", icon::INFO)).size(12.0).color(egui::Color32::from_rgb(19, 78, 74)));
                         ui.label(egui::RichText::new(&self.status_message).size(12.0).color(egui::Color32::from_rgb(75, 85, 99)));
                     });
+                    if self.show_progress {
+                        ui.add_space(4.0);
+                        ui.horizontal(|ui| {
+                            let cancelling = self.batch_cancel.load(Ordering::Relaxed);
+                            let button_width = 56.0;
+                            ui.add(
+                                egui::ProgressBar::new(self.progress)
+                                    .show_percentage()
+                                    .animate(!cancelling)
+                                    .desired_width(ui.available_width() - button_width - ui.spacing().item_spacing.x),
+                            );
+                            let label = if cancelling { "取消中" } else { "取消" };
+                            if ui.add_enabled(!cancelling, egui::Button::new(label).min_size(egui::vec2(button_width, 0.0)))
+                                .on_hover_text("不再开始新的图片，正在处理的图片会完成")
+                                .clicked()
+                            {
+                                self.batch_cancel.store(true, Ordering::Relaxed);
+                            }
+                        });
+                    }
                     
                     ui.add_space(12.0);
                     
-                    // 关于按钮
-                    if ui.button(format!("{} 关于软件", icon::INFO)).clicked() {
-                        self.show_about = true;
-                    }
+                    // 设置与关于按钮
+                    ui.horizontal(|ui| {
+                        if ui.button(format!("{} 设置", icon::SETTINGS)).clicked() {
+                            self.show_settings = true;
+                        }
+                        if ui.button(format!("{} 关于软件", icon::INFO)).clicked() {
+                            self.show_about = true;
+                        }
+                    });
                 });
             });
 
@@ -840,8 +3571,9 @@ impl eframe::App for BatchImageSplitterApp {
                 if let Some(texture) = self.current_texture.clone() {
                     let total_available = ui.available_rect_before_wrap();
                     
-                    // 划分 2/3 和 1/3
-                    let main_height = total_available.height() * 0.7;
+                    // 按设置划分预览区与缩略图栏，隐藏缩略图栏时预览占满整个区域
+                    let gallery_fraction = if self.settings.show_gallery { self.settings.gallery_fraction } else { 0.0 };
+                    let main_height = total_available.height() * (1.0 - gallery_fraction);
                     
                     let main_rect = egui::Rect::from_min_max(
                         total_available.min,
@@ -852,6 +3584,23 @@ impl eframe::App for BatchImageSplitterApp {
                         egui::pos2(total_available.min.x, total_available.min.y + main_height),
                         total_available.max
                     );
+                    
+                    // 可拖动的分隔条，用于调整缩略图栏高度
+                    if self.settings.show_gallery {
+                        let splitter_rect = egui::Rect::from_center_size(
+                            egui::pos2(gallery_rect.center().x, gallery_rect.top()),
+                            egui::vec2(gallery_rect.width(), 6.0),
+                        );
+                        let splitter = ui.interact(splitter_rect, ui.id().with("gallery_splitter"), egui::Sense::drag())
+                            .on_hover_cursor(egui::CursorIcon::ResizeVertical);
+                        if splitter.dragged() {
+                            let fraction = self.settings.gallery_fraction - splitter.drag_delta().y / total_available.height();
+                            self.settings.gallery_fraction = fraction.clamp(*AppSettings::GALLERY_FRACTION_RANGE.start(), *AppSettings::GALLERY_FRACTION_RANGE.end());
+                        }
+                        if splitter.hovered() || splitter.dragged() {
+                            ui.painter().hline(gallery_rect.x_range(), gallery_rect.top(), egui::Stroke::new(2.0, egui::Color32::from_rgb(19, 78, 74)));
+                        }
+                    }
 
                     // --- 主预览区域 (main_rect) ---
                     ui.allocate_ui_at_rect(main_rect, |ui| {
@@ -863,81 +3612,364 @@ impl eframe::App for BatchImageSplitterApp {
                         
                         let scale = (content_rect.width() / texture_size.x)
                             .min(content_rect.height() / texture_size.y);
-                        let display_size = texture_size * scale;
-                        self.image_display_scale = scale;
+
+                        // 滚轮以指针为中心缩放：缩放前后指针下的图片位置不变
+                        if ui.rect_contains_pointer(content_rect) {
+                            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+                            if let (Some(pointer), true) = (ui.input(|i| i.pointer.hover_pos()), scroll != 0.0) {
+                                let zoom = (self.zoom * (scroll * 0.002).exp()).clamp(MIN_PREVIEW_ZOOM, MAX_PREVIEW_ZOOM);
+                                let center = content_rect.center() + self.pan_offset;
+                                let new_center = pointer - (pointer - center) * (zoom / self.zoom);
+                                self.pan_offset = new_center - content_rect.center();
+                                self.zoom = zoom;
+                                self.status_message = format!("缩放: {:.0}%", zoom * 100.0);
+                            }
+                        }
+                        let display_size = texture_size * scale * self.zoom;
+                        // 放大后图片边缘不离开预览区，未放大时居中
+                        let max_pan = ((display_size - content_rect.size()) / 2.0).max(egui::Vec2::ZERO);
+                        self.pan_offset = self.pan_offset.clamp(-max_pan, max_pan);
+                        self.image_display_scale = scale * self.zoom;
 
                         let image_rect = egui::Rect::from_center_size(
-                            content_rect.center(),
+                            content_rect.center() + self.pan_offset,
                             display_size,
                         );
                         self.image_rect = Some(image_rect);
 
                         // 获取当前配置的副本以避免借用冲突
-                        let current_config = self.config_overrides.get(&self.current_index).cloned().unwrap_or_else(|| self.config.clone());
+                        let mut current_config = self.config_overrides.get(&self.current_index).cloned().unwrap_or_else(|| self.config.clone());
+                        // 分割线模式属于输出选项，导出时以全局设置为准，预览也保持一致
+                        current_config.line_mode = self.config.line_mode;
+                        // 分割线相对于去掉外边距后的内容区域
+                        let image_size = self.current_image.as_ref().map(|img| (img.width(), img.height()));
+                        let grid_rect = content_screen_rect(image_rect, &current_config, image_size);
+
+                        // 预览区右上角的间隙预览开关
+                        let toggle_rect = egui::Rect::from_min_size(
+                            egui::pos2(main_rect.right() - 96.0, main_rect.top() + 4.0),
+                            egui::vec2(88.0, 20.0),
+                        );
+                        let fit_rect = egui::Rect::from_min_size(
+                            egui::pos2(toggle_rect.left() - 124.0, toggle_rect.top()),
+                            egui::vec2(118.0, 20.0),
+                        );
+                        if ui.put(fit_rect, egui::Button::new(format!("适应窗口 ({:.0}%)", self.zoom * 100.0)).small())
+                            .on_hover_text("滚轮以指针为中心缩放，按住中键拖动平移；点击恢复为适应窗口")
+                            .clicked()
+                        {
+                            self.zoom = 1.0;
+                            self.pan_offset = egui::Vec2::ZERO;
+                            self.status_message = "缩放: 100% (适应窗口)".to_string();
+                        }
+                        let zoom_in_rect = egui::Rect::from_min_size(fit_rect.left_top() - egui::vec2(28.0, 0.0), egui::vec2(24.0, 20.0));
+                        let zoom_out_rect = zoom_in_rect.translate(egui::vec2(-28.0, 0.0));
+                        let keys = &self.settings.key_bindings;
+                        let (zoom_in_key, zoom_out_key) = (keys.get(Action::ZoomIn), keys.get(Action::ZoomOut));
+                        if ui.put(zoom_in_rect, egui::Button::new(icon::ZOOM_IN).small())
+                            .on_hover_text(format!("放大 ({})", zoom_in_key))
+                            .clicked()
+                        {
+                            self.step_zoom(true);
+                        }
+                        if ui.put(zoom_out_rect, egui::Button::new(icon::ZOOM_OUT).small())
+                            .on_hover_text(format!("缩小 ({})", zoom_out_key))
+                            .clicked()
+                        {
+                            self.step_zoom(false);
+                        }
+                        let rotate_right_rect = zoom_out_rect.translate(egui::vec2(-32.0, 0.0));
+                        let rotate_left_rect = rotate_right_rect.translate(egui::vec2(-28.0, 0.0));
+                        if ui.put(rotate_right_rect, egui::Button::new(icon::ROTATE_RIGHT).small())
+                            .on_hover_text("顺时针旋转 90°（仅当前图片，导出时同样旋转）")
+                            .clicked()
+                        {
+                            self.rotate_current_image(ui.ctx(), true);
+                        }
+                        if ui.put(rotate_left_rect, egui::Button::new(icon::ROTATE_LEFT).small())
+                            .on_hover_text("逆时针旋转 90°（仅当前图片，导出时同样旋转）")
+                            .clicked()
+                        {
+                            self.rotate_current_image(ui.ctx(), false);
+                        }
+                        let flip_v_rect = rotate_left_rect.translate(egui::vec2(-32.0, 0.0));
+                        let flip_h_rect = flip_v_rect.translate(egui::vec2(-28.0, 0.0));
+                        if ui.put(flip_v_rect, egui::Button::new(icon::FLIP_VERTICAL).small().selected(current_config.image_flip_v))
+                            .on_hover_text("上下翻转（仅当前图片，导出时同样翻转）")
+                            .clicked()
+                        {
+                            self.flip_current_image(ui.ctx(), false);
+                        }
+                        if ui.put(flip_h_rect, egui::Button::new(icon::FLIP).small().selected(current_config.image_flip_h))
+                            .on_hover_text("左右翻转（仅当前图片，导出时同样翻转）")
+                            .clicked()
+                        {
+                            self.flip_current_image(ui.ctx(), true);
+                        }
+                        if ui.put(toggle_rect, egui::SelectableLabel::new(self.show_gapped_preview, "间隙预览"))
+                            .on_hover_text("以带间隙的方式显示分割结果，便于检查分块是否正确")
+                            .clicked()
+                        {
+                            self.show_gapped_preview = !self.show_gapped_preview;
+                        }
 
-                        // 1. 绘制顶部尺子
+                        if self.current_orientation != Orientation::NoTransforms {
+                            let hint_rect = egui::Rect::from_min_size(
+                                egui::pos2(main_rect.left() + 8.0, main_rect.top() + 4.0),
+                                egui::vec2(main_rect.width() - 430.0, 20.0),
+                            );
+                            ui.allocate_ui_at_rect(hint_rect, |ui| {
+                                self.draw_orientation_hint(ui, current_config.apply_exif_orientation);
+                            });
+                        }
+
+                        // 1. 绘制顶部尺子（放大时停靠在预览区边缘）
+                        let view_rect = image_rect.intersect(content_rect);
                         let top_ruler_rect = egui::Rect::from_min_max(
-                            egui::pos2(image_rect.left(), image_rect.top() - ruler_size - 4.0),
-                            egui::pos2(image_rect.right(), image_rect.top() - 4.0)
+                            egui::pos2(grid_rect.left(), view_rect.top() - ruler_size - 4.0),
+                            egui::pos2(grid_rect.right(), view_rect.top() - 4.0)
                         );
-                        let top_resp = self.draw_ruler(ui, top_ruler_rect, false);
+                        let top_resp = self.draw_ruler(ui, top_ruler_rect, content_rect, false);
                         if top_resp.clicked() {
                             if let Some(pos) = top_resp.interact_pointer_pos() {
-                                let rel_x = (pos.x - image_rect.left()) / image_rect.width();
+                                let rel_x = (pos.x - grid_rect.left()) / grid_rect.width();
                                 self.add_line(LineType::Vertical, rel_x);
                             }
-                        }
-
-                        // 2. 绘制左侧尺子
-                        let left_ruler_rect = egui::Rect::from_min_max(
-                            egui::pos2(image_rect.left() - ruler_size - 4.0, image_rect.top()),
-                            egui::pos2(image_rect.left() - 4.0, image_rect.bottom())
-                        );
-                        let left_resp = self.draw_ruler(ui, left_ruler_rect, true);
-                        if left_resp.clicked() {
-                            if let Some(pos) = left_resp.interact_pointer_pos() {
-                                let rel_y = (pos.y - image_rect.top()) / image_rect.height();
-                                self.add_line(LineType::Horizontal, rel_y);
+                        }
+                        if top_resp.secondary_clicked() {
+                            if let Some(pos) = top_resp.interact_pointer_pos() {
+                                let rel_x = (pos.x - grid_rect.left()) / grid_rect.width();
+                                self.remove_nearest_line(LineType::Vertical, rel_x, RULER_REMOVE_TOLERANCE / grid_rect.width());
+                            }
+                        }
+
+                        // 2. 绘制左侧尺子
+                        let left_ruler_rect = egui::Rect::from_min_max(
+                            egui::pos2(view_rect.left() - ruler_size - 4.0, grid_rect.top()),
+                            egui::pos2(view_rect.left() - 4.0, grid_rect.bottom())
+                        );
+                        let left_resp = self.draw_ruler(ui, left_ruler_rect, content_rect, true);
+                        if left_resp.clicked() {
+                            if let Some(pos) = left_resp.interact_pointer_pos() {
+                                let rel_y = (pos.y - grid_rect.top()) / grid_rect.height();
+                                self.add_line(LineType::Horizontal, rel_y);
+                            }
+                        }
+                        if left_resp.secondary_clicked() {
+                            if let Some(pos) = left_resp.interact_pointer_pos() {
+                                let rel_y = (pos.y - grid_rect.top()) / grid_rect.height();
+                                self.remove_nearest_line(LineType::Horizontal, rel_y, RULER_REMOVE_TOLERANCE / grid_rect.height());
+                            }
+                        }
+
+                        // 按百分比精确添加分割线，放在预览区底部
+                        let input_rect = egui::Rect::from_min_size(
+                            egui::pos2(main_rect.left() + 8.0, main_rect.bottom() - 30.0),
+                            egui::vec2(main_rect.width() - 16.0, 24.0),
+                        );
+                        ui.allocate_ui_at_rect(input_rect, |ui| {
+                            ui.horizontal(|ui| {
+                                for line_type in [LineType::Horizontal, LineType::Vertical] {
+                                    let (label, input) = match line_type {
+                                        LineType::Horizontal => ("水平线", &mut self.h_line_input),
+                                        LineType::Vertical => ("垂直线", &mut self.v_line_input),
+                                    };
+                                    ui.label(egui::RichText::new(label).size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                    let edit = ui.add(egui::TextEdit::singleline(input).hint_text("0-100").desired_width(56.0));
+                                    ui.label(egui::RichText::new("%").size(13.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                    let submitted = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                    if ui.button("添加").clicked() || submitted {
+                                        self.add_line_at_percent(line_type);
+                                    }
+                                    // 回车添加后保持焦点，便于连续输入
+                                    if submitted {
+                                        edit.request_focus();
+                                    }
+                                    ui.add_space(12.0);
+                                }
+                            });
+                        });
+
+                        // 3. 绘制图片，放大后超出预览区的部分不绘制也不响应
+                        ui.set_clip_rect(content_rect.intersect(ui.clip_rect()));
+                        // 带透明通道的图片先铺棋盘格，便于分辨透明区域
+                        let has_alpha = self.current_image.as_ref().is_some_and(|img| img.color().has_alpha());
+                        let checker_cell = (CHECKER_IMAGE_PX * self.image_display_scale).clamp(CHECKER_MIN_PX, CHECKER_MAX_PX);
+                        if has_alpha && !self.show_gapped_preview {
+                            paint_checkerboard(ui.painter(), image_rect, ui.clip_rect(), checker_cell);
+                        }
+                        let response = ui.put(
+                            image_rect,
+                            egui::Image::new(&texture)
+                                .fit_to_exact_size(display_size)
+                                .sense(egui::Sense::click_and_drag()),
+                        );
+                        // 中键拖动平移
+                        if response.dragged_by(egui::PointerButton::Middle) {
+                            self.pan_offset += response.drag_delta();
+                        }
+
+                        // 间隙预览：按分块区域取纹理子区域，在带间隙的位置重新绘制
+                        if self.show_gapped_preview {
+                            if let Some(img) = self.current_image.as_ref() {
+                                let painter = ui.painter();
+                                painter.rect_filled(image_rect, 0.0, egui::Color32::from_rgb(243, 244, 246));
+                                let gap = 6.0;
+                                let (img_w, img_h) = (img.width() as f32, img.height() as f32);
+                                // 缩放分块位置，使加上间隙后仍处于原预览区域内
+                                let (rows, cols) = current_config.grid_size();
+                                let sx = (image_rect.width() - gap * (cols.saturating_sub(1)) as f32).max(1.0) / img_w;
+                                let sy = (image_rect.height() - gap * (rows.saturating_sub(1)) as f32).max(1.0) / img_h;
+                                for region in ImageSplitter::compute_regions(img.width(), img.height(), &current_config) {
+                                    let uv = egui::Rect::from_min_max(
+                                        egui::pos2(region.x as f32 / img_w, region.y as f32 / img_h),
+                                        egui::pos2((region.x + region.width) as f32 / img_w, (region.y + region.height) as f32 / img_h),
+                                    );
+                                    let min = image_rect.min
+                                        + egui::vec2(region.x as f32 * sx + region.col as f32 * gap, region.y as f32 * sy + region.row as f32 * gap);
+                                    let cell = egui::Rect::from_min_size(min, egui::vec2(region.width as f32 * sx, region.height as f32 * sy));
+                                    if has_alpha {
+                                        paint_checkerboard(painter, cell, painter.clip_rect(), checker_cell);
+                                    }
+                                    painter.image(texture.id(), cell, uv, egui::Color32::WHITE);
+                                }
+                            }
+                        }
+
+                        // 吸管：取当前图片在点击位置的像素作为背景参考色
+                        if self.eyedropper_active {
+                            let response = response.clone().on_hover_cursor(egui::CursorIcon::Crosshair);
+                            if response.clicked() {
+                                if let (Some(pos), Some(img)) = (response.interact_pointer_pos(), self.current_image.as_ref()) {
+                                    let rel = (pos - image_rect.min) / image_rect.size();
+                                    let x = ((rel.x.clamp(0.0, 1.0) * img.width() as f32) as u32).min(img.width().saturating_sub(1));
+                                    let y = ((rel.y.clamp(0.0, 1.0) * img.height() as f32) as u32).min(img.height().saturating_sub(1));
+                                    let image::Rgba([r, g, b, _]) = image::GenericImageView::get_pixel(img, x, y);
+                                    self.settings.background_color = [r, g, b];
+                                    self.eyedropper_active = false;
+                                    self.status_message = format!("已吸取背景色: #{:02X}{:02X}{:02X}", r, g, b);
+                                }
+                            }
+                        }
+
+                        // Ctrl+点击分块：加入或移出待合并的选择
+                        if response.clicked() && ui.input(|i| i.modifiers.command) && !self.eyedropper_active && !self.show_gapped_preview {
+                            if let Some(pos) = response.interact_pointer_pos() {
+                                let rel = (pos - grid_rect.min) / grid_rect.size();
+                                let on_line = nearest_line(grid_rect, &current_config, pos, line_grab_tolerance(self.image_display_scale)).is_some();
+                                if let Some(cell) = cell_at(&current_config, rel.x, rel.y).filter(|_| !on_line) {
+                                    match self.selected_cells.iter().position(|&c| c == cell) {
+                                        Some(i) => {
+                                            self.selected_cells.remove(i);
+                                        }
+                                        None => self.selected_cells.push(cell),
+                                    }
+                                    // 正在选择分块，放弃待确认的裁剪区域
+                                    self.pending_crop = None;
+                                    self.status_message = format!("已选中 {} 个分块，右键可合并", self.selected_cells.len());
+                                }
+                            }
+                        }
+
+                        // 右键分块：设置该分块的旋转
+                        if response.secondary_clicked() {
+                            if let Some(pos) = response.interact_pointer_pos() {
+                                let rel = (pos - grid_rect.min) / grid_rect.size();
+                                self.context_cell = cell_at(&current_config, rel.x, rel.y);
+                            }
+                        }
+                        response.context_menu(|ui| {
+                            let Some((row, col)) = self.context_cell else {
+                                ui.close_menu();
+                                return;
+                            };
+                            ui.label(egui::RichText::new(format!("分块 ({}, {})", row + 1, col + 1)).strong());
+                            if ui.button("预览此块").clicked() {
+                                self.preview_tile(ctx, &current_config, row, col);
+                                ui.close_menu();
+                            }
+                            if ui.button(format!("{} 复制到剪贴板", icon::COPY)).clicked() {
+                                self.copy_tile_to_clipboard(&current_config, row, col);
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            let current = current_config.cell_rotation(row, col);
+                            let mut choice = None;
+                            if ui.radio(current.is_none(), "不旋转").clicked() {
+                                choice = Some(None);
+                            }
+                            for rotation in Rotation::ALL {
+                                if ui.radio(current == Some(rotation), format!("{} 旋转 {}°", icon::ROTATE_RIGHT, rotation.degrees())).clicked() {
+                                    choice = Some(Some(rotation));
+                                }
+                            }
+                            if let Some(rotation) = choice {
+                                self.active_config_mut().set_cell_rotation(row, col, rotation);
+                                ui.close_menu();
+                            }
+                            let merged = current_config.merge_at(row, col).is_some();
+                            if self.selected_cells.len() >= 2 || merged {
+                                ui.separator();
+                            }
+                            if self.selected_cells.len() >= 2
+                                && ui.button(format!("合并选中的 {} 个分块", self.selected_cells.len())).clicked()
+                            {
+                                let cells = std::mem::take(&mut self.selected_cells);
+                                self.status_message = match self.active_config_mut().merge_cells(&cells) {
+                                    Ok(merge) => format!("已合并分块: {} 行 x {} 列，导出为一个文件", merge.rows, merge.cols),
+                                    Err(e) => {
+                                        self.selected_cells = cells;
+                                        format!("无法合并: {}", e)
+                                    }
+                                };
+                                ui.close_menu();
+                            }
+                            if merged && ui.button("取消合并").clicked() {
+                                self.active_config_mut().unmerge_at(row, col);
+                                self.status_message = "已取消合并".to_string();
+                                ui.close_menu();
                             }
-                        }
-
-                        // 3. 绘制图片
-                        let response = ui.put(
-                            image_rect,
-                            egui::Image::new(&texture)
-                                .fit_to_exact_size(display_size)
-                                .sense(egui::Sense::click_and_drag()),
-                        );
+                            if !current_config.crop_regions.is_empty() {
+                                ui.separator();
+                                if ui.button(format!("清除裁剪区域 ({})", current_config.crop_regions.len())).clicked() && self.ensure_unlocked() {
+                                    let before = self.active_snapshot();
+                                    self.active_config_mut().crop_regions.clear();
+                                    self.commit_undo(before);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
 
-                        // 处理拖拽分割线
-                        if let Some(rect) = self.image_rect {
-                            if response.drag_started() {
+                        // 处理拖拽分割线（吸管模式和间隙预览下不响应）
+                        if let Some(rect) = self.image_rect.filter(|_| !self.eyedropper_active && !self.show_gapped_preview) {
+                            if response.drag_started_by(egui::PointerButton::Primary) {
                                 if let Some(pointer_pos) = response.interact_pointer_pos() {
-                                    // 检查是否点击了已有的分割线
-                                    let mut found_line = None;
-                                    
-                                    // 检查水平线
-                                    for (i, &pos) in current_config.h_lines.iter().enumerate() {
-                                        let y = rect.top() + rect.height() * pos;
-                                        if (pointer_pos.y - y).abs() < 5.0 {
-                                            found_line = Some((LineType::Horizontal, i));
-                                            break;
+                                    // 优先检查待确认裁剪区域的角点
+                                    if let Some(crop) = self.pending_crop {
+                                        let screen = norm_to_screen(rect, crop);
+                                        let corners = [screen.left_top(), screen.right_top(), screen.left_bottom(), screen.right_bottom()];
+                                        if let Some(i) = corners.iter().position(|c| c.distance(pointer_pos) < 8.0) {
+                                            self.crop_drag_anchor = Some(screen_to_norm(rect, corners[3 - i]));
+                                        } else {
+                                            self.pending_crop = None;
                                         }
                                     }
+
+                                    // 检查是否点击了已有的分割线（多条线都在范围内时取最近的）
+                                    let tolerance = line_grab_tolerance(self.image_display_scale);
+                                    let found_line = nearest_line(grid_rect, &current_config, pointer_pos, tolerance);
                                     
-                                    // 如果没找到水平线，检查垂直线
-                                    if found_line.is_none() {
-                                        for (i, &pos) in current_config.v_lines.iter().enumerate() {
-                                            let x = rect.left() + rect.width() * pos;
-                                            if (pointer_pos.x - x).abs() < 5.0 {
-                                                found_line = Some((LineType::Vertical, i));
-                                                break;
-                                            }
+                                    if self.crop_drag_anchor.is_some() {
+                                        // 正在调整裁剪区域，不处理分割线和框选
+                                    } else if let Some(line_key) = found_line {
+                                        if self.ensure_unlocked() {
+                                            self.dragging_line = Some(line_key);
+                                            // 拖动总是写入独立配置
+                                            self.drag_snapshot = Some(LineSnapshot {
+                                                index: Some(self.current_index),
+                                                config: self.config_overrides.get(&self.current_index).cloned(),
+                                            });
                                         }
-                                    }
-                                    
-                                    if let Some(line_key) = found_line {
-                                        self.dragging_line = Some(line_key);
                                         // 确保拖拽的线被选中
                                         if !self.selected_lines.contains(&line_key) {
                                             if !ui.input(|i| i.modifiers.shift) {
@@ -957,59 +3989,57 @@ impl eframe::App for BatchImageSplitterApp {
                                 }
                             }
                             
+                            if let (Some(anchor), Some(pointer_pos)) = (self.crop_drag_anchor, response.interact_pointer_pos()) {
+                                self.pending_crop = Some(egui::Rect::from_two_pos(anchor, screen_to_norm(rect, pointer_pos)));
+                            }
+
                             if let Some((line_type, line_idx)) = self.dragging_line {
                                 if let Some(pointer_pos) = response.interact_pointer_pos() {
+                                    // 按住 Alt 时临时关闭吸附，便于精细调整
+                                    let snap_step = if ui.input(|i| i.modifiers.alt) { None } else { self.settings.snap_step };
                                     // 只要开始拖拽，就自动创建独立配置（如果还没有的话）
                                     let config = self.config_overrides.entry(self.current_index)
                                         .or_insert_with(|| self.config.clone());
                                     
-                                    match line_type {
-                                        LineType::Horizontal => {
-                                            if line_idx < config.h_lines.len() {
-                                                let new_pos = ((pointer_pos.y - rect.top()) / rect.height()).max(0.0).min(1.0);
-                                                config.h_lines[line_idx] = new_pos;
-                                                // 注意：这里不排序，否则索引会乱。排序应该在拖拽结束时进行。
-                                            }
-                                        }
-                                        LineType::Vertical => {
-                                            if line_idx < config.v_lines.len() {
-                                                let new_pos = ((pointer_pos.x - rect.left()) / rect.width()).max(0.0).min(1.0);
-                                                config.v_lines[line_idx] = new_pos;
-                                            }
-                                        }
+                                    let (lines, rel) = match line_type {
+                                        LineType::Horizontal => (&mut config.h_lines, (pointer_pos.y - grid_rect.top()) / grid_rect.height()),
+                                        LineType::Vertical => (&mut config.v_lines, (pointer_pos.x - grid_rect.left()) / grid_rect.width()),
+                                    };
+                                    if let Some(line) = lines.get_mut(line_idx) {
+                                        let (new_pos, snapped) = snap_position(rel.clamp(0.0, 1.0), snap_step);
+                                        // 注意：这里不排序，否则索引会乱。排序应该在拖拽结束时进行。
+                                        *line = new_pos;
+                                        self.drag_snapped = snapped;
                                     }
                                 }
                             }
                             
                             if response.drag_stopped() {
-                                if let Some((line_type, _)) = self.dragging_line {
-                                    // 拖拽结束，进行排序并重新计算行列
+                                self.crop_drag_anchor = None;
+                                if self.dragging_line.is_some() {
+                                    // 拖拽结束，规范化分割线（排序、去除与相邻线重叠的线）并重新计算行列
                                     if let Some(config) = self.config_overrides.get_mut(&self.current_index) {
-                                        match line_type {
-                                            LineType::Horizontal => {
-                                                config.h_lines.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                                                config.rows = config.h_lines.len() + 1;
-                                            }
-                                            LineType::Vertical => {
-                                                config.v_lines.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                                                config.cols = config.v_lines.len() + 1;
-                                            }
-                                        }
+                                        config.normalize();
+                                    }
+                                    if let Some(before) = self.drag_snapshot.take() {
+                                        self.commit_undo(before);
                                     }
                                     self.dragging_line = None;
+                                    self.drag_snapped = false;
                                     self.selected_lines.clear(); // 拖拽结束后清除选中，或者保留？通常保留更好，但为了简单先清除
                                 }
                                 
                                 if self.is_selecting {
+                                    let selected_before = self.selected_lines.len();
                                     if let (Some(start), Some(end)) = (self.selection_start, self.selection_end) {
                                         let selection_rect = egui::Rect::from_two_pos(start, end);
                                         
                                         // 检查水平分割线
                                         for (i, &pos) in current_config.h_lines.iter().enumerate() {
-                                            let y = rect.top() + rect.height() * pos;
+                                            let y = grid_rect.top() + grid_rect.height() * pos;
                                             let line_rect = egui::Rect::from_min_max(
-                                                egui::pos2(rect.left(), y - 3.0),
-                                                egui::pos2(rect.right(), y + 3.0)
+                                                egui::pos2(grid_rect.left(), y - 3.0),
+                                                egui::pos2(grid_rect.right(), y + 3.0)
                                             );
                                             if selection_rect.intersects(line_rect) {
                                                 let line_key = (LineType::Horizontal, i);
@@ -1021,10 +4051,10 @@ impl eframe::App for BatchImageSplitterApp {
                                         
                                         // 检查垂直分割线
                                         for (i, &pos) in current_config.v_lines.iter().enumerate() {
-                                            let x = rect.left() + rect.width() * pos;
+                                            let x = grid_rect.left() + grid_rect.width() * pos;
                                             let line_rect = egui::Rect::from_min_max(
-                                                egui::pos2(x - 3.0, rect.top()),
-                                                egui::pos2(x + 3.0, rect.bottom())
+                                                egui::pos2(x - 3.0, grid_rect.top()),
+                                                egui::pos2(x + 3.0, grid_rect.bottom())
                                             );
                                             if selection_rect.intersects(line_rect) {
                                                 let line_key = (LineType::Vertical, i);
@@ -1034,6 +4064,17 @@ impl eframe::App for BatchImageSplitterApp {
                                             }
                                         }
                                     }
+                                    // 框选足够大且没有选中分割线时保留为待确认的裁剪区域，需点击按钮才会添加
+                                    let selected_lines = self.selected_lines.len() > selected_before;
+                                    if let (Some(start), Some(end)) = (self.selection_start.filter(|_| !selected_lines), self.selection_end) {
+                                        let selection_rect = egui::Rect::from_two_pos(start, end).intersect(rect);
+                                        if selection_rect.width() > 8.0 && selection_rect.height() > 8.0 {
+                                            self.pending_crop = Some(egui::Rect::from_min_max(
+                                                screen_to_norm(rect, selection_rect.min),
+                                                screen_to_norm(rect, selection_rect.max),
+                                            ));
+                                        }
+                                    }
                                     self.is_selecting = false;
                                     self.selection_start = None;
                                     self.selection_end = None;
@@ -1047,58 +4088,278 @@ impl eframe::App for BatchImageSplitterApp {
                             }
                         }
 
-                        // 绘制分割线
-                        if let Some(rect) = self.image_rect {
+                        // 绘制分割线（间隙预览时隐藏）
+                        if let Some(rect) = self.image_rect.filter(|_| !self.show_gapped_preview) {
                             let painter = ui.painter();
                             
+                            // 分割线标注，带半透明底色以便在复杂图片上看清
+                            let draw_line_label = |pos: egui::Pos2, anchor: egui::Align2, label: &str, color: egui::Color32| {
+                                let galley = painter.layout_no_wrap(label.to_string(), egui::FontId::proportional(12.0), egui::Color32::WHITE);
+                                let label_rect = anchor.anchor_size(pos, galley.size());
+                                painter.rect_filled(label_rect.expand(3.0), 4.0, color);
+                                painter.galley(label_rect.min, galley, egui::Color32::WHITE);
+                            };
+
+                            // 间隔带模式下被丢弃的区域以半透明阴影标出
+                            let gutter_fill = egui::Color32::from_rgba_unmultiplied(17, 24, 39, 110);
+                            // 外边距同样不导出
+                            for margin in [
+                                egui::Rect::from_x_y_ranges(rect.x_range(), rect.top()..=grid_rect.top()),
+                                egui::Rect::from_x_y_ranges(rect.x_range(), grid_rect.bottom()..=rect.bottom()),
+                                egui::Rect::from_x_y_ranges(rect.left()..=grid_rect.left(), grid_rect.y_range()),
+                                egui::Rect::from_x_y_ranges(grid_rect.right()..=rect.right(), grid_rect.y_range()),
+                            ] {
+                                if margin.is_positive() {
+                                    painter.rect_filled(margin, 0.0, gutter_fill);
+                                }
+                            }
+                            for (start, end) in current_config.line_mode.gutters(&current_config.h_lines) {
+                                let band = egui::Rect::from_x_y_ranges(
+                                    grid_rect.x_range(),
+                                    (grid_rect.top() + grid_rect.height() * start)..=(grid_rect.top() + grid_rect.height() * end),
+                                );
+                                painter.rect_filled(band, 0.0, gutter_fill);
+                            }
+                            for (start, end) in current_config.line_mode.gutters(&current_config.v_lines) {
+                                let band = egui::Rect::from_x_y_ranges(
+                                    (grid_rect.left() + grid_rect.width() * start)..=(grid_rect.left() + grid_rect.width() * end),
+                                    grid_rect.y_range(),
+                                );
+                                painter.rect_filled(band, 0.0, gutter_fill);
+                            }
+
+                            // 精确模式：半透明的抗锯齿线带 + 1 物理像素的参考线，参考线画在实际裁切的像素边界上
+                            let precise = self.settings.precise_line_guides;
+                            let hairline = 1.0 / ui.ctx().pixels_per_point();
+                            let content_size = image_size.map(|(w, h)| {
+                                let (_, _, w, h) = current_config.content_rect(w, h);
+                                (w, h)
+                            });
+                            let cut_at = |pos: f32, size: Option<u32>| match size {
+                                Some(size) if size > 0 => (size as f32 * pos) as u32 as f32 / size as f32,
+                                _ => pos,
+                            };
+                            // 只画可见范围内的线，放大查看密集网格时不做无用绘制
+                            let visible = painter.clip_rect().expand(4.0);
+                            let draw_split_line = |line: [egui::Pos2; 2], guide: [egui::Pos2; 2], width: f32, color: egui::Color32| {
+                                if precise {
+                                    painter.line_segment(line, egui::Stroke::new(width + 1.0, color.gamma_multiply(0.45)));
+                                    painter.line_segment(guide, egui::Stroke::new(hairline, color));
+                                } else {
+                                    painter.line_segment(line, egui::Stroke::new(width, color));
+                                }
+                            };
+
                             // 水平分割线
                             for (i, &pos) in current_config.h_lines.iter().enumerate() {
-                                let y = rect.top() + rect.height() * pos;
+                                let y = grid_rect.top() + grid_rect.height() * pos;
+                                if !visible.y_range().contains(y) {
+                                    continue;
+                                }
                                 let is_selected = self.selected_lines.contains(&(LineType::Horizontal, i));
                                 let is_dragging = self.dragging_line == Some((LineType::Horizontal, i));
+                                let style = current_config.line_style(true, i);
                                 
+                                // 选中或拖拽中的线始终以完全不透明显示
                                 let color = if is_selected || is_dragging {
                                     egui::Color32::from_rgb(34, 197, 94) // 绿色
                                 } else {
-                                    egui::Color32::from_rgb(239, 68, 68) // 红色
+                                    egui::Color32::from_rgb(239, 68, 68).gamma_multiply(style.opacity) // 红色
                                 };
                                 
-                                let stroke = if is_selected || is_dragging {
-                                    egui::Stroke::new(4.0, color)
-                                } else {
-                                    egui::Stroke::new(2.0, color)
-                                };
-                                
-                                painter.line_segment(
-                                    [egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)],
-                                    stroke,
+                                let width = if is_selected || is_dragging { 4.0 } else { 2.0 };
+                                let guide_y = grid_rect.top() + grid_rect.height() * cut_at(pos, content_size.map(|(_, h)| h));
+                                draw_split_line(
+                                    [egui::pos2(grid_rect.left(), y), egui::pos2(grid_rect.right(), y)],
+                                    [egui::pos2(grid_rect.left(), guide_y), egui::pos2(grid_rect.right(), guide_y)],
+                                    width,
+                                    color,
                                 );
+                                if !style.label.is_empty() {
+                                    draw_line_label(egui::pos2(grid_rect.left() + 6.0, y - 6.0), egui::Align2::LEFT_BOTTOM, &style.label, color);
+                                }
                             }
 
                             // 垂直分割线
                             for (i, &pos) in current_config.v_lines.iter().enumerate() {
-                                let x = rect.left() + rect.width() * pos;
+                                let x = grid_rect.left() + grid_rect.width() * pos;
+                                if !visible.x_range().contains(x) {
+                                    continue;
+                                }
                                 let is_selected = self.selected_lines.contains(&(LineType::Vertical, i));
                                 let is_dragging = self.dragging_line == Some((LineType::Vertical, i));
+                                let style = current_config.line_style(false, i);
                                 
                                 let color = if is_selected || is_dragging {
                                     egui::Color32::from_rgb(34, 197, 94) // 绿色
                                 } else {
-                                    egui::Color32::from_rgb(239, 68, 68) // 红色
+                                    egui::Color32::from_rgb(239, 68, 68).gamma_multiply(style.opacity) // 红色
                                 };
                                 
-                                let stroke = if is_selected || is_dragging {
-                                    egui::Stroke::new(3.0, color)
-                                } else {
-                                    egui::Stroke::new(2.0, color)
+                                let width = if is_selected || is_dragging { 3.0 } else { 2.0 };
+                                let guide_x = grid_rect.left() + grid_rect.width() * cut_at(pos, content_size.map(|(w, _)| w));
+                                draw_split_line(
+                                    [egui::pos2(x, grid_rect.top()), egui::pos2(x, grid_rect.bottom())],
+                                    [egui::pos2(guide_x, grid_rect.top()), egui::pos2(guide_x, grid_rect.bottom())],
+                                    width,
+                                    color,
+                                );
+                                if !style.label.is_empty() {
+                                    draw_line_label(egui::pos2(x + 6.0, grid_rect.top() + 6.0), egui::Align2::LEFT_TOP, &style.label, color);
+                                }
+                            }
+                            
+                            // 合并区域以紫色框出，待合并的选择以主题色高亮
+                            let merge_color = egui::Color32::from_rgb(147, 51, 234);
+                            for merge in &current_config.cell_merges {
+                                let last = (merge.row + merge.rows - 1, merge.col + merge.cols - 1);
+                                let (Some(first), Some(last)) = (
+                                    cell_rect(grid_rect, &current_config, merge.row, merge.col),
+                                    cell_rect(grid_rect, &current_config, last.0, last.1),
+                                ) else {
+                                    continue;
                                 };
-                                
-                                painter.line_segment(
-                                    [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
-                                    stroke,
+                                let merged = first.union(last);
+                                painter.rect_filled(merged, 0.0, merge_color.gamma_multiply(0.15));
+                                painter.rect_stroke(merged.shrink(1.5), 0.0, egui::Stroke::new(3.0, merge_color));
+                                draw_line_label(
+                                    merged.right_bottom() - egui::vec2(6.0, 6.0),
+                                    egui::Align2::RIGHT_BOTTOM,
+                                    &format!("合并 {}x{}", merge.rows, merge.cols),
+                                    merge_color,
                                 );
                             }
+                            for &(row, col) in &self.selected_cells {
+                                if let Some(cell) = cell_rect(grid_rect, &current_config, row, col) {
+                                    painter.rect_filled(cell, 0.0, egui::Color32::from_rgba_unmultiplied(19, 78, 74, 60));
+                                    painter.rect_stroke(cell.shrink(1.0), 0.0, egui::Stroke::new(2.0, egui::Color32::from_rgb(19, 78, 74)));
+                                }
+                            }
+
+                            // 每个分块的输出像素尺寸，拖动中的线尚未排序，按排序后的位置计算
+                            if let Some((img_w, img_h)) = image_size {
+                                let mut sized = current_config.clone();
+                                if self.dragging_line.is_some() {
+                                    sized.normalize();
+                                }
+                                let scale = rect.width() / img_w as f32;
+                                for region in ImageSplitter::compute_regions(img_w, img_h, &sized) {
+                                    let cell = egui::Rect::from_min_size(
+                                        rect.min + egui::vec2(region.x as f32, region.y as f32) * scale,
+                                        egui::vec2(region.width as f32, region.height as f32) * scale,
+                                    );
+                                    if !visible.intersects(cell) {
+                                        continue;
+                                    }
+                                    let text = format!("{}×{}", region.width, region.height);
+                                    let galley = painter.layout_no_wrap(text, egui::FontId::proportional(11.0), egui::Color32::WHITE);
+                                    // 分块放不下标注时不绘制，避免密集网格上文字重叠
+                                    if galley.size().x + 8.0 > cell.width() || galley.size().y + 8.0 > cell.height() {
+                                        continue;
+                                    }
+                                    let label_rect = egui::Align2::CENTER_CENTER.anchor_size(cell.center(), galley.size());
+                                    painter.rect_filled(label_rect.expand(3.0), 4.0, egui::Color32::from_rgba_unmultiplied(31, 41, 55, 170));
+                                    painter.galley(label_rect.min, galley, egui::Color32::WHITE);
+                                }
+                            }
+
+                            // 分块旋转标记
+                            for cell in &current_config.cell_rotations {
+                                if let Some(cell_rect) = cell_rect(grid_rect, &current_config, cell.row, cell.col) {
+                                    let text = format!("{} {}°", icon::ROTATE_RIGHT, cell.rotation.degrees());
+                                    let pos = cell_rect.left_top() + egui::vec2(6.0, 6.0);
+                                    let galley = painter.layout_no_wrap(text, egui::FontId::proportional(13.0), egui::Color32::WHITE);
+                                    painter.rect_filled(
+                                        egui::Rect::from_min_size(pos, galley.size()).expand(3.0),
+                                        4.0,
+                                        egui::Color32::from_rgba_unmultiplied(19, 78, 74, 200),
+                                    );
+                                    painter.galley(pos, galley, egui::Color32::WHITE);
+                                }
+                            }
                             
+                            // 已添加的裁剪区域
+                            let crop_color = egui::Color32::from_rgb(59, 130, 246);
+                            for (i, crop) in current_config.crop_regions.iter().enumerate() {
+                                let norm = egui::Rect::from_min_size(egui::pos2(crop.x, crop.y), egui::vec2(crop.width, crop.height));
+                                let screen = norm_to_screen(rect, norm);
+                                painter.rect_stroke(screen, 0.0, egui::Stroke::new(2.0, crop_color));
+                                painter.text(
+                                    screen.left_top() + egui::vec2(4.0, 4.0),
+                                    egui::Align2::LEFT_TOP,
+                                    format!("裁剪 {}", i + 1),
+                                    egui::FontId::proportional(12.0),
+                                    crop_color,
+                                );
+                            }
+
+                            // 待确认的裁剪区域及其角点
+                            if let Some(crop) = self.pending_crop {
+                                let screen = norm_to_screen(rect, crop);
+                                painter.rect_filled(screen, 0.0, egui::Color32::from_rgba_unmultiplied(59, 130, 246, 30));
+                                painter.rect_stroke(screen, 0.0, egui::Stroke::new(1.5, crop_color));
+                                for corner in [screen.left_top(), screen.right_top(), screen.left_bottom(), screen.right_bottom()] {
+                                    painter.rect_filled(egui::Rect::from_center_size(corner, egui::vec2(8.0, 8.0)), 1.0, crop_color);
+                                }
+                            }
+
+                            // 分割线间距小于两倍命中距离时容易抓错，提示放大窗口
+                            let tolerance = line_grab_tolerance(self.image_display_scale);
+                            let too_dense = [
+                                min_line_spacing(&current_config.h_lines, grid_rect.height()),
+                                min_line_spacing(&current_config.v_lines, grid_rect.width()),
+                            ]
+                            .into_iter()
+                            .flatten()
+                            .any(|spacing| spacing < tolerance * 2.0);
+                            if too_dense {
+                                let text = format!("{} 分割线过密，将抓取离指针最近的线；放大窗口可更精确地调整", icon::INFO);
+                                let galley = painter.layout_no_wrap(text, egui::FontId::proportional(12.0), egui::Color32::WHITE);
+                                let hint_rect = egui::Align2::CENTER_BOTTOM.anchor_size(rect.intersect(painter.clip_rect()).center_bottom() - egui::vec2(0.0, 8.0), galley.size());
+                                painter.rect_filled(hint_rect.expand(4.0), 4.0, egui::Color32::from_rgba_unmultiplied(31, 41, 55, 200));
+                                painter.galley(hint_rect.min, galley, egui::Color32::WHITE);
+                            }
+
+                            // 拖动或用方向键微调分割线时，在线旁显示位置（百分比及实际裁切的像素行/列），吸附时注明
+                            let arrow_held = ui.input(|i| {
+                                !i.modifiers.ctrl
+                                    && [egui::Key::ArrowUp, egui::Key::ArrowDown, egui::Key::ArrowLeft, egui::Key::ArrowRight]
+                                        .into_iter()
+                                        .any(|key| i.key_down(key))
+                            });
+                            let live_lines = match self.dragging_line {
+                                Some(line) => vec![line],
+                                None if arrow_held => self.selected_lines.clone(),
+                                None => Vec::new(),
+                            };
+                            let pointer = ui.input(|i| i.pointer.hover_pos());
+                            for (line_type, idx) in live_lines {
+                                let horizontal = line_type == LineType::Horizontal;
+                                let lines = if horizontal { &current_config.h_lines } else { &current_config.v_lines };
+                                let Some(&pos) = lines.get(idx) else {
+                                    continue;
+                                };
+                                let mut text = format!("{:.2}%", pos * 100.0);
+                                if let Some((width, height)) = image_size {
+                                    let (x, y, w, h) = current_config.content_rect(width, height);
+                                    let pixel = if horizontal { y + (h as f32 * pos) as u32 } else { x + (w as f32 * pos) as u32 };
+                                    text.push_str(&format!(" · {} {} px", if horizontal { "y" } else { "x" }, pixel));
+                                }
+                                if self.dragging_line.is_some() && self.drag_snapped {
+                                    text.push_str(" (吸附)");
+                                }
+                                let label_color = egui::Color32::from_rgb(31, 41, 55);
+                                if horizontal {
+                                    let y = grid_rect.top() + grid_rect.height() * pos;
+                                    let x = pointer.map_or(grid_rect.center().x, |p| p.x + 14.0).clamp(grid_rect.left(), grid_rect.right());
+                                    draw_line_label(egui::pos2(x, y - 8.0), egui::Align2::LEFT_BOTTOM, &text, label_color);
+                                } else {
+                                    let x = grid_rect.left() + grid_rect.width() * pos;
+                                    let y = pointer.map_or(grid_rect.center().y, |p| p.y + 14.0).clamp(grid_rect.top(), grid_rect.bottom());
+                                    draw_line_label(egui::pos2(x + 8.0, y), egui::Align2::LEFT_TOP, &text, label_color);
+                                }
+                            }
+
                             // 绘制选择框
                             if self.is_selecting {
                                 if let (Some(start), Some(end)) = (self.selection_start, self.selection_end) {
@@ -1116,123 +4377,532 @@ impl eframe::App for BatchImageSplitterApp {
                                 }
                             }
                         }
+
+                        // 待确认裁剪区域的操作按钮
+                        if let (Some(rect), Some(crop)) = (self.image_rect, self.pending_crop) {
+                            if self.crop_drag_anchor.is_none() {
+                                let screen = norm_to_screen(rect, crop);
+                                let buttons = egui::Rect::from_min_size(screen.left_bottom() + egui::vec2(0.0, 6.0), egui::vec2(190.0, 24.0));
+                                ui.allocate_ui_at_rect(buttons, |ui| {
+                                    ui.horizontal(|ui| {
+                                        if ui.button("添加为裁剪区域").clicked() && self.ensure_unlocked() {
+                                            let before = self.active_snapshot();
+                                            self.active_config_mut().crop_regions.push(CropRegion {
+                                                x: crop.min.x,
+                                                y: crop.min.y,
+                                                width: crop.width(),
+                                                height: crop.height(),
+                                            });
+                                            self.commit_undo(before);
+                                            self.pending_crop = None;
+                                            self.status_message = "已添加裁剪区域".to_string();
+                                        }
+                                        if ui.button("取消").clicked() {
+                                            self.pending_crop = None;
+                                        }
+                                    });
+                                });
+                            }
+                        }
                     });
 
                     // --- 底部缩略图列表 (gallery_rect) ---
-                    ui.allocate_ui_at_rect(gallery_rect, |ui| {
-                        ui.set_clip_rect(gallery_rect);
-                        egui::Frame::none()
-                            .fill(egui::Color32::from_rgb(229, 231, 235)) // Gray 200
-                            .inner_margin(8.0)
-                            .show(ui, |ui| {
-                                egui::ScrollArea::horizontal()
-                                    .auto_shrink([false, false])
-                                    .show(ui, |ui| {
-                                        ui.horizontal(|ui| {
-                                            let image_paths = self.image_paths.clone();
-                                            for (idx, path) in image_paths.iter().enumerate() {
-                                                // 尝试加载缩略图
-                                                let texture = {
-                                                    let t = self.thumbnails.entry(idx).or_insert_with(|| {
-                                                        match ImageSplitter::open_image(path) {
-                                                            Ok(img) => {
-                                                                // 使用更高的分辨率以支持缩放
-                                                                let thumb = img.thumbnail(512, 512);
-                                                                let size = [thumb.width() as usize, thumb.height() as usize];
-                                                                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, thumb.to_rgba8().as_raw());
-                                                                ui.ctx().load_texture(format!("thumb_{}", idx), color_image, egui::TextureOptions::default())
-                                                            }
-                                                            Err(_) => {
-                                                                // 加载失败时使用默认空纹理或错误提示
-                                                                ui.ctx().load_texture(format!("thumb_err_{}", idx), egui::ColorImage::example(), egui::TextureOptions::default())
-                                                            }
-                                                        }
+                    if self.settings.show_gallery {
+                        ui.allocate_ui_at_rect(gallery_rect, |ui| {
+                            ui.set_clip_rect(gallery_rect);
+                            egui::Frame::none()
+                                .fill(egui::Color32::from_rgb(229, 231, 235)) // Gray 200
+                                .inner_margin(8.0)
+                                .show(ui, |ui| {
+                                    egui::ScrollArea::horizontal()
+                                        .auto_shrink([false, false])
+                                        .show(ui, |ui| {
+                                            ui.horizontal(|ui| {
+                                                let image_paths = self.image_paths.clone();
+                                                let visible: std::collections::HashSet<usize> =
+                                                    self.filtered_images().into_iter().map(|(idx, _)| idx).collect();
+                                                for (idx, path) in image_paths.iter().enumerate() {
+                                                    if !visible.contains(&idx) {
+                                                        continue;
+                                                    }
+                                                    // 缩略图在后台生成（优先读磁盘缓存），按该图片的配置校正方向、旋转和翻转，与分割线对应；
+                                                    // 只为滚动到可见的项生成，完成前显示加载占位
+                                                    let texture = self.thumbnails.get(idx).cloned();
+
+                                                    let is_selected = idx == self.current_index;
+                                                    let border_color = if is_selected {
+                                                        egui::Color32::from_rgb(19, 78, 74) // 主题色
+                                                    } else {
+                                                        egui::Color32::TRANSPARENT
+                                                    };
+
+                                                    // 独立配置与共用配置的网格结构是否不同（行列数不同会产生不同数量的分块）
+                                                    let thumb_config = self.effective_config(idx);
+                                                    let shared = self.shared_config();
+                                                    let override_grid = self.config_overrides.contains_key(&idx).then(|| {
+                                                        let structural = thumb_config.h_lines.len() != shared.h_lines.len()
+                                                            || thumb_config.v_lines.len() != shared.v_lines.len();
+                                                        (structural, thumb_config.h_lines.len() + 1, thumb_config.v_lines.len() + 1)
                                                     });
-                                                    t.clone()
-                                                };
-
-                                                let is_selected = idx == self.current_index;
-                                                let border_color = if is_selected {
-                                                    egui::Color32::from_rgb(19, 78, 74) // 主题色
-                                                } else {
-                                                    egui::Color32::TRANSPARENT
-                                                };
-
-                                                let has_override = self.config_overrides.contains_key(&idx);
-
-                                                ui.vertical(|ui| {
-                                                    // 动态计算缩略图尺寸：基于区域高度，预留空间给标签
-                                                    let thumb_height = (gallery_rect.height() - 60.0).max(120.0);
-                                                    let frame_size = egui::vec2(thumb_height, thumb_height);
-                                                     let inner_res = egui::Frame::none()
-                                                         .stroke(egui::Stroke::new(2.0, border_color))
-                                                         .rounding(4.0)
-                                                         .inner_margin(2.0)
-                                                         .show(ui, |ui| {
-                                                             ui.add(egui::Image::new(&texture).fit_to_exact_size(frame_size))
-                                                         });
-                                                     let rect = inner_res.response.rect;
-                                                     let resp = ui.interact(rect, ui.id().with(idx), egui::Sense::click());
-
-                                                     // 在缩略图上绘制分割线预览
-                                                     let painter = ui.painter();
-                                                    let thumb_config = self.config_overrides.get(&idx).unwrap_or(&self.config);
+                                                    let (thumb_h_lines, thumb_v_lines) = (thumb_config.h_lines.clone(), thumb_config.v_lines.clone());
+                                                    let thumb_locked = thumb_config.locked;
+
+                                                    ui.vertical(|ui| {
+                                                        // 动态计算缩略图尺寸：基于区域高度，预留空间给标签
+                                                        let thumb_height = (gallery_rect.height() - 60.0).max(120.0);
+                                                        let frame_size = egui::vec2(thumb_height, thumb_height);
+                                                         let inner_res = egui::Frame::none()
+                                                             .stroke(egui::Stroke::new(2.0, border_color))
+                                                             .rounding(4.0)
+                                                             .inner_margin(2.0)
+                                                             .show(ui, |ui| {
+                                                                 match &texture {
+                                                                     Some(texture) => {
+                                                                         let rect = ui.add(egui::Image::new(texture).fit_to_exact_size(frame_size)).rect;
+                                                                         if ui.is_rect_visible(rect) {
+                                                                             self.thumbnails.touch(idx, ui.ctx().frame_nr());
+                                                                         }
+                                                                     }
+                                                                     None => {
+                                                                         let (rect, _) = ui.allocate_exact_size(frame_size, egui::Sense::hover());
+                                                                         ui.put(rect, egui::Spinner::new().size(24.0));
+                                                                         if ui.is_rect_visible(rect) && !self.thumbnail_loader.is_pending(idx) {
+                                                                             let config = self.effective_config(idx).clone();
+                                                                             self.thumbnail_loader.request(ui.ctx(), idx, path, config);
+                                                                         }
+                                                                     }
+                                                                 }
+                                                             });
+                                                         let rect = inner_res.response.rect;
+                                                         let resp = ui.interact(rect, ui.id().with(idx), egui::Sense::click());
+
+                                                         // 在缩略图上绘制分割线预览
+                                                         let painter = ui.painter();
                                                     
-                                                    // 缩略图中的分割线颜色稍微淡一点
-                                                    let line_color = egui::Color32::from_rgba_premultiplied(239, 68, 68, 200); // 红色，透明度略低
-                                                    let line_stroke = egui::Stroke::new(2.0, line_color);
-
-                                                    for &pos in &thumb_config.h_lines {
-                                                        let y = rect.top() + rect.height() * pos;
-                                                        painter.line_segment(
-                                                            [egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)],
-                                                            line_stroke,
-                                                        );
-                                                    }
-                                                    for &pos in &thumb_config.v_lines {
-                                                        let x = rect.left() + rect.width() * pos;
-                                                        painter.line_segment(
-                                                            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
-                                                            line_stroke,
-                                                        );
-                                                    }
+                                                        // 缩略图中的分割线颜色稍微淡一点
+                                                        let line_color = egui::Color32::from_rgba_premultiplied(239, 68, 68, 200); // 红色，透明度略低
+                                                        let line_stroke = egui::Stroke::new(2.0, line_color);
 
-                                                    if resp.clicked() {
-                                                        self.current_index = idx;
-                                                        self.load_image(ui.ctx(), &path.clone());
-                                                    }
+                                                        for &pos in &thumb_h_lines {
+                                                            let y = rect.top() + rect.height() * pos;
+                                                            painter.line_segment(
+                                                                [egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)],
+                                                                line_stroke,
+                                                            );
+                                                        }
+                                                        for &pos in &thumb_v_lines {
+                                                            let x = rect.left() + rect.width() * pos;
+                                                            painter.line_segment(
+                                                                [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                                                                line_stroke,
+                                                            );
+                                                        }
 
-                                                    ui.horizontal(|ui| {
-                                                        ui.add_space(2.0);
-                                                        if has_override {
-                                                            ui.label(egui::RichText::new("已调").size(12.0).color(egui::Color32::from_rgb(34, 197, 94)));
-                                                        } else {
-                                                            ui.label(egui::RichText::new("共享").size(12.0).color(egui::Color32::from_rgb(107, 114, 128)));
+                                                        if resp.clicked() {
+                                                            self.current_index = idx;
+                                                            self.load_image(ui.ctx(), &path.clone());
                                                         }
+
+                                                        ui.horizontal(|ui| {
+                                                            ui.add_space(2.0);
+                                                            if let Some((structural, rows, cols)) = override_grid {
+                                                                if structural {
+                                                                    ui.label(egui::RichText::new(format!("{} 已调 {}x{}", icon::WARNING, rows, cols)).size(12.0).color(egui::Color32::from_rgb(251, 146, 60)))
+                                                                        .on_hover_text(format!("网格与全局配置不同 ({}行 x {}列)，分块数量将不同", rows, cols));
+                                                                } else {
+                                                                    ui.label(egui::RichText::new("已调").size(12.0).color(egui::Color32::from_rgb(34, 197, 94)))
+                                                                        .on_hover_text("网格与全局配置相同，仅调整了分割线位置");
+                                                                }
+                                                            } else {
+                                                                ui.label(egui::RichText::new("共享").size(12.0).color(egui::Color32::from_rgb(107, 114, 128)));
+                                                            }
                                                         
-                                                        if is_selected {
-                                                            ui.label(egui::RichText::new("当前").size(12.0).color(egui::Color32::from_rgb(19, 78, 74)).strong());
-                                                        }
+                                                            if thumb_locked {
+                                                                ui.label(egui::RichText::new(format!("{} 锁定", icon::LOCK)).size(12.0).color(egui::Color32::from_rgb(107, 114, 128)))
+                                                                    .on_hover_text("分割线已锁定");
+                                                            }
+                                                            if self.sidecars.is_loaded(path) {
+                                                                ui.label(egui::RichText::new(format!("{} 旁车", icon::INSERT_DRIVE_FILE)).size(12.0).color(egui::Color32::from_rgb(59, 130, 246)))
+                                                                    .on_hover_text(format!("独立配置已从 {} 恢复", sidecar::sidecar_path(path).display()));
+                                                            }
+                                                            if is_selected {
+                                                                ui.label(egui::RichText::new("当前").size(12.0).color(egui::Color32::from_rgb(19, 78, 74)).strong());
+                                                            }
+                                                        });
+                                                        ui.add_space(4.0);
                                                     });
-                                                    ui.add_space(4.0);
-                                                });
-                                                ui.add_space(12.0); // 增加项之间的间距
-                                            }
+                                                    ui.add_space(12.0); // 增加项之间的间距
+                                                }
+                                            });
                                         });
-                                    });
-                            });
-                    });
+                                });
+                        });
+                    }
                     } else {
                         ui.vertical_centered(|ui| {
                             ui.add_space(100.0);
                             ui.label(icon_text(icon::IMAGE, 64.0).color(egui::Color32::from_rgb(209, 213, 219)));
                             ui.add_space(20.0);
                             ui.label(egui::RichText::new("请点击右侧「选择文件」按钮").size(20.0).color(egui::Color32::from_rgb(107, 114, 128)));
-                            ui.label(egui::RichText::new("或使用 Ctrl+O 快捷键").size(14.0).color(egui::Color32::from_rgb(156, 163, 175)));
+                            ui.label(egui::RichText::new(format!("或使用 {} 快捷键", self.settings.key_bindings.get(Action::OpenFiles))).size(14.0).color(egui::Color32::from_rgb(156, 163, 175)));
+                        });
+                    }
+                });
+        
+        // 设置窗口
+        if self.show_settings {
+            let mut open = true;
+            egui::Window::new("设置")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.set_min_width(300.0);
+                    ui.label(egui::RichText::new("Delete 键").strong());
+                    ui.checkbox(&mut self.settings.delete_removes_image, "未选中分割线时移除当前图片");
+                    ui.add_enabled(
+                        self.settings.delete_removes_image,
+                        egui::Checkbox::new(&mut self.settings.confirm_image_removal, "移除前确认"),
+                    );
+                    ui.separator();
+                    ui.label(egui::RichText::new("分割线").strong());
+                    ui.horizontal(|ui| {
+                        ui.label("最小单元尺寸:");
+                        ui.add(egui::DragValue::new(&mut self.settings.min_cell_size).range(1..=1024).suffix(" px"));
+                    }).response.on_hover_text("均分分割线时每个分块的最小宽高，图片放不下时自动减少行列数");
+                    ui.checkbox(&mut self.settings.keep_selection_on_switch, "切换图片时保留选择")
+                        .on_hover_text("新图片有相同序号的分割线时保持选中（如第二条垂直线），便于在一组图片上逐张微调同一条线；否则清除选择");
+                    ui.checkbox(&mut self.settings.precise_line_guides, "显示精确裁切位置")
+                        .on_hover_text("分割线改为半透明线带，并叠加 1 像素参考线标出实际裁切落在的像素边界");
+                    let mut snap = self.settings.snap_step.is_some();
+                    let mut snap_percent = self.settings.snap_step.unwrap_or(DEFAULT_SNAP_STEP) * 100.0;
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut snap, "拖动时吸附到");
+                        ui.add_enabled(snap, egui::DragValue::new(&mut snap_percent).range(0.5..=50.0).speed(0.1).suffix("%"));
+                        ui.label("的整数倍");
+                    }).response.on_hover_text("拖动分割线时位置取最近的整数倍；拖动时按住 Alt 可临时关闭吸附");
+                    self.settings.snap_step = snap.then_some(snap_percent / 100.0);
+                    ui.separator();
+                    ui.label(egui::RichText::new("独立配置").strong());
+                    ui.checkbox(&mut self.settings.sidecar_overrides, format!("自动保存到旁车文件 (*{})", sidecar::SIDECAR_SUFFIX))
+                        .on_hover_text("每张图片的独立配置修改后自动写入图片旁的同名 .split.json 文件，添加图片时自动读取；取消独立配置时删除该文件");
+                    ui.separator();
+                    ui.label(egui::RichText::new("文件夹扫描").strong());
+                    ui.checkbox(&mut self.settings.ignore_hidden_files, "忽略隐藏文件")
+                        .on_hover_text("跳过以点开头或带有隐藏属性的文件，例如系统生成的缩略图缓存");
+                    ui.separator();
+                    ui.label(egui::RichText::new("缩略图栏").strong());
+                    ui.checkbox(&mut self.settings.show_gallery, "显示缩略图栏");
+                    ui.add_enabled(
+                        self.settings.show_gallery,
+                        egui::Slider::new(&mut self.settings.gallery_fraction, AppSettings::GALLERY_FRACTION_RANGE)
+                            .text("高度比例")
+                            .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("缓存上限:");
+                        ui.add(egui::DragValue::new(&mut self.settings.max_thumbnails).range(20..=5000).suffix(" 个"));
+                        ui.add(egui::DragValue::new(&mut self.settings.thumbnail_memory_mb).range(16..=4096).suffix(" MB"));
+                    }).response.on_hover_text("超出任一上限时释放最久未显示的缩略图，再次滚动到时重新生成（通常直接读取磁盘缓存）");
+                    ui.label(egui::RichText::new(format!(
+                        "当前: {} 个, 约 {}",
+                        self.thumbnails.len(),
+                        format_bytes(self.thumbnails.bytes())
+                    )).size(12.0).color(egui::Color32::from_rgb(107, 114, 128)));
+                    ui.separator();
+                    ui.label(egui::RichText::new("快捷键").strong());
+                    egui::Grid::new("key_bindings_grid").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+                        for action in Action::ALL {
+                            ui.label(action.label());
+                            let text = if self.capturing_binding == Some(action) {
+                                "请按下新组合… (Esc 取消)".to_string()
+                            } else {
+                                self.settings.key_bindings.get(action).to_string()
+                            };
+                            if ui.button(text).clicked() {
+                                self.capturing_binding = Some(action);
+                                self.binding_message = None;
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    if let Some(msg) = &self.binding_message {
+                        ui.label(egui::RichText::new(msg).color(egui::Color32::from_rgb(220, 38, 38)));
+                    }
+                    if ui.button("恢复默认快捷键").clicked() {
+                        self.settings.key_bindings.reset_to_default();
+                        self.capturing_binding = None;
+                        self.binding_message = None;
+                    }
+                });
+            self.show_settings = open;
+            if !open {
+                self.capturing_binding = None;
+                self.binding_message = None;
+            }
+        }
+
+        // 录入新快捷键
+        if let Some(action) = self.capturing_binding {
+            let pressed = ctx.input(|i| {
+                i.events.iter().find_map(|e| match e {
+                    egui::Event::Key { key, pressed: true, modifiers, .. } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+            });
+            if let Some((key, modifiers)) = pressed {
+                if key == egui::Key::Escape {
+                    self.capturing_binding = None;
+                } else {
+                    let binding = KeyBinding { key, ctrl: modifiers.ctrl, shift: modifiers.shift, alt: modifiers.alt };
+                    match self.settings.key_bindings.set(action, binding) {
+                        Ok(()) => {
+                            self.capturing_binding = None;
+                            self.binding_message = None;
+                        }
+                        Err(other) => {
+                            self.binding_message = Some(format!("{} 已被「{}」占用", binding, other.label()));
+                        }
+                    }
+                }
+            }
+        }
+        
+        // 移除图片确认
+        if let Some(idx) = self.pending_remove {
+            let name = self.image_paths.get(idx)
+                .map(|p| p.file_name().unwrap_or_default().to_string_lossy().to_string())
+                .unwrap_or_default();
+            egui::Window::new("移除图片")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.label(format!("从列表中移除「{}」？", name));
+                    ui.label(egui::RichText::new("源文件不会被删除").size(11.0).color(egui::Color32::GRAY));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("移除").clicked() {
+                            self.pending_remove = None;
+                            self.remove_image(ctx, idx);
+                        }
+                        if ui.button("取消").clicked() {
+                            self.pending_remove = None;
+                        }
+                    });
+                });
+        }
+        
+        // 打开项目会替换当前列表和配置，有未保存的修改时先确认
+        if self.confirm_open_project {
+            egui::Window::new("打开项目")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.label(egui::RichText::new(format!("{} 当前有未保存的修改", icon::WARNING)).color(egui::Color32::from_rgb(251, 146, 60)));
+                    ui.label(egui::RichText::new("打开项目会替换当前的图片列表、全局配置和独立配置，且无法撤销").size(11.0).color(egui::Color32::GRAY));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("仍然打开").clicked() {
+                            self.confirm_open_project = false;
+                            self.open_dialog(DialogKind::OpenProject);
+                        }
+                        if ui.button("取消").clicked() {
+                            self.confirm_open_project = false;
+                        }
+                    });
+                });
+        }
+
+        self.draw_batch_result(ctx);
+        self.draw_drop_overlay(ctx);
+
+        // 批处理确认窗口
+        if let Some(plan) = self.batch_confirm.take() {
+            let estimate = plan.summary();
+            let mut keep_open = true;
+            egui::Window::new("开始批量处理")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.set_min_width(300.0);
+                    ui.label(format!("图片数量: {}", estimate.images));
+                    ui.label(format!("分块总数: {}", estimate.tiles));
+                    ui.label(format!("预计输出大小: 约 {}", format_bytes(estimate.bytes)));
+                    if estimate.unreadable > 0 {
+                        ui.label(egui::RichText::new(format!("{} {} 张图片无法读取尺寸，未计入预估", icon::WARNING, estimate.unreadable))
+                            .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                    }
+                    if self.config.auto_detect_grid {
+                        ui.label(egui::RichText::new(format!("{} 自动网格批处理：分块数按全局分割线估算，实际以检测结果为准", icon::INFO))
+                            .size(12.0).color(egui::Color32::GRAY));
+                    }
+                    if estimate.naming_fallbacks > 0 {
+                        ui.label(egui::RichText::new(format!("{} {} 张图片的网格不均匀或含合并分块，将改用 行_列 命名", icon::WARNING, estimate.naming_fallbacks))
+                            .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                    }
+                    if let Some((w, h)) = estimate.smallest_tile {
+                        ui.label(format!("最小分块: {}×{} px", w, h));
+                    }
+                    if estimate.collapsed > 0 {
+                        ui.label(egui::RichText::new(format!("{} {} 张图片尺寸过小，部分分割线将被合并，分块数少于网格", icon::WARNING, estimate.collapsed))
+                            .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                    }
+                    if estimate.tiny_tiles > 0 {
+                        ui.label(egui::RichText::new(format!("{} {} 个分块宽或高不足 {} px", icon::WARNING, estimate.tiny_tiles, MIN_USEFUL_TILE_PX))
+                            .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                    }
+                    if estimate.empty_crops > 0 {
+                        ui.label(egui::RichText::new(format!("{} {} 个裁剪区域超出图片范围，不会导出", icon::WARNING, estimate.empty_crops))
+                            .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                    }
+                    let warned: Vec<_> = plan.warned_images().collect();
+                    if !warned.is_empty() {
+                        egui::CollapsingHeader::new(format!("有问题的图片 ({})", warned.len()))
+                            .default_open(false)
+                            .show(ui, |ui| {
+                                egui::ScrollArea::vertical().max_height(180.0).show(ui, |ui| {
+                                    for image in warned {
+                                        let name = image.path.file_name().unwrap_or_default().to_string_lossy();
+                                        let problems: Vec<String> = image.warnings.iter().map(|w| match *w {
+                                            PlanWarning::Unreadable => "无法读取尺寸".to_string(),
+                                            PlanWarning::Collapsed => "分割线将被合并".to_string(),
+                                            PlanWarning::TinyTiles(n) => format!("{} 个过小分块", n),
+                                            PlanWarning::EmptyCrops(n) => format!("{} 个裁剪区域无效", n),
+                                            PlanWarning::NamingFallback => "改用 行_列 命名".to_string(),
+                                        }).collect();
+                                        ui.label(egui::RichText::new(format!("{}: {}", name, problems.join("，")))
+                                            .size(12.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                                    }
+                                });
+                            });
+                    }
+                    if self.saved_config.is_some() && self.has_unsaved_changes() {
+                        ui.label(egui::RichText::new(format!("{} 有未保存的修改，全局分割线将使用上次保存的位置", icon::WARNING))
+                            .size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                    }
+                    ui.label(egui::RichText::new("大小为粗略估计，实际取决于图片内容").size(11.0).color(egui::Color32::GRAY));
+                    ui.add_space(8.0);
+                    let alongside = self.settings.output_alongside_source;
+                    let output_dir = self.settings.last_output_dir.clone().filter(|dir| dir.is_dir());
+                    if alongside {
+                        ui.label(egui::RichText::new("输出到: 各源图片旁的 名称_split 文件夹").size(12.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                    } else if let Some(dir) = &output_dir {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(format!("输出到: {}", dir.display())).size(12.0).color(egui::Color32::from_rgb(75, 85, 99)));
+                            if ui.small_button("更改").clicked() {
+                                self.open_dialog(DialogKind::ChangeOutputFolder);
+                            }
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        let start_label = if alongside || output_dir.is_some() { "开始处理" } else { "选择输出文件夹并开始" };
+                        if ui.button(start_label).clicked() {
+                            keep_open = false;
+                            self.start_batch_process(ctx);
+                        }
+                        if ui.button("取消").clicked() {
+                            keep_open = false;
+                        }
+                    });
+                });
+            if keep_open && self.batch_confirm.is_none() {
+                self.batch_confirm = Some(plan);
+            }
+        }
+        
+        // 输出目录冲突确认
+        if let Some((output_dir, input_dir, scope)) = self.output_conflict.clone() {
+            egui::Window::new("输出目录位于输入目录内")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.set_max_width(360.0);
+                    ui.label(egui::RichText::new(format!("{} 输出目录与输入目录相同或位于其内部", icon::WARNING)).color(egui::Color32::from_rgb(251, 146, 60)));
+                    ui.label(egui::RichText::new(format!("输出: {}", output_dir.display())).size(12.0));
+                    ui.label(egui::RichText::new(format!("输入: {}", input_dir.display())).size(12.0));
+                    ui.label(egui::RichText::new("再次扫描该文件夹时，生成的分块会被当作新图片加入列表。").size(12.0).color(egui::Color32::GRAY));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("重新选择").clicked() {
+                            self.output_conflict = None;
+                            self.open_dialog(match scope {
+                                BatchScope::All => DialogKind::OutputFolder,
+                                BatchScope::Current => DialogKind::CurrentImageOutputFolder,
+                            });
+                        }
+                        if ui.button("仍然继续").clicked() {
+                            self.output_conflict = None;
+                            self.run_batch_process(ctx, OutputLocation::Chosen(output_dir.clone()), scope);
+                        }
+                        if ui.button("取消").clicked() {
+                            self.output_conflict = None;
+                        }
+                    });
+                });
+        }
+        
+        // 配置校验窗口
+        if let Some(report) = self.validation_report.clone() {
+            let mut open = true;
+            egui::Window::new("配置校验")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut open)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.set_min_width(320.0);
+                    if report.is_empty() {
+                        ui.label(egui::RichText::new(format!("{} 所有配置均有效", icon::CHECK)).color(egui::Color32::from_rgb(34, 197, 94)));
+                    } else {
+                        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                            for (name, issues) in &report {
+                                ui.label(egui::RichText::new(name).strong());
+                                for issue in issues {
+                                    ui.label(egui::RichText::new(format!("{} {}", icon::WARNING, issue)).size(12.0).color(egui::Color32::from_rgb(251, 146, 60)));
+                                }
+                                ui.add_space(4.0);
+                            }
                         });
                     }
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if !report.is_empty() && ui.button("自动修复").clicked() {
+                            self.fix_all_configs();
+                            self.validation_report = None;
+                        }
+                        if ui.button("关闭").clicked() {
+                            self.validation_report = None;
+                        }
+                    });
                 });
+            if !open {
+                self.validation_report = None;
+            }
+        }
         
+        // 单块原始分辨率预览
+        if let Some((title, texture)) = self.tile_preview.clone() {
+            let mut open = true;
+            egui::Window::new(title)
+                .id(egui::Id::new("tile_preview_window"))
+                .open(&mut open)
+                .default_size(egui::vec2(640.0, 480.0))
+                .resizable(true)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+                        // 按物理像素 1:1 显示
+                        ui.add(egui::Image::new(&texture).fit_to_original_size(1.0 / ctx.pixels_per_point()));
+                    });
+                });
+            if !open {
+                self.tile_preview = None;
+            }
+        }
+
         // 关于窗口
         if self.show_about {
             self.load_about_icon(ctx);
@@ -1283,20 +4953,32 @@ impl eframe::App for BatchImageSplitterApp {
                         
                         ui.add_space(20.0);
                          ui.horizontal(|ui| {
+                             let info_url = format!("https://{}", self.obfuscated_info_url);
                              ui.label(egui::RichText::new(&self.obfuscated_info_label).size(12.0));
                              ui.hyperlink_to(
                                  egui::RichText::new(&self.obfuscated_info_url).size(12.0).color(egui::Color32::from_rgb(59, 130, 246)),
-                                 format!("https://{}", self.obfuscated_info_url)
+                                 &info_url
                              );
+                             if ui.add(egui::Button::new(egui::RichText::new(format!("{} 复制", icon::COPY)).size(11.0)).small()).clicked() {
+                                 self.copy_link(&info_url);
+                             }
                          });
                          ui.add_space(8.0);
                          ui.horizontal(|ui| {
+                             let repo_url = self.obfuscated_repo_url.clone();
                              ui.label(egui::RichText::new(&self.obfuscated_repo_label).size(12.0));
                              ui.hyperlink_to(
-                                 egui::RichText::new(&self.obfuscated_repo_url).size(12.0).color(egui::Color32::from_rgb(59, 130, 246)),
-                                 &self.obfuscated_repo_url
+                                 egui::RichText::new(&repo_url).size(12.0).color(egui::Color32::from_rgb(59, 130, 246)),
+                                 &repo_url
                              );
+                             if ui.add(egui::Button::new(egui::RichText::new(format!("{} 复制", icon::COPY)).size(11.0)).small()).clicked() {
+                                 self.copy_link(&repo_url);
+                             }
                          });
+                         if let Some(copied) = &self.copied_link {
+                             ui.add_space(4.0);
+                             ui.label(egui::RichText::new(copied).size(11.0).color(egui::Color32::from_rgb(107, 114, 128)));
+                         }
                          ui.add_space(24.0);
                          ui.horizontal(|ui| {
                              ui.style_mut().spacing.item_spacing.x = 12.0;
@@ -1360,5 +5042,10 @@ impl eframe::App for BatchImageSplitterApp {
                     });
                 });
         }
+
+        self.draw_undo_clear_toast(ctx);
+        if self.show_debug_hud {
+            self.draw_debug_hud(ctx);
+        }
     }
 }
@@ -0,0 +1,186 @@
+//! 无界面命令行模式，用于在服务器或脚本中批量分割
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+
+use crate::dialogs::IMAGE_EXTENSIONS;
+use crate::image_splitter::{ImageSplitter, OutputLocation, SplitConfig};
+
+/// 启用命令行模式的参数
+pub const HEADLESS_FLAG: &str = "--headless";
+
+const USAGE: &str = "\
+用法: batch-image-splitter --headless --input <文件夹> --output <文件夹> [选项]
+
+选项:
+  --input <文件夹>    读取其中的图片（不含子文件夹）
+  --output <文件夹>   分块输出位置
+  --rows <N>          行数，按均匀网格分割
+  --cols <N>          列数，按均匀网格分割
+  --config <文件>     从界面保存的 JSON 配置读取分割线和输出选项；
+                      同时给出 --rows/--cols 时改用均匀网格
+  -h, --help          显示本帮助";
+
+/// 命令行参数
+struct HeadlessArgs {
+    input: PathBuf,
+    output: PathBuf,
+    rows: Option<usize>,
+    cols: Option<usize>,
+    config: Option<PathBuf>,
+}
+
+impl HeadlessArgs {
+    /// 解析 `--headless` 之外的参数，`Ok(None)` 表示只需显示帮助
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut input = None;
+        let mut output = None;
+        let mut rows = None;
+        let mut cols = None;
+        let mut config = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("{} 缺少参数值", name));
+            match arg.as_str() {
+                HEADLESS_FLAG => {}
+                "-h" | "--help" => return Ok(None),
+                "--input" => input = Some(PathBuf::from(value("--input")?)),
+                "--output" => output = Some(PathBuf::from(value("--output")?)),
+                "--config" => config = Some(PathBuf::from(value("--config")?)),
+                "--rows" => rows = Some(parse_count("--rows", &value("--rows")?)?),
+                "--cols" => cols = Some(parse_count("--cols", &value("--cols")?)?),
+                other => return Err(format!("未知参数: {}", other)),
+            }
+        }
+
+        let input = input.ok_or("缺少 --input")?;
+        let output = output.ok_or("缺少 --output")?;
+        if config.is_none() && (rows.is_none() || cols.is_none()) {
+            return Err("未指定 --config 时必须同时给出 --rows 和 --cols".to_string());
+        }
+        Ok(Some(Self { input, output, rows, cols, config }))
+    }
+
+    /// 读取配置文件（如有）并套用行列数
+    fn split_config(&self) -> Result<SplitConfig, String> {
+        let mut config = match &self.config {
+            Some(path) => SplitConfig::load_from_file(path)
+                .map_err(|e| format!("无法读取配置 {}: {}", path.display(), e))?,
+            None => SplitConfig::default(),
+        };
+        if self.rows.is_some() || self.cols.is_some() {
+            config.rows = self.rows.unwrap_or(config.rows);
+            config.cols = self.cols.unwrap_or(config.cols);
+            config.reset_to_default();
+        }
+        Ok(config)
+    }
+}
+
+fn parse_count(name: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(format!("{} 需要大于 0 的整数，收到 \"{}\"", name, value)),
+    }
+}
+
+/// 文件夹中可处理的图片，按文件名排序
+fn collect_images(dir: &PathBuf) -> std::io::Result<Vec<PathBuf>> {
+    let mut images: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| IMAGE_EXTENSIONS.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+                .unwrap_or(false)
+        })
+        .collect();
+    images.sort();
+    Ok(images)
+}
+
+/// 运行命令行模式，返回进程退出码：0 成功，1 有图片失败或处理出错，2 参数错误
+pub fn run(args: impl IntoIterator<Item = String>) -> i32 {
+    let args = match HeadlessArgs::parse(args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return 0;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+    let config = match args.split_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    if let Err(e) = config.validate_template() {
+        eprintln!("文件名模板无效: {}", e);
+        return 2;
+    }
+
+    let images = match collect_images(&args.input) {
+        Ok(images) => images,
+        Err(e) => {
+            eprintln!("无法读取输入文件夹 {}: {}", args.input.display(), e);
+            return 2;
+        }
+    };
+    if images.is_empty() {
+        eprintln!("输入文件夹中没有可处理的图片: {}", args.input.display());
+        return 1;
+    }
+    if let Err(e) = std::fs::create_dir_all(&args.output) {
+        eprintln!("无法创建输出文件夹 {}: {}", args.output.display(), e);
+        return 1;
+    }
+
+    println!(
+        "共 {} 张图片，{} 行 × {} 列，输出到 {}",
+        images.len(),
+        config.rows,
+        config.cols,
+        args.output.display()
+    );
+    let result = ImageSplitter::batch_process(
+        &images,
+        &config,
+        &HashMap::new(),
+        &OutputLocation::Chosen(args.output.clone()),
+        &AtomicBool::new(false),
+        |done, total| println!("进度: {}/{}", done, total),
+    );
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("批量处理失败: {}", e);
+            return 1;
+        }
+    };
+
+    println!(
+        "批量处理完成: {} 成功, {} 失败，写入 {} 个分块",
+        report.processed, report.failed, report.written_tiles
+    );
+    if report.skipped_tiles > 0 {
+        println!("跳过已存在的分块 {} 个", report.skipped_tiles);
+    }
+    if report.empty_tiles > 0 {
+        println!("跳过宽或高为 0 的分块 {} 个", report.empty_tiles);
+    }
+    for (path, reason) in &report.format_fallbacks {
+        println!("已改存为 PNG: {} ({})", path.display(), reason);
+    }
+    for (path, error) in &report.failures {
+        eprintln!("失败: {}: {}", path.display(), error);
+    }
+    if report.failed > 0 { 1 } else { 0 }
+}
@@ -0,0 +1,151 @@
+//! 非阻塞文件对话框
+//!
+//! 使用 rfd 的异步对话框，每帧轮询一次结果，对话框打开期间界面仍可刷新。
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use eframe::egui;
+
+/// 图片文件扩展名过滤
+pub const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp", "gif"];
+
+/// 对话框用途，决定结果如何处理
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DialogKind {
+    /// 选择图片文件
+    ImageFiles,
+    /// 选择输入文件夹
+    InputFolder,
+    /// 选择批处理输出文件夹
+    OutputFolder,
+    /// 为「处理当前图片」选择输出文件夹
+    CurrentImageOutputFolder,
+    /// 只更改记住的输出文件夹，不开始处理
+    ChangeOutputFolder,
+    /// 选择分割线位置文本
+    LinePositions,
+    /// 选择要导入的分割配置文件
+    ImportConfig,
+    /// 选择分割配置的导出位置
+    ExportConfig,
+    /// 选择要打开的项目文件
+    OpenProject,
+    /// 选择项目文件的保存位置
+    SaveProject,
+}
+
+type PickFuture = Pin<Box<dyn Future<Output = Vec<PathBuf>> + Send>>;
+
+/// 正在打开的对话框
+pub struct PendingDialog {
+    pub kind: DialogKind,
+    future: PickFuture,
+}
+
+/// 对话框有结果时请求重绘，使下一帧能取到结果
+struct RepaintWaker(egui::Context);
+
+impl Wake for RepaintWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.request_repaint();
+    }
+}
+
+impl PendingDialog {
+    /// 打开对话框，`directory` 为文件夹对话框的初始位置
+    pub fn open(kind: DialogKind, directory: Option<&Path>) -> Self {
+        let future: PickFuture = match kind {
+            DialogKind::ImageFiles => {
+                let dialog = rfd::AsyncFileDialog::new().add_filter("图片", IMAGE_EXTENSIONS);
+                Box::pin(async move {
+                    dialog
+                        .pick_files()
+                        .await
+                        .map(|files| files.into_iter().map(|f| f.path().to_path_buf()).collect())
+                        .unwrap_or_default()
+                })
+            }
+            DialogKind::LinePositions => {
+                let dialog = rfd::AsyncFileDialog::new().add_filter("分割线位置", &["txt", "csv"]);
+                Box::pin(async move {
+                    dialog
+                        .pick_file()
+                        .await
+                        .map(|f| vec![f.path().to_path_buf()])
+                        .unwrap_or_default()
+                })
+            }
+            DialogKind::OpenProject => {
+                let dialog = rfd::AsyncFileDialog::new().add_filter("项目", &["json"]);
+                Box::pin(async move {
+                    dialog
+                        .pick_file()
+                        .await
+                        .map(|f| vec![f.path().to_path_buf()])
+                        .unwrap_or_default()
+                })
+            }
+            DialogKind::SaveProject => {
+                let dialog = rfd::AsyncFileDialog::new()
+                    .add_filter("项目", &["json"])
+                    .set_file_name("split_project.json");
+                Box::pin(async move {
+                    dialog
+                        .save_file()
+                        .await
+                        .map(|f| vec![f.path().to_path_buf()])
+                        .unwrap_or_default()
+                })
+            }
+            DialogKind::ImportConfig => {
+                let dialog = rfd::AsyncFileDialog::new().add_filter("分割配置", &["json"]);
+                Box::pin(async move {
+                    dialog
+                        .pick_file()
+                        .await
+                        .map(|f| vec![f.path().to_path_buf()])
+                        .unwrap_or_default()
+                })
+            }
+            DialogKind::ExportConfig => {
+                let dialog = rfd::AsyncFileDialog::new()
+                    .add_filter("分割配置", &["json"])
+                    .set_file_name("split_config.json");
+                Box::pin(async move {
+                    dialog
+                        .save_file()
+                        .await
+                        .map(|f| vec![f.path().to_path_buf()])
+                        .unwrap_or_default()
+                })
+            }
+            DialogKind::InputFolder
+            | DialogKind::OutputFolder
+            | DialogKind::CurrentImageOutputFolder
+            | DialogKind::ChangeOutputFolder => {
+                let mut dialog = rfd::AsyncFileDialog::new();
+                if let Some(directory) = directory.filter(|d| d.is_dir()) {
+                    dialog = dialog.set_directory(directory);
+                }
+                Box::pin(async move {
+                    dialog
+                        .pick_folder()
+                        .await
+                        .map(|f| vec![f.path().to_path_buf()])
+                        .unwrap_or_default()
+                })
+            }
+        };
+        Self { kind, future }
+    }
+
+    /// 轮询对话框，关闭后返回所选路径（取消时为空）
+    pub fn poll(&mut self, ctx: &egui::Context) -> Poll<Vec<PathBuf>> {
+        let waker = Waker::from(Arc::new(RepaintWaker(ctx.clone())));
+        self.future.as_mut().poll(&mut Context::from_waker(&waker))
+    }
+}
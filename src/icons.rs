@@ -1,102 +1,152 @@
 //! Material Icons 工具模块
 
+use std::sync::OnceLock;
+
+use ab_glyph::Font as _;
 use eframe::egui;
 
 /// Material Icons 图标字符映射
+///
+/// 图标表按需取用，未用到的常量保留以便后续扩展。
+#[allow(dead_code)]
 pub mod icon {
+    use super::Icon;
+
     // 文件相关
-    pub const FOLDER: &str = "\u{e2c7}";           // folder
-    pub const FOLDER_OPEN: &str = "\u{e2c8}";      // folder_open
-    pub const INSERT_DRIVE_FILE: &str = "\u{e24d}"; // insert_drive_file
-    pub const FILE_UPLOAD: &str = "\u{e2c6}";      // file_upload
-    pub const FILE_DOWNLOAD: &str = "\u{e2c4}";    // file_download
+    pub const FOLDER: Icon = Icon::new("\u{e2c7}", "文件夹");           // folder
+    pub const FOLDER_OPEN: Icon = Icon::new("\u{e2c8}", "...");      // folder_open
+    pub const INSERT_DRIVE_FILE: Icon = Icon::new("\u{e24d}", "文件"); // insert_drive_file
+    pub const FILE_UPLOAD: Icon = Icon::new("\u{e2c6}", "上传");      // file_upload
+    pub const FILE_DOWNLOAD: Icon = Icon::new("\u{e2c4}", "下载");    // file_download
     
     // 图片相关
-    pub const IMAGE: &str = "\u{e3f4}";            // image
-    pub const PHOTO_LIBRARY: &str = "\u{e413}";    // photo_library
-    pub const CAMERA_ALT: &str = "\u{e3b0}";       // camera_alt
+    pub const IMAGE: Icon = Icon::new("\u{e3f4}", "图片");            // image
+    pub const PHOTO_LIBRARY: Icon = Icon::new("\u{e413}", "图库");    // photo_library
+    pub const CAMERA_ALT: Icon = Icon::new("\u{e3b0}", "相机");       // camera_alt
     
     // 操作相关
-    pub const SAVE: &str = "\u{e161}";             // save
-    pub const DELETE: &str = "\u{e872}";           // delete
-    pub const CLEAR: &str = "\u{e0b8}";            // clear
-    pub const SETTINGS: &str = "\u{e8b8}";         // settings
-    pub const REFRESH: &str = "\u{e5d5}";          // refresh
+    pub const SAVE: Icon = Icon::new("\u{e161}", "保存");             // save
+    pub const DELETE: Icon = Icon::new("\u{e872}", "删除");           // delete
+    pub const CLEAR: Icon = Icon::new("\u{e0b8}", "x");            // clear
+    pub const SETTINGS: Icon = Icon::new("\u{e8b8}", "设置");         // settings
+    pub const REFRESH: Icon = Icon::new("\u{e5d5}", "刷新");          // refresh
+    pub const LOCK: Icon = Icon::new("\u{e897}", "锁");               // lock
+    pub const LOCK_OPEN: Icon = Icon::new("\u{e898}", "解锁");         // lock_open
     
     // 导航相关
-    pub const ARROW_BACK: &str = "\u{e5c4}";       // arrow_back
-    pub const ARROW_FORWARD: &str = "\u{e5c8}";    // arrow_forward
-    pub const ARROW_UPWARD: &str = "\u{e5d8}";     // arrow_upward
-    pub const ARROW_DOWNWARD: &str = "\u{e5db}";   // arrow_downward
-    pub const FIRST_PAGE: &str = "\u{e5dc}";       // first_page
-    pub const LAST_PAGE: &str = "\u{e5dd}";        // last_page
+    pub const ARROW_BACK: Icon = Icon::new("\u{e5c4}", "<");       // arrow_back
+    pub const ARROW_FORWARD: Icon = Icon::new("\u{e5c8}", ">");    // arrow_forward
+    pub const ARROW_UPWARD: Icon = Icon::new("\u{e5d8}", "^");     // arrow_upward
+    pub const ARROW_DOWNWARD: Icon = Icon::new("\u{e5db}", "v");   // arrow_downward
+    pub const FIRST_PAGE: Icon = Icon::new("\u{e5dc}", "|<");       // first_page
+    pub const LAST_PAGE: Icon = Icon::new("\u{e5dd}", ">|");        // last_page
     
     // 播放控制
-    pub const PLAY_ARROW: &str = "\u{e037}";       // play_arrow
-    pub const PAUSE: &str = "\u{e034}";            // pause
-    pub const STOP: &str = "\u{e047}";             // stop
+    pub const PLAY_ARROW: Icon = Icon::new("\u{e037}", ">");       // play_arrow
+    pub const PAUSE: Icon = Icon::new("\u{e034}", "||");            // pause
+    pub const STOP: Icon = Icon::new("\u{e047}", "[]");             // stop
     
     // 选择相关
-    pub const CHECK: &str = "\u{e5ca}";            // check
-    pub const CLOSE: &str = "\u{e5cd}";            // close
-    pub const CANCEL: &str = "\u{e5c9}";           // cancel
-    pub const RADIO_BUTTON_UNCHECKED: &str = "\u{e836}"; // radio_button_unchecked
-    pub const RADIO_BUTTON_CHECKED: &str = "\u{e837}";   // radio_button_checked
+    pub const CHECK: Icon = Icon::new("\u{e5ca}", "√");            // check
+    pub const CLOSE: Icon = Icon::new("\u{e5cd}", "x");            // close
+    pub const CANCEL: Icon = Icon::new("\u{e5c9}", "x");           // cancel
+    pub const RADIO_BUTTON_UNCHECKED: Icon = Icon::new("\u{e836}", "( )"); // radio_button_unchecked
+    pub const RADIO_BUTTON_CHECKED: Icon = Icon::new("\u{e837}", "(*)");   // radio_button_checked
     
     // 信息相关
-    pub const INFO: &str = "\u{e88e}";             // info
-    pub const HELP: &str = "\u{e887}";             // help
-    pub const WARNING: &str = "\u{e002}";          // warning
-    pub const ERROR: &str = "\u{e000}";            // error
+    pub const INFO: Icon = Icon::new("\u{e88e}", "i");             // info
+    pub const HELP: Icon = Icon::new("\u{e887}", "?");             // help
+    pub const WARNING: Icon = Icon::new("\u{e002}", "!");          // warning
+    pub const ERROR: Icon = Icon::new("\u{e000}", "!");            // error
     
     // 编辑相关
-    pub const EDIT: &str = "\u{e3c9}";             // edit
-    pub const CUT: &str = "\u{e08b}";              // content_cut
-    pub const COPY: &str = "\u{e14d}";             // content_copy
-    pub const PASTE: &str = "\u{e14f}";            // content_paste
+    pub const EDIT: Icon = Icon::new("\u{e3c9}", "编辑");             // edit
+    pub const CUT: Icon = Icon::new("\u{e08b}", "剪切");              // content_cut
+    pub const COPY: Icon = Icon::new("\u{e14d}", "复制");             // content_copy
+    pub const PASTE: Icon = Icon::new("\u{e14f}", "粘贴");            // content_paste
     
     // 键盘相关
-    pub const KEYBOARD: &str = "\u{e312}";         // keyboard
-    pub const KEYBOARD_ARROW_UP: &str = "\u{e316}";    // keyboard_arrow_up
-    pub const KEYBOARD_ARROW_DOWN: &str = "\u{e313}";  // keyboard_arrow_down
-    pub const KEYBOARD_ARROW_LEFT: &str = "\u{e314}";  // keyboard_arrow_left
-    pub const KEYBOARD_ARROW_RIGHT: &str = "\u{e315}"; // keyboard_arrow_right
+    pub const KEYBOARD: Icon = Icon::new("\u{e312}", "键盘");         // keyboard
+    pub const KEYBOARD_ARROW_UP: Icon = Icon::new("\u{e316}", "^");    // keyboard_arrow_up
+    pub const KEYBOARD_ARROW_DOWN: Icon = Icon::new("\u{e313}", "v");  // keyboard_arrow_down
+    pub const KEYBOARD_ARROW_LEFT: Icon = Icon::new("\u{e314}", "<");  // keyboard_arrow_left
+    pub const KEYBOARD_ARROW_RIGHT: Icon = Icon::new("\u{e315}", ">"); // keyboard_arrow_right
     
     // 其他
-    pub const MENU: &str = "\u{e5d2}";             // menu
-    pub const MORE_VERT: &str = "\u{e5d4}";        // more_vert
-    pub const MORE_HORIZ: &str = "\u{e5d3}";       // more_horiz
-    pub const SEARCH: &str = "\u{e8b6}";           // search
-    pub const ZOOM_IN: &str = "\u{e8ff}";          // zoom_in
-    pub const ZOOM_OUT: &str = "\u{e900}";         // zoom_out
-    pub const FULLSCREEN: &str = "\u{e5d0}";       // fullscreen
-    pub const FULLSCREEN_EXIT: &str = "\u{e5d1}";  // fullscreen_exit
-    pub const GRID_ON: &str = "\u{e3ec}";          // grid_on
-    pub const GRID_OFF: &str = "\u{e3eb}";         // grid_off
-    pub const CROP: &str = "\u{e3be}";             // crop
-    pub const STRAIGHTEN: &str = "\u{e41c}";       // straighten
-    pub const FLIP: &str = "\u{e3e8}";             // flip
-    pub const ROTATE_LEFT: &str = "\u{e419}";      // rotate_left
-    pub const ROTATE_RIGHT: &str = "\u{e41a}";     // rotate_right
+    pub const MENU: Icon = Icon::new("\u{e5d2}", "菜单");             // menu
+    pub const MORE_VERT: Icon = Icon::new("\u{e5d4}", ":");        // more_vert
+    pub const MORE_HORIZ: Icon = Icon::new("\u{e5d3}", "...");       // more_horiz
+    pub const SEARCH: Icon = Icon::new("\u{e8b6}", "搜索");           // search
+    pub const ZOOM_IN: Icon = Icon::new("\u{e8ff}", "+");          // zoom_in
+    pub const ZOOM_OUT: Icon = Icon::new("\u{e900}", "-");         // zoom_out
+    pub const FULLSCREEN: Icon = Icon::new("\u{e5d0}", "全屏");       // fullscreen
+    pub const FULLSCREEN_EXIT: Icon = Icon::new("\u{e5d1}", "退出全屏");  // fullscreen_exit
+    pub const GRID_ON: Icon = Icon::new("\u{e3ec}", "网格");          // grid_on
+    pub const GRID_OFF: Icon = Icon::new("\u{e3eb}", "无网格");         // grid_off
+    pub const CROP: Icon = Icon::new("\u{e3be}", "裁剪");             // crop
+    pub const STRAIGHTEN: Icon = Icon::new("\u{e41c}", "标尺");       // straighten
+    pub const FLIP: Icon = Icon::new("\u{e3e8}", "翻转");             // flip
+    pub const FLIP_VERTICAL: Icon = Icon::new("\u{e8d5}", "上下");    // swap_vert
+    pub const ROTATE_LEFT: Icon = Icon::new("\u{e419}", "左转");      // rotate_left
+    pub const ROTATE_RIGHT: Icon = Icon::new("\u{e41a}", "右转");     // rotate_right
 }
 
-/// 获取图标字体 ID（现在使用 Proportional 字体家族，让 fallback 机制工作）
-pub fn icon_font_id(size: f32) -> egui::FontId {
-    egui::FontId::new(size, egui::FontFamily::Proportional)
+/// 图标：图标字体可用时显示字形，否则显示简短的文字替代
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Icon {
+    glyph: &'static str,
+    fallback: &'static str,
 }
 
-/// 创建一个图标文本
-pub fn icon_text(icon: &str, size: f32) -> egui::RichText {
-    egui::RichText::new(icon).font(icon_font_id(size))
+impl Icon {
+    pub const fn new(glyph: &'static str, fallback: &'static str) -> Self {
+        Self { glyph, fallback }
+    }
+
+    /// 实际显示的文字：字体加载失败或字体中缺少该字形时返回文字替代
+    pub fn as_str(self) -> &'static str {
+        if has_glyphs(self.glyph) { self.glyph } else { self.fallback }
+    }
+}
+
+impl std::fmt::Display for Icon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<Icon> for egui::WidgetText {
+    fn from(icon: Icon) -> Self {
+        icon.as_str().into()
+    }
+}
+
+/// 启动时解析成功的图标字体，解析失败时为 `None`
+static ICON_FONT: OnceLock<Option<ab_glyph::FontRef<'static>>> = OnceLock::new();
+
+/// 校验并记录图标字体，返回字体是否可用
+///
+/// 无效的字体数据交给 egui 会直接崩溃，因此需在 `set_fonts` 之前调用，失败时不要注册该字体。
+pub fn init_icon_font(data: &'static [u8]) -> bool {
+    ICON_FONT
+        .get_or_init(|| ab_glyph::FontRef::try_from_slice(data).ok())
+        .is_some()
+}
+
+/// 图标字体中是否包含全部字符
+fn has_glyphs(text: &str) -> bool {
+    match ICON_FONT.get() {
+        Some(Some(font)) => text.chars().all(|c| font.glyph_id(c).0 != 0),
+        _ => false,
+    }
 }
 
-/// 创建一个带图标和文字的文本
-pub fn icon_with_text(icon: &str, text: &str, size: f32) -> egui::RichText {
-    egui::RichText::new(format!("{} {}", icon, text))
-        .font(icon_font_id(size))
+/// 获取图标字体 ID（现在使用 Proportional 字体家族，让 fallback 机制工作）
+pub fn icon_font_id(size: f32) -> egui::FontId {
+    egui::FontId::new(size, egui::FontFamily::Proportional)
 }
 
-/// 创建一个带图标的按钮
-pub fn icon_button(ui: &mut egui::Ui, icon: &str, size: f32) -> egui::Response {
-    ui.button(icon_text(icon, size))
+/// 创建一个图标文本
+pub fn icon_text(icon: Icon, size: f32) -> egui::RichText {
+    egui::RichText::new(icon.as_str()).font(icon_font_id(size))
 }
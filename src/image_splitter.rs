@@ -1,13 +1,640 @@
-use image::{DynamicImage, ImageReader};
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
+/// JPEG 默认编码质量，配置中的质量超出 1-100 时也回落到此值
+pub const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+/// 有效的 JPEG 质量：1-100 原样使用，其余（如手改配置文件得到的 0）回落到默认值
+pub fn valid_jpeg_quality(quality: u8) -> u8 {
+    if (1..=100).contains(&quality) {
+        quality
+    } else {
+        DEFAULT_JPEG_QUALITY
+    }
+}
+
+/// 两条分割线之间的最小间距（相对比例），小于此值视为重复
+pub const LINE_EPSILON: f32 = 0.0005;
+
+/// 分块宽或高小于该像素数时，批处理计划中提示分块过小
+pub const MIN_USEFUL_TILE_PX: u32 = 8;
+
+/// WebP 支持的最大宽高，超出时分块改存为 PNG
+pub const WEBP_MAX_DIMENSION: u32 = 16383;
+
+/// 分块输出格式（未开启保持源格式时使用）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputFormat {
+    Jpeg,
+    /// 默认无损，避免截图中的文字被 JPEG 压糊
+    #[default]
+    Png,
+    Bmp,
+    /// WebP，可选无损或按质量有损压缩
+    WebP,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 4] = [OutputFormat::Jpeg, OutputFormat::Png, OutputFormat::Bmp, OutputFormat::WebP];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg => "JPEG",
+            OutputFormat::Png => "PNG",
+            OutputFormat::Bmp => "BMP",
+            OutputFormat::WebP => "WebP",
+        }
+    }
+
+    pub fn image_format(self) -> ImageFormat {
+        match self {
+            OutputFormat::Jpeg => ImageFormat::Jpeg,
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Bmp => ImageFormat::Bmp,
+            OutputFormat::WebP => ImageFormat::WebP,
+        }
+    }
+
+    /// 输出文件扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Png => "png",
+            OutputFormat::Bmp => "bmp",
+            OutputFormat::WebP => "webp",
+        }
+    }
+}
+
+/// PNG 压缩级别
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+impl PngCompression {
+    pub const ALL: [PngCompression; 3] = [PngCompression::Fast, PngCompression::Default, PngCompression::Best];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PngCompression::Fast => "快速",
+            PngCompression::Default => "均衡",
+            PngCompression::Best => "最小体积",
+        }
+    }
+
+    fn png_compression(self) -> png::Compression {
+        match self {
+            PngCompression::Fast => png::Compression::Fast,
+            PngCompression::Default => png::Compression::Balanced,
+            PngCompression::Best => png::Compression::High,
+        }
+    }
+
+    fn compression_type(self) -> image::codecs::png::CompressionType {
+        use image::codecs::png::CompressionType;
+        match self {
+            PngCompression::Fast => CompressionType::Fast,
+            PngCompression::Default => CompressionType::Default,
+            PngCompression::Best => CompressionType::Best,
+        }
+    }
+}
+
+/// PNG 行过滤方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PngFilter {
+    NoFilter,
+    Sub,
+    Up,
+    Avg,
+    Paeth,
+    /// 逐行自动选择（通常体积最小）
+    #[default]
+    Adaptive,
+}
+
+impl PngFilter {
+    pub const ALL: [PngFilter; 6] = [
+        PngFilter::Adaptive,
+        PngFilter::NoFilter,
+        PngFilter::Sub,
+        PngFilter::Up,
+        PngFilter::Avg,
+        PngFilter::Paeth,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PngFilter::NoFilter => "无",
+            PngFilter::Sub => "Sub",
+            PngFilter::Up => "Up",
+            PngFilter::Avg => "Avg",
+            PngFilter::Paeth => "Paeth",
+            PngFilter::Adaptive => "自适应",
+        }
+    }
+
+    fn png_filter(self) -> png::Filter {
+        match self {
+            PngFilter::NoFilter => png::Filter::NoFilter,
+            PngFilter::Sub => png::Filter::Sub,
+            PngFilter::Up => png::Filter::Up,
+            PngFilter::Avg => png::Filter::Avg,
+            PngFilter::Paeth => png::Filter::Paeth,
+            PngFilter::Adaptive => png::Filter::Adaptive,
+        }
+    }
+
+    fn filter_type(self) -> image::codecs::png::FilterType {
+        use image::codecs::png::FilterType;
+        match self {
+            PngFilter::NoFilter => FilterType::NoFilter,
+            PngFilter::Sub => FilterType::Sub,
+            PngFilter::Up => FilterType::Up,
+            PngFilter::Avg => FilterType::Avg,
+            PngFilter::Paeth => FilterType::Paeth,
+            PngFilter::Adaptive => FilterType::Adaptive,
+        }
+    }
+}
+
+/// 编码分块时使用的各格式参数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// JPEG 质量 (1-100)
+    pub jpeg_quality: u8,
+    pub png_compression: PngCompression,
+    pub png_filter: PngFilter,
+    /// 量化为调色板 PNG 时的最大颜色数，`None` 表示不量化
+    pub png_max_colors: Option<u16>,
+    /// WebP 无损编码，否则按 `jpeg_quality` 有损压缩
+    pub webp_lossless: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            png_compression: PngCompression::default(),
+            png_filter: PngFilter::default(),
+            png_max_colors: None,
+            webp_lossless: true,
+        }
+    }
+}
+
+/// 自动网格检测的默认灵敏度
+pub const DEFAULT_DETECT_SENSITIVITY: u8 = 30;
+
+/// 多尺寸导出的默认文件名后缀
+pub const DEFAULT_SCALE_SUFFIX: &str = "@{scale}x";
+
+/// 分割线的含义
+///
+/// - `SharedEdge`：分割线是相邻分块的共用边界，分块之间既不重叠也无空隙（平铺用）。
+/// - `TrimWithBleed`：分割线是裁切线，每个分块越过其内部的每条裁切线向外多取 `bleed_px` 像素，
+///   相邻分块因此重叠 `2 * bleed_px`；图片外边缘没有裁切线，不外扩（印刷出血用）。
+/// - `Gutters`：排序后的分割线两两成对，每对之间是丢弃的间隔带（面板之间的留白），只导出其余内容区域；
+///   末尾落单的一条线按普通分割线处理。配对完全由线的顺序决定，随分割线一起保存即可还原。
+/// - `Lossless`：无损拼回。与共用边界相同，但均匀网格按整数均分，余下的像素依次分给前面的分块，
+///   不均匀的线四舍五入到最近的像素；每个方向的分块宽度之和恰好等于图片尺寸，按原点放回可无缝拼出原图。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineMode {
+    #[default]
+    SharedEdge,
+    TrimWithBleed,
+    Gutters,
+    Lossless,
+}
+
+impl LineMode {
+    pub const ALL: [LineMode; 4] = [LineMode::SharedEdge, LineMode::TrimWithBleed, LineMode::Gutters, LineMode::Lossless];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineMode::SharedEdge => "共用边界",
+            LineMode::TrimWithBleed => "裁切线 + 出血",
+            LineMode::Gutters => "成对间隔带",
+            LineMode::Lossless => "无损拼回",
+        }
+    }
+
+    /// 一个方向上各分块所占的相对区间（不含出血），分割线需已排序
+    pub fn spans(self, lines: &[f32]) -> Vec<(f32, f32)> {
+        match self {
+            LineMode::Gutters => content_spans(lines, 0.0, 1.0),
+            LineMode::SharedEdge | LineMode::TrimWithBleed | LineMode::Lossless => {
+                let mut bounds = Vec::with_capacity(lines.len() + 2);
+                bounds.push(0.0);
+                bounds.extend_from_slice(lines);
+                bounds.push(1.0);
+                bounds.windows(2).map(|w| (w[0], w[1])).collect()
+            }
+        }
+    }
+
+    /// 一个方向上被丢弃的间隔带（相对位置），只有 `Gutters` 模式才有
+    pub fn gutters(self, lines: &[f32]) -> Vec<(f32, f32)> {
+        match self {
+            LineMode::Gutters => lines.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect(),
+            LineMode::SharedEdge | LineMode::TrimWithBleed | LineMode::Lossless => Vec::new(),
+        }
+    }
+}
+
+/// 一个方向的分割线是否与平均分割的位置一致
+fn is_uniform_lines(lines: &[f32]) -> bool {
+    let count = lines.len() + 1;
+    lines
+        .iter()
+        .enumerate()
+        .all(|(i, &p)| (p - (i + 1) as f32 / count as f32).abs() < LINE_EPSILON)
+}
+
+/// 去掉成对间隔带后剩下的内容区间，落单的末条线只做切分；宽度为 0 的区间被省略
+fn content_spans<T: Copy + PartialOrd>(cuts: &[T], start: T, end: T) -> Vec<(T, T)> {
+    let mut spans = Vec::new();
+    let mut from = start;
+    for pair in cuts.chunks(2) {
+        let (gutter_start, gutter_end) = (pair[0], pair[pair.len() - 1]);
+        if gutter_start > from {
+            spans.push((from, gutter_start));
+        }
+        if gutter_end > from {
+            from = gutter_end;
+        }
+    }
+    if end > from {
+        spans.push((from, end));
+    }
+    spans
+}
+
+/// 分块文件命名方式
+///
+/// - `RowCol`：`{名称}_{行}_{列}`，行列从 1 开始，按行列总数补零到相同位数。
+/// - `ZoomXY`：`{名称}/{z}/{x}/{y}`，按瓦片地图约定建立嵌套文件夹，x 为列、y 为行（从 0 开始），
+///   z 为能容纳网格的最小缩放级别（2^z ≥ 行列数中的较大者）。
+/// - `Morton`：`{名称}_{序号}`，序号为列、行二进制位交错得到的 Z 序索引，补零到相同位数。
+/// - `Template`：按 `filename_template` 生成，见 [`render_file_template`]。
+///
+/// `ZoomXY`、`Morton` 只适用于均匀网格，分割线不均匀时回落为 `RowCol`。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NamingScheme {
+    #[default]
+    RowCol,
+    ZoomXY,
+    Morton,
+    Template,
+}
+
+impl NamingScheme {
+    pub const ALL: [NamingScheme; 4] = [NamingScheme::RowCol, NamingScheme::ZoomXY, NamingScheme::Morton, NamingScheme::Template];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NamingScheme::RowCol => "行_列",
+            NamingScheme::ZoomXY => "z/x/y 文件夹",
+            NamingScheme::Morton => "Z 序索引",
+            NamingScheme::Template => "自定义模板",
+        }
+    }
+}
+
+/// 分块的编号与写入顺序
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileOrder {
+    /// 先从左到右，再从上到下
+    #[default]
+    RowMajor,
+    /// 先从上到下，再从左到右
+    ColumnMajor,
+}
+
+impl TileOrder {
+    pub const ALL: [TileOrder; 2] = [TileOrder::RowMajor, TileOrder::ColumnMajor];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TileOrder::RowMajor => "按行",
+            TileOrder::ColumnMajor => "按列",
+        }
+    }
+
+    /// 分块在 `rows` x `cols` 网格中从 1 开始的序号
+    pub fn index(self, row: usize, col: usize, rows: usize, cols: usize) -> usize {
+        match self {
+            TileOrder::RowMajor => row * cols + col + 1,
+            TileOrder::ColumnMajor => col * rows + row + 1,
+        }
+    }
+}
+
+/// 默认的文件名模板，与 `RowCol` 命名一致
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{name}_{row}_{col}.{ext}";
+
+/// 文件名模板支持的占位符：源文件名、行号、列号、序号（均从 1 开始）和扩展名
+pub const FILENAME_PLACEHOLDERS: [&str; 5] = ["name", "row", "col", "index", "ext"];
+
+/// 按模板生成文件名，`value` 给出各占位符的取值
+///
+/// 模板不含 `{ext}` 时自动追加扩展名。遇到未知占位符、未闭合的括号，或模板无法区分各分块
+/// （既没有 `{index}` 也没有同时包含 `{row}` 和 `{col}`）时返回错误。
+pub fn render_file_template(template: &str, value: impl Fn(&str) -> String) -> anyhow::Result<String> {
+    let mut output = String::new();
+    let mut used = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        output.push_str(&rest[..start]);
+        if rest[start..].starts_with('}') {
+            anyhow::bail!("模板中的 }} 没有对应的 {{");
+        }
+        let Some(len) = rest[start + 1..].find('}') else {
+            anyhow::bail!("模板中的 {{ 没有闭合: {}", &rest[start..]);
+        };
+        let token = &rest[start + 1..start + 1 + len];
+        if !FILENAME_PLACEHOLDERS.contains(&token) {
+            anyhow::bail!("未知的占位符 {{{}}}，可用: {}", token, FILENAME_PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(" "));
+        }
+        output.push_str(&value(token));
+        used.push(token);
+        rest = &rest[start + len + 2..];
+    }
+    output.push_str(rest);
+    let distinct = used.contains(&"index") || (used.contains(&"row") && used.contains(&"col"));
+    if !distinct {
+        anyhow::bail!("模板需包含 {{index}} 或同时包含 {{row}} 和 {{col}}，否则分块会重名");
+    }
+    if !used.contains(&"ext") {
+        output = format!("{}.{}", output, value("ext"));
+    }
+    Ok(output)
+}
+
+/// 从 1 开始的序号按总数补零，使文件管理器按名称排序时与网格顺序一致，如共 12 行时第 3 行为 `03`
+pub fn padded_index(number: usize, total: usize) -> String {
+    format!("{:0width$}", number, width = total.to_string().len())
+}
+
+/// 检查文件名模板是否有效，错误信息说明有问题的部分
+pub fn validate_file_template(template: &str) -> anyhow::Result<()> {
+    render_file_template(template, |_| String::from("1")).map(|_| ())
+}
+
+/// 把 x、y 的二进制位交错得到 Z 序（Morton）索引，x 占低位
+fn morton_index(x: u32, y: u32) -> u64 {
+    let spread = |v: u32| {
+        let mut v = v as u64;
+        v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+        v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+        v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+        v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+        v = (v | (v << 1)) & 0x5555_5555_5555_5555;
+        v
+    };
+    spread(x) | (spread(y) << 1)
+}
+
+/// 分块旋转角度（顺时针）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rotation {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Rotation {
+    pub const ALL: [Rotation; 3] = [Rotation::Rotate90, Rotation::Rotate180, Rotation::Rotate270];
+
+    /// 旋转角度（度）
+    pub fn degrees(self) -> u32 {
+        match self {
+            Rotation::Rotate90 => 90,
+            Rotation::Rotate180 => 180,
+            Rotation::Rotate270 => 270,
+        }
+    }
+
+    /// 对图片应用旋转
+    pub fn apply(self, img: &DynamicImage) -> DynamicImage {
+        match self {
+            Rotation::Rotate90 => img.rotate90(),
+            Rotation::Rotate180 => img.rotate180(),
+            Rotation::Rotate270 => img.rotate270(),
+        }
+    }
+
+    /// 旋转后宽高是否互换
+    pub fn swaps_dimensions(self) -> bool {
+        self != Rotation::Rotate180
+    }
+
+    /// 在 `current` 的基础上再顺时针或逆时针转 90°，转回原方向时返回 `None`
+    pub fn turned(current: Option<Rotation>, clockwise: bool) -> Option<Rotation> {
+        let degrees = current.map_or(0, Rotation::degrees) + if clockwise { 90 } else { 270 };
+        Rotation::ALL.into_iter().find(|r| r.degrees() == degrees % 360)
+    }
+}
+
+/// 单个分块的旋转设置
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CellRotation {
+    pub row: usize,
+    pub col: usize,
+    pub rotation: Rotation,
+}
+
+/// 合并导出的相邻分块，以左上角分块为锚点跨 `rows` 行 `cols` 列，导出为一个文件
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CellMerge {
+    pub row: usize,
+    pub col: usize,
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl CellMerge {
+    /// 由一组分块得到合并区域，分块须恰好组成至少两块的完整矩形
+    pub fn from_cells(cells: &[(usize, usize)]) -> Result<Self, String> {
+        let mut cells = cells.to_vec();
+        cells.sort_unstable();
+        cells.dedup();
+        if cells.len() < 2 {
+            return Err("至少需要选中两个分块".to_string());
+        }
+        let (first, last) = (cells[0], cells[cells.len() - 1]);
+        let col_min = cells.iter().map(|c| c.1).min().unwrap_or(0);
+        let col_max = cells.iter().map(|c| c.1).max().unwrap_or(0);
+        let merge = Self {
+            row: first.0,
+            col: col_min,
+            rows: last.0 - first.0 + 1,
+            cols: col_max - col_min + 1,
+        };
+        if merge.rows * merge.cols != cells.len() {
+            return Err("选中的分块必须组成一个完整的矩形".to_string());
+        }
+        Ok(merge)
+    }
+
+    /// 是否包含指定分块
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        (self.row..self.row + self.rows).contains(&row) && (self.col..self.col + self.cols).contains(&col)
+    }
+
+    /// 包含的全部分块 (行, 列)
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.row..self.row + self.rows).flat_map(move |row| (self.col..self.col + self.cols).map(move |col| (row, col)))
+    }
+
+    fn overlaps(&self, other: &CellMerge) -> bool {
+        self.row < other.row + other.rows
+            && other.row < self.row + self.rows
+            && self.col < other.col + other.cols
+            && other.col < self.col + self.cols
+    }
+}
+
+/// 自由裁剪区域（相对图片尺寸的归一化坐标 0.0 - 1.0）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CropRegion {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl CropRegion {
+    /// 换算为像素区域 `(x, y, 宽, 高)`，超出图片的部分被截掉，面积为 0 时返回 `None`
+    pub fn to_pixels(&self, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+        let x0 = (self.x.clamp(0.0, 1.0) * width as f32) as u32;
+        let y0 = (self.y.clamp(0.0, 1.0) * height as f32) as u32;
+        let x1 = ((self.x + self.width).clamp(0.0, 1.0) * width as f32) as u32;
+        let y1 = ((self.y + self.height).clamp(0.0, 1.0) * height as f32) as u32;
+        (x1 > x0 && y1 > y0).then(|| (x0, y0, x1 - x0, y1 - y0))
+    }
+}
+
+/// 分割线的显示样式，只影响预览，不影响导出
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineStyle {
+    /// 显示在分割线旁的标注（如「页眉」「页脚」），为空不显示
+    pub label: String,
+    /// 不透明度 (0.1 - 1.0)，可将已确定的线调暗
+    pub opacity: f32,
+}
+
+impl LineStyle {
+    pub const DEFAULT: LineStyle = LineStyle { label: String::new(), opacity: 1.0 };
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::DEFAULT
+    }
+}
+
+/// 供 `SplitConfig::line_style` 返回引用的默认样式
+static DEFAULT_LINE_STYLE: LineStyle = LineStyle::DEFAULT;
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 /// 分割配置
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SplitConfig {
     pub rows: usize,
     pub cols: usize,
     pub h_lines: Vec<f32>, // 水平分割线位置 (0.0 - 1.0)
     pub v_lines: Vec<f32>, // 垂直分割线位置 (0.0 - 1.0)
+    /// 与 `h_lines` 按下标对应的显示样式，缺少的条目使用默认样式
+    pub h_line_styles: Vec<LineStyle>,
+    /// 与 `v_lines` 按下标对应的显示样式，缺少的条目使用默认样式
+    pub v_line_styles: Vec<LineStyle>,
+    /// 按源文件格式保存分块（否则使用 `output_format`）
+    pub keep_source_format: bool,
+    /// 跳过已存在的输出文件，用于续跑中断的批处理
+    pub skip_existing: bool,
+    /// 在分块中写入来源信息（JPEG 注释段 / PNG 文本块）
+    pub embed_source_info: bool,
+    /// 各分块裁剪后的旋转（未列出的分块不旋转）
+    pub cell_rotations: Vec<CellRotation>,
+    /// 额外导出的自由裁剪区域，与网格分块互不影响
+    pub crop_regions: Vec<CropRegion>,
+    /// 合并导出的相邻分块，被合并的分块不再单独导出
+    pub cell_merges: Vec<CellMerge>,
+    /// 输出格式
+    pub output_format: OutputFormat,
+    /// JPEG 质量 (1-100)
+    pub jpeg_quality: u8,
+    /// PNG 压缩级别
+    pub png_compression: PngCompression,
+    /// PNG 行过滤方式
+    pub png_filter: PngFilter,
+    /// 将 PNG 分块量化为 8 位调色板图片
+    pub png_quantize: bool,
+    /// 量化时的最大颜色数 (2-256)
+    pub png_max_colors: u16,
+    /// WebP 无损编码；关闭时按 JPEG 质量有损压缩
+    pub webp_lossless: bool,
+    /// 分割线的含义（共用边界 / 裁切线加出血）
+    pub line_mode: LineMode,
+    /// 裁切线模式下每侧的出血宽度（像素）
+    pub bleed_px: u32,
+    /// 每个分块在与相邻分块共用的边上向内收缩的像素数，图片外边缘不收缩
+    pub gutter_px: u32,
+    /// 分块文件命名方式
+    pub naming_scheme: NamingScheme,
+    /// 分块编号（模板中的 `{index}`）与写入顺序
+    pub tile_order: TileOrder,
+    /// 每张图片的输出写入输出目录下以图片名称命名的子文件夹
+    pub per_image_subfolder: bool,
+    /// 锁定后界面上不再修改分割线（用于已调好的独立配置）
+    pub locked: bool,
+    /// 按 EXIF 方向信息旋转/翻转后再分割（默认开启，关闭时使用原始像素）
+    pub apply_exif_orientation: bool,
+    /// 分割前整张图片的旋转（顺时针，在 EXIF 校正之后），用于校正扫描方向
+    pub image_rotation: Option<Rotation>,
+    /// 分割前左右镜像整张图片（在 `image_rotation` 之后）
+    pub image_flip_h: bool,
+    /// 分割前上下镜像整张图片（在 `image_rotation` 之后）
+    pub image_flip_v: bool,
+    /// 分割前先裁掉的外边距（像素，如扫描件的黑边），分割线位置相对于裁掉边距后的内容区域
+    pub margin_top: u32,
+    pub margin_right: u32,
+    pub margin_bottom: u32,
+    pub margin_left: u32,
+    /// 多尺寸导出：每个分块按 `output_scales` 中的每个倍数各保存一份
+    pub multi_scale: bool,
+    /// 多尺寸导出的倍数，以分块像素尺寸为 1 倍
+    pub output_scales: Vec<f32>,
+    /// `Template` 命名方式使用的文件名模板
+    pub filename_template: String,
+    /// 非 1 倍文件名后缀模板，`{scale}` 替换为倍数，如 `@{scale}x` 得到 `name@2x.png`
+    pub scale_suffix: String,
+    /// 额外导出一张画出分割线的整图（`{名称}_grid.{扩展名}`），与分块导出互不影响
+    pub export_overview: bool,
+    /// 总览图在每个分块左上角标注行列号
+    pub overview_labels: bool,
+    /// 自动网格批处理：没有独立配置的图片按检测到的间隔带分割，检测失败时使用本配置的分割线
+    pub auto_detect_grid: bool,
+    /// 检测灵敏度 (0-100)，越高对间隔带颜色偏差和杂点越宽容
+    pub detect_sensitivity: u8,
+    /// 间隔带的背景参考色 (RGB)
+    pub detect_background: [u8; 3],
+    /// 统一画布尺寸：每个分块居中放到 `canvas_width` x `canvas_height` 的透明画布上，放不下时等比缩小
+    pub uniform_canvas: bool,
+    pub canvas_width: u32,
+    pub canvas_height: u32,
 }
 
 impl Default for SplitConfig {
@@ -17,6 +644,48 @@ impl Default for SplitConfig {
             cols: 1,
             h_lines: vec![],
             v_lines: vec![],
+            h_line_styles: vec![],
+            v_line_styles: vec![],
+            keep_source_format: false,
+            skip_existing: false,
+            embed_source_info: false,
+            cell_rotations: vec![],
+            crop_regions: vec![],
+            cell_merges: vec![],
+            output_format: OutputFormat::default(),
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            png_compression: PngCompression::default(),
+            png_filter: PngFilter::default(),
+            png_quantize: false,
+            png_max_colors: 256,
+            webp_lossless: true,
+            line_mode: LineMode::default(),
+            bleed_px: 0,
+            gutter_px: 0,
+            naming_scheme: NamingScheme::default(),
+            tile_order: TileOrder::default(),
+            per_image_subfolder: false,
+            locked: false,
+            apply_exif_orientation: true,
+            image_rotation: None,
+            image_flip_h: false,
+            image_flip_v: false,
+            margin_top: 0,
+            margin_right: 0,
+            margin_bottom: 0,
+            margin_left: 0,
+            multi_scale: false,
+            output_scales: vec![1.0, 2.0, 3.0],
+            scale_suffix: DEFAULT_SCALE_SUFFIX.to_string(),
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            export_overview: false,
+            overview_labels: true,
+            auto_detect_grid: false,
+            detect_sensitivity: DEFAULT_DETECT_SENSITIVITY,
+            detect_background: [255, 255, 255],
+            uniform_canvas: false,
+            canvas_width: 256,
+            canvas_height: 256,
         }
     }
 }
@@ -27,8 +696,7 @@ impl SplitConfig {
         let mut config = Self {
             rows,
             cols,
-            h_lines: vec![],
-            v_lines: vec![],
+            ..Default::default()
         };
         config.reset_to_default();
         config
@@ -36,131 +704,2552 @@ impl SplitConfig {
 
     /// 重置为平均分割
     pub fn reset_to_default(&mut self) {
+        self.make_even_grid(self.rows, self.cols);
+        self.h_line_styles.clear();
+        self.v_line_styles.clear();
+    }
+
+    /// 按给定行列数重新平均分布分割线
+    ///
+    /// 与 [`Self::reset_to_default`] 不同，保留仍然存在的线的样式，用于拖动调整后一键恢复均匀网格。
+    pub fn make_even_grid(&mut self, rows: usize, cols: usize) {
+        self.rows = rows.max(1);
+        self.cols = cols.max(1);
         self.h_lines = (1..self.rows)
             .map(|i| i as f32 / self.rows as f32)
             .collect();
         self.v_lines = (1..self.cols)
             .map(|i| i as f32 / self.cols as f32)
             .collect();
+        self.h_line_styles.truncate(self.h_lines.len());
+        self.v_line_styles.truncate(self.v_lines.len());
     }
 
-    /// 验证配置是否有效
-    pub fn is_valid(&self) -> bool {
-        self.h_lines.len() == self.rows.saturating_sub(1)
-            && self.v_lines.len() == self.cols.saturating_sub(1)
+    /// 平均分布分割线，并保证每个分块的宽高不小于 `min_cell` 像素
+    ///
+    /// 图片放不下当前行列数时减少行列数。发生减少时返回减少前的 `(行, 列)`。
+    pub fn equalize_with_min_cell(&mut self, width: u32, height: u32, min_cell: u32) -> Option<(usize, usize)> {
+        let (_, _, width, height) = self.content_rect(width, height);
+        let before = (self.h_lines.len() + 1, self.v_lines.len() + 1);
+        let min_cell = min_cell.max(1);
+        let max_rows = (height / min_cell).max(1) as usize;
+        let max_cols = (width / min_cell).max(1) as usize;
+        self.rows = before.0.min(max_rows);
+        self.cols = before.1.min(max_cols);
+        self.reset_to_default();
+        (before != (self.rows, self.cols)).then_some(before)
     }
-}
 
-/// 图片分割器
-pub struct ImageSplitter;
+    /// 分割线的显示样式
+    pub fn line_style(&self, horizontal: bool, idx: usize) -> &LineStyle {
+        let styles = if horizontal { &self.h_line_styles } else { &self.v_line_styles };
+        styles.get(idx).unwrap_or(&DEFAULT_LINE_STYLE)
+    }
 
-impl ImageSplitter {
-    /// 打开图片
-    pub fn open_image<P: AsRef<Path>>(path: P) -> anyhow::Result<DynamicImage> {
-        let img = ImageReader::open(path)?.decode()?;
-        Ok(img)
+    /// 设置分割线的显示样式，分割线不存在时忽略
+    pub fn set_line_style(&mut self, horizontal: bool, idx: usize, style: LineStyle) {
+        let (count, styles) = if horizontal {
+            (self.h_lines.len(), &mut self.h_line_styles)
+        } else {
+            (self.v_lines.len(), &mut self.v_line_styles)
+        };
+        if idx >= count {
+            return;
+        }
+        if styles.len() <= idx {
+            styles.resize(idx + 1, LineStyle::DEFAULT);
+        }
+        styles[idx] = style;
+        Self::trim_default_styles(styles);
     }
 
-    /// 分割图片
-    pub fn split_image(
-        img: &DynamicImage,
-        config: &SplitConfig,
-    ) -> anyhow::Result<Vec<Vec<DynamicImage>>> {
-        let (width, height) = (img.width(), img.height());
+    /// 按位置排序分割线，样式随分割线一起移动
+    ///
+    /// 新增分割线时先追加到末尾再调用，样式列表会用默认样式补齐到与分割线等长。
+    pub fn sort_lines(&mut self) {
+        Self::sort_with_styles(&mut self.h_lines, &mut self.h_line_styles);
+        Self::sort_with_styles(&mut self.v_lines, &mut self.v_line_styles);
+    }
 
-        // 计算分割边界（像素）- 使用截断方式与 Python 版本保持一致
-        let h_positions: Vec<u32> = std::iter::once(0)
-            .chain(config.h_lines.iter().map(|&p| (height as f32 * p) as u32))
-            .chain(std::iter::once(height))
-            .collect();
+    /// 删除分割线及其样式
+    pub fn remove_line(&mut self, horizontal: bool, idx: usize) {
+        let (lines, styles) = if horizontal {
+            (&mut self.h_lines, &mut self.h_line_styles)
+        } else {
+            (&mut self.v_lines, &mut self.v_line_styles)
+        };
+        if idx < lines.len() {
+            lines.remove(idx);
+        }
+        if idx < styles.len() {
+            styles.remove(idx);
+        }
+    }
 
-        let v_positions: Vec<u32> = std::iter::once(0)
-            .chain(config.v_lines.iter().map(|&p| (width as f32 * p) as u32))
-            .chain(std::iter::once(width))
-            .collect();
+    fn sort_with_styles(lines: &mut Vec<f32>, styles: &mut Vec<LineStyle>) {
+        styles.resize(lines.len(), LineStyle::DEFAULT);
+        let mut pairs: Vec<(f32, LineStyle)> = lines.drain(..).zip(styles.drain(..)).collect();
+        pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        (*lines, *styles) = pairs.into_iter().unzip();
+        Self::trim_default_styles(styles);
+    }
 
-        // 使用实际的线条数量来计算行列数（而不是依赖 config.rows/cols）
-        let actual_rows = config.h_lines.len() + 1;
-        let actual_cols = config.v_lines.len() + 1;
+    /// 去掉末尾的默认样式，未设置样式的配置保持为空列表
+    fn trim_default_styles(styles: &mut Vec<LineStyle>) {
+        while styles.last().is_some_and(LineStyle::is_default) {
+            styles.pop();
+        }
+    }
 
-        let mut result = Vec::with_capacity(actual_rows);
+    /// 指定分块的旋转设置
+    pub fn cell_rotation(&self, row: usize, col: usize) -> Option<Rotation> {
+        self.cell_rotations
+            .iter()
+            .find(|c| c.row == row && c.col == col)
+            .map(|c| c.rotation)
+    }
 
-        for row in 0..actual_rows {
-            let mut row_images = Vec::with_capacity(actual_cols);
-            let upper = h_positions[row];
-            let lower = h_positions[row + 1];
+    /// 设置指定分块的旋转，`None` 表示不旋转
+    pub fn set_cell_rotation(&mut self, row: usize, col: usize, rotation: Option<Rotation>) {
+        self.cell_rotations.retain(|c| !(c.row == row && c.col == col));
+        if let Some(rotation) = rotation {
+            self.cell_rotations.push(CellRotation { row, col, rotation });
+        }
+    }
 
-            for col in 0..actual_cols {
-                let left = v_positions[col];
-                let right = v_positions[col + 1];
+    /// 包含指定分块的合并区域
+    pub fn merge_at(&self, row: usize, col: usize) -> Option<&CellMerge> {
+        self.cell_merges.iter().find(|m| m.contains(row, col))
+    }
 
-                // 使用 crop_imm 代替 crop（不需要可变引用）
-                let cropped = img.crop_imm(left, upper, right - left, lower - upper);
-                row_images.push(cropped);
+    /// 合并一组相邻分块
+    ///
+    /// 选中已合并区域中的任一分块视为选中整个区域，合并后被包含的旧合并区域并入新区域。
+    /// 分块须组成完整矩形且位于当前网格内，否则返回错误说明。
+    pub fn merge_cells(&mut self, cells: &[(usize, usize)]) -> Result<CellMerge, String> {
+        let mut expanded = cells.to_vec();
+        for &(row, col) in cells {
+            if let Some(merge) = self.merge_at(row, col) {
+                expanded.extend(merge.cells());
             }
-            result.push(row_images);
         }
+        let merge = CellMerge::from_cells(&expanded)?;
+        let (rows, cols) = self.grid_size();
+        if merge.row + merge.rows > rows || merge.col + merge.cols > cols {
+            return Err("选中的分块超出当前网格".to_string());
+        }
+        self.cell_merges.retain(|m| !m.overlaps(&merge));
+        self.cell_merges.push(merge);
+        Ok(merge)
+    }
 
-        Ok(result)
+    /// 取消包含指定分块的合并，返回是否有合并被取消
+    pub fn unmerge_at(&mut self, row: usize, col: usize) -> bool {
+        let before = self.cell_merges.len();
+        self.cell_merges.retain(|m| !m.contains(row, col));
+        self.cell_merges.len() != before
     }
 
-    /// 批量处理图片
-    pub fn batch_process(
-        image_paths: &[PathBuf],
-        global_config: &SplitConfig,
-        overrides: &std::collections::HashMap<usize, SplitConfig>,
-        output_dir: &Path,
-        progress_callback: impl Fn(usize, usize) + Sync,
-    ) -> anyhow::Result<(usize, usize)> {
-        use rayon::prelude::*;
-        use std::fs;
+    /// 从另一个配置复制输出相关的选项（分割线保持不变）
+    pub fn copy_output_options_from(&mut self, other: &SplitConfig) {
+        self.keep_source_format = other.keep_source_format;
+        self.skip_existing = other.skip_existing;
+        self.embed_source_info = other.embed_source_info;
+        self.output_format = other.output_format;
+        self.jpeg_quality = other.jpeg_quality;
+        self.png_compression = other.png_compression;
+        self.png_filter = other.png_filter;
+        self.png_quantize = other.png_quantize;
+        self.png_max_colors = other.png_max_colors;
+        self.webp_lossless = other.webp_lossless;
+        self.line_mode = other.line_mode;
+        self.bleed_px = other.bleed_px;
+        self.gutter_px = other.gutter_px;
+        self.naming_scheme = other.naming_scheme;
+        self.tile_order = other.tile_order;
+        self.per_image_subfolder = other.per_image_subfolder;
+        self.filename_template = other.filename_template.clone();
+        self.multi_scale = other.multi_scale;
+        self.output_scales = other.output_scales.clone();
+        self.scale_suffix = other.scale_suffix.clone();
+        self.export_overview = other.export_overview;
+        self.overview_labels = other.overview_labels;
+        self.auto_detect_grid = other.auto_detect_grid;
+        self.detect_sensitivity = other.detect_sensitivity;
+        self.detect_background = other.detect_background;
+        self.uniform_canvas = other.uniform_canvas;
+        self.canvas_width = other.canvas_width;
+        self.canvas_height = other.canvas_height;
+    }
 
-        fs::create_dir_all(output_dir)?;
+    /// 生效的统一画布尺寸，未开启或尺寸为 0 时为 `None`
+    pub fn canvas_size(&self) -> Option<(u32, u32)> {
+        (self.uniform_canvas && self.canvas_width > 0 && self.canvas_height > 0)
+            .then_some((self.canvas_width, self.canvas_height))
+    }
 
-        let total = image_paths.len();
-        let processed = std::sync::atomic::AtomicUsize::new(0);
-        let failed = std::sync::atomic::AtomicUsize::new(0);
+    /// 实际导出的倍数：未开启多尺寸导出或列表无效时只导出 1 倍
+    pub fn effective_scales(&self) -> Vec<f32> {
+        let mut scales: Vec<f32> = self
+            .output_scales
+            .iter()
+            .copied()
+            .filter(|s| s.is_finite() && *s > 0.0)
+            .collect();
+        scales.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        scales.dedup();
+        if !self.multi_scale || scales.is_empty() {
+            return vec![1.0];
+        }
+        scales
+    }
 
-        image_paths.par_iter().enumerate().for_each(|(idx, path)| {
-            let config = overrides.get(&idx).unwrap_or(global_config);
-            let result = Self::process_single_image(path, config, output_dir);
+    /// 某个倍数对应的输出路径：1 倍保持原文件名，其余在扩展名前插入后缀
+    ///
+    /// 后缀模板不含 `{scale}` 时各倍数会重名，此时改用默认模板。
+    pub fn scaled_path(&self, path: &Path, scale: f32) -> PathBuf {
+        if scale == 1.0 {
+            return path.to_path_buf();
+        }
+        let template = if self.scale_suffix.contains("{scale}") {
+            self.scale_suffix.as_str()
+        } else {
+            DEFAULT_SCALE_SUFFIX
+        };
+        let suffix = template.replace("{scale}", &scale.to_string());
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match path.extension() {
+            Some(ext) => format!("{}{}.{}", stem, suffix, ext.to_string_lossy()),
+            None => format!("{}{}", stem, suffix),
+        };
+        path.with_file_name(name)
+    }
 
-            if result.is_ok() {
-                processed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            } else {
-                failed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                eprintln!("处理失败 {:?}: {:?}", path, result.err());
-            }
+    /// 对（已按 EXIF 校正的）图片应用整图变换：先旋转 `image_rotation`，再左右、上下翻转
+    pub fn transform_image(&self, mut img: DynamicImage) -> DynamicImage {
+        if let Some(rotation) = self.image_rotation {
+            img = rotation.apply(&img);
+        }
+        if self.image_flip_h {
+            img = img.fliph();
+        }
+        if self.image_flip_v {
+            img = img.flipv();
+        }
+        img
+    }
 
-            progress_callback(idx + 1, total);
-        });
+    /// 将变换后的图片再旋转 90°
+    ///
+    /// 翻转固定在旋转之后应用，而「先翻转再转 90°」等于「先转 90° 再沿另一轴翻转」，
+    /// 所以这里同时交换两个翻转标志，使结果与在当前画面上直接旋转一致。
+    pub fn rotate_image(&mut self, clockwise: bool) {
+        self.image_rotation = Rotation::turned(self.image_rotation, clockwise);
+        std::mem::swap(&mut self.image_flip_h, &mut self.image_flip_v);
+    }
 
-        Ok((processed.load(std::sync::atomic::Ordering::Relaxed),
-            failed.load(std::sync::atomic::Ordering::Relaxed)))
+    /// 是否设置了外边距
+    pub fn has_margins(&self) -> bool {
+        self.margin_top > 0 || self.margin_right > 0 || self.margin_bottom > 0 || self.margin_left > 0
     }
 
-    fn process_single_image(
-        path: &Path,
-        config: &SplitConfig,
-        output_dir: &Path,
-    ) -> anyhow::Result<()> {
-        let img = Self::open_image(path)?;
-        let parts = Self::split_image(&img, config)?;
+    /// 去掉外边距后的内容区域 `(x, y, 宽, 高)`
+    ///
+    /// 边距超出图片时依次压缩右、下边距，再压缩左、上边距，内容区域至少保留 1 像素；空图片返回全 0。
+    pub fn content_rect(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        if width == 0 || height == 0 {
+            return (0, 0, 0, 0);
+        }
+        let left = self.margin_left.min(width - 1);
+        let right = self.margin_right.min(width - 1 - left);
+        let top = self.margin_top.min(height - 1);
+        let bottom = self.margin_bottom.min(height - 1 - top);
+        (left, top, width - left - right, height - top - bottom)
+    }
+
+    /// 按分割线模式得到的分块行列数
+    pub fn grid_size(&self) -> (usize, usize) {
+        (self.line_mode.spans(&self.h_lines).len(), self.line_mode.spans(&self.v_lines).len())
+    }
+
+    /// 分割线是否与平均分割的位置一致（间隔带模式不算均匀网格）
+    pub fn is_uniform_grid(&self) -> bool {
+        self.line_mode != LineMode::Gutters && is_uniform_lines(&self.h_lines) && is_uniform_lines(&self.v_lines)
+    }
+
+    /// 实际使用的命名方式：网格不均匀或含合并分块时 z/x/y 和 Z 序命名回落为行列命名
+    pub fn effective_naming_scheme(&self) -> NamingScheme {
+        if matches!(self.naming_scheme, NamingScheme::RowCol | NamingScheme::Template) || (self.is_uniform_grid() && self.cell_merges.is_empty()) {
+            self.naming_scheme
+        } else {
+            NamingScheme::RowCol
+        }
+    }
+
+    /// 一张图片的输出目录：开启按图片分文件夹时为 `output_dir/{名称}`
+    ///
+    /// z/x/y 命名本身已按图片名称建立文件夹，不再额外嵌套。
+    pub fn image_output_dir(&self, output_dir: &Path, base_name: &str) -> PathBuf {
+        if self.per_image_subfolder && self.effective_naming_scheme() != NamingScheme::ZoomXY {
+            output_dir.join(base_name)
+        } else {
+            output_dir.to_path_buf()
+        }
+    }
 
-        let base_name = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("image");
+    /// 当前配置的编码参数
+    pub fn encode_options(&self) -> EncodeOptions {
+        EncodeOptions {
+            jpeg_quality: valid_jpeg_quality(self.jpeg_quality),
+            png_compression: self.png_compression,
+            png_filter: self.png_filter,
+            png_max_colors: self.png_quantize.then_some(self.png_max_colors),
+            webp_lossless: self.webp_lossless,
+        }
+    }
 
-        for (row_idx, row) in parts.iter().enumerate() {
-            for (col_idx, part) in row.iter().enumerate() {
-                let output_name = format!("{}_{}_{}.jpg", base_name, row_idx + 1, col_idx + 1);
-                let output_path = output_dir.join(output_name);
+    /// 检查自定义文件名模板：语法有效，且写死的扩展名与输出格式一致（保持源格式时不检查扩展名）
+    pub fn validate_template(&self) -> anyhow::Result<()> {
+        validate_file_template(&self.filename_template)?;
+        if self.keep_source_format {
+            return Ok(());
+        }
+        let placeholder = "\u{1}";
+        let rendered = render_file_template(&self.filename_template, |token| match token {
+            "ext" => placeholder.to_string(),
+            _ => String::from("1"),
+        })?;
+        let expected = self.output_format.extension();
+        match Path::new(&rendered).extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext != placeholder && !self.output_format.image_format().extensions_str().contains(&ext.to_ascii_lowercase().as_str()) => {
+                anyhow::bail!("扩展名 .{} 与输出格式 {} 不符，请使用 {{ext}} 或 .{}", ext, self.output_format.label(), expected)
+            }
+            _ => Ok(()),
+        }
+    }
 
-                part.save_with_format(&output_path, image::ImageFormat::Jpeg)?;
+    /// 根据源文件路径确定输出格式及扩展名
+    pub fn output_format_for(&self, source: &Path) -> (ImageFormat, &'static str) {
+        if self.keep_source_format {
+            if let Ok(format) = ImageFormat::from_path(source) {
+                if matches!(format, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::Bmp | ImageFormat::Gif | ImageFormat::WebP) {
+                    return (format, format.extensions_str()[0]);
+                }
             }
         }
+        (self.output_format.image_format(), self.output_format.extension())
+    }
 
+    /// 把配置（含分割线和输出选项）保存为 JSON 文件
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
         Ok(())
     }
+
+    /// 从 JSON 文件读取配置，分割线经 [`Self::normalize`] 规范化（限制范围、排序、去除重叠）并同步行列数
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let mut config: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        config.normalize();
+        Ok(config)
+    }
+
+    /// 验证配置是否有效
+    pub fn is_valid(&self) -> bool {
+        self.issues().is_empty()
+    }
+
+    /// 列出配置中存在的问题（为空表示有效）
+    pub fn issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        for (name, unit, lines, count) in [
+            ("水平", "行", &self.h_lines, self.rows),
+            ("垂直", "列", &self.v_lines, self.cols),
+        ] {
+            if lines.len() != count.saturating_sub(1) {
+                issues.push(format!("{}分割线数量 ({}) 与{}数 ({}) 不一致", name, lines.len(), unit, count));
+            }
+            if lines.iter().any(|p| !p.is_finite() || *p <= 0.0 || *p >= 1.0) {
+                issues.push(format!("存在超出范围的{}分割线", name));
+            }
+            if lines.windows(2).any(|w| w[0] > w[1]) {
+                issues.push(format!("{}分割线未排序", name));
+            } else if lines.windows(2).any(|w| w[1] - w[0] < LINE_EPSILON) {
+                issues.push(format!("存在重叠的{}分割线", name));
+            }
+        }
+        issues
+    }
+
+    /// 规范化分割线：限制到 (0, 1)、排序、去除过近的重复线，并同步行列数
+    ///
+    /// 返回配置是否被修改
+    pub fn normalize(&mut self) -> bool {
+        let before = self.clone();
+        Self::normalize_lines(&mut self.h_lines, &mut self.h_line_styles);
+        Self::normalize_lines(&mut self.v_lines, &mut self.v_line_styles);
+        self.rows = self.h_lines.len() + 1;
+        self.cols = self.v_lines.len() + 1;
+        self.rows != before.rows
+            || self.cols != before.cols
+            || self.h_lines != before.h_lines
+            || self.v_lines != before.v_lines
+            || self.h_line_styles != before.h_line_styles
+            || self.v_line_styles != before.v_line_styles
+    }
+
+    fn normalize_lines(lines: &mut Vec<f32>, styles: &mut Vec<LineStyle>) {
+        styles.resize(lines.len(), LineStyle::DEFAULT);
+        let mut pairs: Vec<(f32, LineStyle)> = lines
+            .drain(..)
+            .zip(styles.drain(..))
+            .filter(|(p, _)| p.is_finite())
+            .map(|(p, style)| (p.clamp(LINE_EPSILON, 1.0 - LINE_EPSILON), style))
+            .collect();
+        pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        pairs.dedup_by(|b, a| b.0 - a.0 < LINE_EPSILON);
+        (*lines, *styles) = pairs.into_iter().unzip();
+        Self::trim_default_styles(styles);
+    }
+}
+
+/// 从文本导入的分割线位置
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineImport {
+    /// 文件中出现水平线条目时为 `Some`
+    pub h_lines: Option<Vec<f32>>,
+    /// 文件中出现垂直线条目时为 `Some`
+    pub v_lines: Option<Vec<f32>>,
+    /// 无法解析或超出范围的行，形如「第 3 行: …」
+    pub errors: Vec<String>,
+}
+
+impl LineImport {
+    /// 解析分割线位置文本
+    ///
+    /// 每行一个条目：`h:0.33`、`v:0.5`（也可用 `,`、`=` 或空白分隔，如 CSV 的 `h,0.33`），
+    /// 只写数值的行视为水平线。位置须在 (0, 1) 之间，空行和 `#` 开头的注释行忽略。
+    pub fn parse(text: &str) -> Self {
+        let mut result = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (axis, value) = match line.split_once(|c: char| c == ':' || c == ',' || c == '=' || c.is_whitespace()) {
+                Some((axis, value)) => (
+                    axis.trim().to_ascii_lowercase(),
+                    value.trim().trim_start_matches([':', ',', '=']).trim(),
+                ),
+                None => ("h".to_string(), line),
+            };
+            let lines = match axis.as_str() {
+                "h" => &mut result.h_lines,
+                "v" => &mut result.v_lines,
+                _ => {
+                    result.errors.push(format!("第 {} 行: 未知方向 \"{}\"，应为 h 或 v", number + 1, axis));
+                    continue;
+                }
+            };
+            match value.parse::<f32>() {
+                Ok(pos) if pos > 0.0 && pos < 1.0 => lines.get_or_insert_with(Vec::new).push(pos),
+                Ok(pos) => result.errors.push(format!("第 {} 行: {} 不在 0 与 1 之间", number + 1, pos)),
+                Err(_) => result.errors.push(format!("第 {} 行: 无法解析 \"{}\"", number + 1, value)),
+            }
+        }
+        result
+    }
+
+    /// 写入配置：只替换文件中出现的方向，随后规范化
+    pub fn apply_to(&self, config: &mut SplitConfig) {
+        if let Some(h_lines) = &self.h_lines {
+            config.h_lines = h_lines.clone();
+            config.h_line_styles.clear();
+        }
+        if let Some(v_lines) = &self.v_lines {
+            config.v_lines = v_lines.clone();
+            config.v_line_styles.clear();
+        }
+        config.normalize();
+    }
+}
+
+/// 分块区域（像素坐标）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub row: usize,
+    pub col: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 批处理输出预估
+#[derive(Clone, Debug, Default)]
+pub struct BatchEstimate {
+    pub images: usize,
+    pub tiles: usize,
+    pub bytes: u64,
+    /// 无法读取尺寸的图片数量
+    pub unreadable: usize,
+    /// 网格不均匀、命名方式回落为行列命名的图片数量
+    pub naming_fallbacks: usize,
+    /// 尺寸过小、部分分割线会被合并的图片数量
+    pub collapsed: usize,
+    /// 宽或高小于 [`MIN_USEFUL_TILE_PX`] 的分块数量
+    pub tiny_tiles: usize,
+    /// 超出图片范围、不会导出的裁剪区域数量
+    pub empty_crops: usize,
+    /// 面积最小的分块 (宽, 高)
+    pub smallest_tile: Option<(u32, u32)>,
+}
+
+/// 批处理计划中单张图片的问题
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlanWarning {
+    /// 无法读取图片尺寸
+    Unreadable,
+    /// 图片过小，部分分割线被合并，分块少于网格
+    Collapsed,
+    /// 宽或高小于 [`MIN_USEFUL_TILE_PX`] 的分块数
+    TinyTiles(usize),
+    /// 超出图片范围、不会导出的裁剪区域数
+    EmptyCrops(usize),
+    /// 网格不均匀或含合并分块，命名方式回落为行列命名
+    NamingFallback,
+}
+
+/// 单张图片的输出计划
+#[derive(Clone, Debug, Default)]
+pub struct ImagePlan {
+    pub path: PathBuf,
+    /// 各分块及裁剪区域 1 倍尺寸下的 (宽, 高)
+    pub tile_sizes: Vec<(u32, u32)>,
+    /// 输出文件数（含多尺寸和总览图）
+    pub files: usize,
+    pub bytes: u64,
+    pub warnings: Vec<PlanWarning>,
+}
+
+/// 批处理计划：按批处理的配置逐张推算输出，不读取像素也不写入文件
+#[derive(Clone, Debug, Default)]
+pub struct BatchPlan {
+    pub images: Vec<ImagePlan>,
+}
+
+impl BatchPlan {
+    /// 汇总为批处理预估
+    pub fn summary(&self) -> BatchEstimate {
+        let mut estimate = BatchEstimate {
+            images: self.images.len(),
+            ..Default::default()
+        };
+        for image in &self.images {
+            estimate.tiles += image.files;
+            estimate.bytes += image.bytes;
+            for warning in &image.warnings {
+                match *warning {
+                    PlanWarning::Unreadable => estimate.unreadable += 1,
+                    PlanWarning::Collapsed => estimate.collapsed += 1,
+                    PlanWarning::TinyTiles(n) => estimate.tiny_tiles += n,
+                    PlanWarning::EmptyCrops(n) => estimate.empty_crops += n,
+                    PlanWarning::NamingFallback => estimate.naming_fallbacks += 1,
+                }
+            }
+        }
+        estimate.smallest_tile = self
+            .images
+            .iter()
+            .flat_map(|image| image.tile_sizes.iter().copied())
+            .min_by_key(|&(w, h)| w as u64 * h as u64);
+        estimate
+    }
+
+    /// 有问题的图片
+    pub fn warned_images(&self) -> impl Iterator<Item = &ImagePlan> {
+        self.images.iter().filter(|image| !image.warnings.is_empty())
+    }
+}
+
+/// 批处理的输出位置
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputLocation {
+    /// 全部输出到选定的文件夹
+    Chosen(PathBuf),
+    /// 输出到每张源图片旁的 `{名称}_split` 文件夹
+    AlongsideSource,
+}
+
+impl OutputLocation {
+    /// 输出文件名前缀，同名图片追加副本序号
+    pub fn base_name(&self, image_paths: &[PathBuf], idx: usize) -> String {
+        self.base_names(image_paths).swap_remove(idx)
+    }
+
+    /// 列表中每张图片的输出文件名前缀
+    ///
+    /// 输出到源图片旁时不同文件夹的图片互不影响，只区分同一文件夹中的同名图片（如 `a.png` 与 `a.jpg`）。
+    pub fn base_names(&self, image_paths: &[PathBuf]) -> Vec<String> {
+        match self {
+            Self::Chosen(_) => ImageSplitter::output_base_names(image_paths),
+            Self::AlongsideSource => ImageSplitter::unique_base_names(image_paths, |path| path.parent().map(Path::to_path_buf)),
+        }
+    }
+
+    /// 一张图片的输出目录；输出到源图片旁时本身已按图片分文件夹，不再按配置嵌套
+    pub fn image_dir(&self, config: &SplitConfig, source: &Path, base_name: &str) -> PathBuf {
+        match self {
+            Self::Chosen(dir) => config.image_output_dir(dir, base_name),
+            Self::AlongsideSource => source
+                .parent()
+                .unwrap_or(Path::new("."))
+                .join(format!("{}_split", base_name)),
+        }
+    }
+}
+
+/// 批处理结果统计
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchReport {
+    /// 成功处理的图片数（含因全部分块已存在而跳过的图片）
+    pub processed: usize,
+    pub failed: usize,
+    /// 处理失败的图片及错误信息（按列表顺序）
+    pub failures: Vec<(PathBuf, String)>,
+    /// 取消后未处理的图片数
+    pub cancelled: usize,
+    /// 无法按所选格式编码、改存为 PNG 的文件及原因（按列表顺序）
+    pub format_fallbacks: Vec<(PathBuf, String)>,
+    /// 所有分块均已存在、未解码直接跳过的图片数
+    pub skipped_images: usize,
+    /// 因已存在而跳过的分块数
+    pub skipped_tiles: usize,
+    /// 本次写入的分块数
+    pub written_tiles: usize,
+    /// 宽或高为 0、未导出的分块数
+    pub empty_tiles: usize,
+    /// 尺寸过小、部分分割线被合并的图片数（太小无法分割时整张导出）
+    pub collapsed_images: usize,
+    /// 自动网格批处理中按检测结果分割的图片（按列表顺序）
+    pub detected_images: Vec<PathBuf>,
+    /// 自动网格批处理中未检测到网格、改用全局配置的图片数
+    pub detection_fallbacks: usize,
+}
+
+impl BatchReport {
+    /// 是否为续跑（有已存在的分块被跳过）
+    pub fn resumed(&self) -> bool {
+        self.skipped_tiles > 0
+    }
+}
+
+/// 批处理进度事件
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// 开始处理，`total` 为图片总数
+    Started { total: usize },
+    /// 一张图片处理完毕（并行处理，顺序不定），失败时携带错误信息
+    FileDone { index: usize, path: PathBuf, result: Result<(), String> },
+    /// 全部处理完毕
+    Finished { processed: usize, failed: usize },
+    /// 批处理未能开始或中途出错（如输出目录无法创建），之后不再有其他事件
+    Failed(String),
+}
+
+/// 单张图片的写入统计 (写入, 跳过)
+#[derive(Clone, Debug, Default)]
+struct ImageOutcome {
+    written: usize,
+    skipped: usize,
+    /// 无法按所选格式编码、改存为 PNG 的文件及原因
+    fallbacks: Vec<(PathBuf, String)>,
+    /// 图片过小，部分分割线被合并
+    collapsed: bool,
+    /// 宽或高为 0、未导出的分块数
+    empty: usize,
+    /// 自动网格检测结果：`None` 为未检测，`Some(false)` 为未检测到、使用原配置
+    detected: Option<bool>,
+}
+
+/// 按倍数缩放后的尺寸，四舍五入且至少 1 像素
+fn scaled_size((width, height): (u32, u32), scale: f32) -> (u32, u32) {
+    if scale == 1.0 {
+        return (width, height);
+    }
+    (((width as f32 * scale).round() as u32).max(1), ((height as f32 * scale).round() as u32).max(1))
+}
+
+/// 是否为 WebP 放不下（宽或高超过 [`WEBP_MAX_DIMENSION`]）、需要改存为 PNG 的尺寸
+fn exceeds_webp(format: ImageFormat, width: u32, height: u32) -> bool {
+    format == ImageFormat::WebP && width.max(height) > WEBP_MAX_DIMENSION
+}
+
+/// 粗略估计每像素编码后的字节数
+fn estimated_bytes_per_pixel(format: ImageFormat, config: &SplitConfig) -> f64 {
+    let quality = valid_jpeg_quality(config.jpeg_quality) as f64 / 100.0;
+    match format {
+        ImageFormat::Jpeg => 0.1 + 0.5 * quality.powi(2),
+        ImageFormat::Png => 1.5,
+        ImageFormat::Bmp => 3.0,
+        ImageFormat::Gif => 0.6,
+        ImageFormat::WebP if config.webp_lossless => 1.2,
+        ImageFormat::WebP => 0.05 + 0.35 * quality.powi(2),
+        _ => 1.0,
+    }
+}
+
+/// 总览图标注使用的字体：内嵌的 Hack 等宽字体（只含拉丁字符，许可见 Hack-LICENSE.txt）
+fn overview_font() -> Option<&'static ab_glyph::FontRef<'static>> {
+    static FONT: std::sync::OnceLock<Option<ab_glyph::FontRef<'static>>> = std::sync::OnceLock::new();
+    FONT.get_or_init(|| ab_glyph::FontRef::try_from_slice(include_bytes!("../Hack-Regular.ttf")).ok())
+        .as_ref()
+}
+
+/// 在编码后的图片中插入文本注释，不支持的格式保持不变
+///
+/// JPEG 写入 COM 段（位于 SOI/APP0 之后），PNG 写入 `Comment` 文本块（位于 IHDR 之后，
+/// 非 ASCII 内容使用 iTXt 以保留 UTF-8）。
+fn embed_comment(bytes: &mut Vec<u8>, format: ImageFormat, comment: &str) {
+    match format {
+        ImageFormat::Jpeg => {
+            if bytes.len() < 4 || bytes[..2] != [0xFF, 0xD8] {
+                return;
+            }
+            let data = &comment.as_bytes()[..comment.len().min(u16::MAX as usize - 2)];
+            // JFIF 要求 APP0 紧跟 SOI，注释放在其后
+            let mut pos = 2;
+            if bytes.len() > pos + 4 && bytes[pos..pos + 2] == [0xFF, 0xE0] {
+                pos += 2 + u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+            }
+            let mut segment = vec![0xFF, 0xFE];
+            segment.extend_from_slice(&((data.len() + 2) as u16).to_be_bytes());
+            segment.extend_from_slice(data);
+            bytes.splice(pos..pos, segment);
+        }
+        ImageFormat::Png => {
+            // 8 字节签名 + IHDR (长度 4 + 类型 4 + 数据 13 + CRC 4)
+            const IHDR_END: usize = 8 + 25;
+            if bytes.len() < IHDR_END || &bytes[12..16] != b"IHDR" {
+                return;
+            }
+            let (chunk_type, data): (&[u8; 4], Vec<u8>) = if comment.is_ascii() {
+                (b"tEXt", [b"Comment\0".as_slice(), comment.as_bytes()].concat())
+            } else {
+                // 关键字\0 压缩标志 压缩方法 语言\0 翻译关键字\0 文本
+                (b"iTXt", [b"Comment\0\0\0\0\0".as_slice(), comment.as_bytes()].concat())
+            };
+            let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+            chunk.extend_from_slice(chunk_type);
+            chunk.extend_from_slice(&data);
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(chunk_type);
+            hasher.update(&data);
+            chunk.extend_from_slice(&hasher.finalize().to_be_bytes());
+            bytes.splice(IHDR_END..IHDR_END, chunk);
+        }
+        _ => {}
+    }
+}
+
+/// 打开图片的结果及附加信息
+#[derive(Clone, Debug)]
+pub struct LoadedImage {
+    pub image: DynamicImage,
+    /// 源文件为 CMYK JPEG，已转换为 RGB
+    pub cmyk_converted: bool,
+    /// EXIF 中记录的方向，`image` 保持原始像素未应用
+    pub orientation: Orientation,
+}
+
+/// EXIF 方向的中文说明
+pub fn orientation_label(orientation: Orientation) -> &'static str {
+    match orientation {
+        Orientation::NoTransforms => "正常",
+        Orientation::Rotate90 => "顺时针旋转 90°",
+        Orientation::Rotate180 => "旋转 180°",
+        Orientation::Rotate270 => "逆时针旋转 90°",
+        Orientation::FlipHorizontal => "水平翻转",
+        Orientation::FlipVertical => "垂直翻转",
+        Orientation::Rotate90FlipH => "顺时针旋转 90° 后水平翻转",
+        Orientation::Rotate270FlipH => "逆时针旋转 90° 后水平翻转",
+    }
+}
+
+/// 只解析文件头读取 EXIF 方向，读取失败视为正常方向
+fn read_orientation<R: std::io::BufRead + std::io::Seek>(reader: ImageReader<R>) -> Orientation {
+    reader
+        .into_decoder()
+        .and_then(|mut decoder| decoder.orientation())
+        .unwrap_or(Orientation::NoTransforms)
+}
+
+/// JPEG 头部中与 CMYK 相关的信息
+struct JpegColorInfo {
+    components: u8,
+    /// Adobe APP14 段中的 transform 字段（无该段时为 None）
+    adobe_transform: Option<u8>,
+}
+
+/// 扫描 JPEG 段头（直到 SOS），读取分量数与 Adobe APP14 标记
+fn read_jpeg_color_info(data: &[u8]) -> Option<JpegColorInfo> {
+    if data.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+    let mut pos = 2;
+    let mut components = None;
+    let mut adobe_transform = None;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = data.get(pos + 4..pos + 2 + length)?;
+        match marker {
+            // SOF0..SOF15（排除 DHT/JPG/DAC）
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                components = segment.get(5).copied();
+            }
+            0xEE if segment.starts_with(b"Adobe") => {
+                adobe_transform = segment.get(11).copied();
+            }
+            0xDA => break,
+            _ => {}
+        }
+        pos += 2 + length;
+    }
+    Some(JpegColorInfo {
+        components: components?,
+        adobe_transform,
+    })
+}
+
+/// 以原始 CMYK 通道解码 JPEG 并转换为 RGB
+///
+/// Adobe 软件写出的 CMYK JPEG（带 APP14 标记）按惯例存储的是反相值（255 表示无墨），
+/// 其他 CMYK JPEG 存储的是正常值，两者需要不同的换算，否则颜色会整体反相。
+fn decode_cmyk_jpeg(data: &[u8], adobe_inverted: bool) -> anyhow::Result<DynamicImage> {
+    use zune_core::bytestream::ZCursor;
+    use zune_core::colorspace::ColorSpace;
+    use zune_core::options::DecoderOptions;
+
+    // 尺寸不设上限，改为与 image 默认解码路径相同的内存上限（Limits::default），解码前按文件头尺寸检查
+    let options = DecoderOptions::default()
+        .jpeg_set_out_colorspace(ColorSpace::CMYK)
+        .set_strict_mode(false)
+        .set_max_width(usize::MAX)
+        .set_max_height(usize::MAX);
+    let mut decoder = zune_jpeg::JpegDecoder::new_with_options(ZCursor::new(data), options);
+    decoder.decode_headers().map_err(|e| anyhow::anyhow!("CMYK JPEG 解码失败: {:?}", e))?;
+    let (width, height) = decoder
+        .dimensions()
+        .ok_or_else(|| anyhow::anyhow!("无法读取 JPEG 尺寸"))?;
+    // CMYK 原始数据与转换后的 RGB 同时存在
+    let pixels = width as u64 * height as u64;
+    let mut limits = image::Limits::default();
+    limits.check_dimensions(width as u32, height as u32)?;
+    limits.reserve(pixels * 4 + pixels * 3)?;
+    let cmyk = decoder.decode().map_err(|e| anyhow::anyhow!("CMYK JPEG 解码失败: {:?}", e))?;
+
+    let rgb: Vec<u8> = cmyk
+        .chunks_exact(4)
+        .flat_map(|px| {
+            let [c, m, y, k] = [px[0], px[1], px[2], px[3]].map(|v| {
+                if adobe_inverted { v as u32 } else { 255 - v as u32 }
+            });
+            [c, m, y].map(|v| (v * k / 255) as u8)
+        })
+        .collect();
+
+    image::RgbImage::from_raw(width as u32, height as u32, rgb)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| anyhow::anyhow!("CMYK JPEG 数据长度不匹配"))
+}
+
+/// 按配置分割单张图片，分块直接写入 `out_dir`（不存在时创建）
+///
+/// 文件名与界面中「只处理当前图片」一致，以源文件名为前缀。
+pub fn split_to_dir(
+    path: impl AsRef<Path>,
+    config: &SplitConfig,
+    out_dir: impl Into<PathBuf>,
+) -> anyhow::Result<BatchReport> {
+    let paths = [path.as_ref().to_path_buf()];
+    ImageSplitter::process_image(&paths, 0, config, &OutputLocation::Chosen(out_dir.into()))
+}
+
+/// 图片分割器
+pub struct ImageSplitter;
+
+impl ImageSplitter {
+    /// 打开图片，并按 EXIF 方向旋转/翻转为正向
+    pub fn open_image<P: AsRef<Path>>(path: P) -> anyhow::Result<DynamicImage> {
+        let loaded = Self::open_image_detailed(path)?;
+        let mut image = loaded.image;
+        image.apply_orientation(loaded.orientation);
+        Ok(image)
+    }
+
+    /// 打开图片并返回附加信息（如 CMYK 转换）
+    pub fn open_image_detailed<P: AsRef<Path>>(path: P) -> anyhow::Result<LoadedImage> {
+        let path = path.as_ref();
+        if ImageFormat::from_path(path).ok() == Some(ImageFormat::Jpeg) {
+            let data = std::fs::read(path)?;
+            if let Some(info) = read_jpeg_color_info(&data) {
+                // transform = 2 为 YCCK，由默认解码器处理
+                if info.components == 4 && info.adobe_transform != Some(2) {
+                    let image = decode_cmyk_jpeg(&data, info.adobe_transform.is_some())?;
+                    let orientation = read_orientation(
+                        ImageReader::with_format(std::io::Cursor::new(&data), ImageFormat::Jpeg),
+                    );
+                    return Ok(LoadedImage { image, cmyk_converted: true, orientation });
+                }
+            }
+            let reader = ImageReader::new(std::io::Cursor::new(data)).with_guessed_format()?;
+            return Self::decode_with_orientation(reader);
+        }
+        Self::decode_with_orientation(ImageReader::open(path)?)
+    }
+
+    /// 解码图片并顺带取出 EXIF 方向（不应用）
+    fn decode_with_orientation<R: std::io::BufRead + std::io::Seek>(
+        reader: ImageReader<R>,
+    ) -> anyhow::Result<LoadedImage> {
+        let mut decoder = reader.into_decoder()?;
+        let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+        let image = DynamicImage::from_decoder(decoder)?;
+        Ok(LoadedImage { image, cmyk_converted: false, orientation })
+    }
+
+    /// 只读文件头得到分割时的图片尺寸，按 EXIF 或 `image_rotation` 旋转 90° 时宽高互换
+    pub fn oriented_dimensions(path: &Path, config: &SplitConfig) -> Option<(u32, u32)> {
+        let (width, height) = image::image_dimensions(path).ok()?;
+        let exif_swapped = config.apply_exif_orientation
+            && matches!(
+                ImageReader::open(path).map(read_orientation),
+                Ok(Orientation::Rotate90
+                    | Orientation::Rotate270
+                    | Orientation::Rotate90FlipH
+                    | Orientation::Rotate270FlipH)
+            );
+        let swapped = exif_swapped != config.image_rotation.is_some_and(Rotation::swaps_dimensions);
+        Some(if swapped { (height, width) } else { (width, height) })
+    }
+
+    /// 按配置打开用于分割的图片：开启 `apply_exif_orientation` 时先按 EXIF 方向校正，
+    /// 再按 [`SplitConfig::transform_image`] 旋转、翻转
+    pub fn open_image_for<P: AsRef<Path>>(path: P, config: &SplitConfig) -> anyhow::Result<DynamicImage> {
+        let loaded = Self::open_image_detailed(path)?;
+        let mut image = loaded.image;
+        if config.apply_exif_orientation {
+            image.apply_orientation(loaded.orientation);
+        }
+        Ok(config.transform_image(image))
+    }
+
+    /// 按背景色间隔带检测规则网格，返回 `(水平线, 垂直线)`，两个方向都没有间隔带时返回 `None`
+    ///
+    /// 几乎全部像素都接近背景色的整行（整列）视为间隔带，接触图片边缘的外边距不算。
+    /// `Gutters` 模式下每条间隔带产生一对线（丢弃间隔带），其余模式在间隔带中间放一条线。
+    /// 设置了外边距时只检测内容区域，分割线同样相对于内容区域。
+    pub fn detect_grid(img: &DynamicImage, config: &SplitConfig) -> Option<(Vec<f32>, Vec<f32>)> {
+        let content;
+        let img = if config.has_margins() {
+            let (x, y, w, h) = config.content_rect(img.width(), img.height());
+            content = img.crop_imm(x, y, w, h);
+            &content
+        } else {
+            img
+        };
+        let (width, height) = (img.width(), img.height());
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let sensitivity = config.detect_sensitivity.min(100) as u32;
+        // 灵敏度 0 → 每通道容差 4、杂点 0%；100 → 容差 68、杂点 5%
+        let tolerance = (4 + sensitivity * 64 / 100) as i32;
+        let max_noise = sensitivity as f32 / 2000.0;
+        let [br, bg, bb] = config.detect_background.map(i32::from);
+
+        let rgb = img.to_rgb8();
+        let mut row_hits = vec![0u32; height as usize];
+        let mut col_hits = vec![0u32; width as usize];
+        for (x, y, pixel) in rgb.enumerate_pixels() {
+            let [r, g, b] = pixel.0.map(i32::from);
+            if (r - br).abs() <= tolerance && (g - bg).abs() <= tolerance && (b - bb).abs() <= tolerance {
+                row_hits[y as usize] += 1;
+                col_hits[x as usize] += 1;
+            }
+        }
+
+        let gutters = |hits: &[u32], across: u32| -> Vec<(u32, u32)> {
+            let needed = (across as f32 * (1.0 - max_noise)).ceil() as u32;
+            let size = hits.len() as u32;
+            // 过窄的空白行多半是内容本身（如文字行距），不算间隔带
+            let min_width = (size / 200).max(2);
+            let mut runs = Vec::new();
+            let mut start = None;
+            for (i, &count) in hits.iter().chain(std::iter::once(&0)).enumerate() {
+                let i = i as u32;
+                match (count >= needed, start) {
+                    (true, None) => start = Some(i),
+                    (false, Some(s)) => {
+                        if s > 0 && i < size && i - s >= min_width {
+                            runs.push((s, i));
+                        }
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+            runs
+        };
+        // 线放在像素中点，换算回像素时截断不会因浮点误差差一
+        let to_lines = |runs: Vec<(u32, u32)>, size: u32| -> Vec<f32> {
+            let size = size as f32;
+            let at = |px: u32| (px as f32 + 0.5) / size;
+            match config.line_mode {
+                LineMode::Gutters => runs.into_iter().flat_map(|(start, end)| [at(start), at(end)]).collect(),
+                LineMode::SharedEdge | LineMode::TrimWithBleed | LineMode::Lossless => runs
+                    .into_iter()
+                    .map(|(start, end)| at((start + end) / 2))
+                    .collect(),
+            }
+        };
+
+        let h_lines = to_lines(gutters(&row_hits, width), height);
+        let v_lines = to_lines(gutters(&col_hits, height), width);
+        if h_lines.is_empty() && v_lines.is_empty() {
+            return None;
+        }
+        Some((h_lines, v_lines))
+    }
+
+    /// 用检测到的分割线替换配置中的网格，与旧网格对应的线样式和分块旋转一并清除
+    fn with_detected_lines(config: &SplitConfig, (h_lines, v_lines): (Vec<f32>, Vec<f32>)) -> SplitConfig {
+        let mut detected = config.clone();
+        detected.rows = h_lines.len() + 1;
+        detected.cols = v_lines.len() + 1;
+        detected.h_lines = h_lines;
+        detected.v_lines = v_lines;
+        detected.h_line_styles.clear();
+        detected.v_line_styles.clear();
+        detected.cell_rotations.clear();
+        detected
+    }
+
+    /// 为目标分块数选择最贴合图片宽高比的行列数（行列各不超过 `max_per_axis`）
+    ///
+    /// 优先让每个分块接近正方形，同时让分块总数接近目标值；返回 (行数, 列数)。
+    pub fn best_grid(target: usize, width: u32, height: u32, max_per_axis: usize) -> (usize, usize) {
+        let target = target.max(1);
+        let max_per_axis = max_per_axis.max(1);
+        if width == 0 || height == 0 {
+            return (1, 1);
+        }
+        let mut best = (1, 1);
+        let mut best_score = f32::INFINITY;
+        for rows in 1..=max_per_axis {
+            let cols = ((target as f32 / rows as f32).round() as usize).clamp(1, max_per_axis);
+            // 分块宽高比偏离 1 的程度（对数尺度，横竖对称）
+            let cell_aspect = (width as f32 / cols as f32) / (height as f32 / rows as f32);
+            let distortion = cell_aspect.ln().abs();
+            let count_error = (rows * cols).abs_diff(target) as f32 / target as f32;
+            let score = distortion + count_error * 2.0;
+            if score < best_score {
+                best_score = score;
+                best = (rows, cols);
+            }
+        }
+        best
+    }
+
+    /// 计算各分块的像素区域（按行优先顺序）
+    ///
+    /// 先去掉外边距，分割线按内容区域换算；返回的坐标仍相对于整张图片。
+    pub fn compute_regions(width: u32, height: u32, config: &SplitConfig) -> Vec<Region> {
+        Self::compute_regions_counted(width, height, config).0
+    }
+
+    /// 同 [`Self::compute_regions`]，另外返回因宽或高为 0 而丢弃的分块数
+    pub fn compute_regions_counted(width: u32, height: u32, config: &SplitConfig) -> (Vec<Region>, usize) {
+        if width == 0 || height == 0 {
+            return (Vec::new(), 0);
+        }
+        let (offset_x, offset_y, width, height) = config.content_rect(width, height);
+
+        let h_spans = Self::pixel_spans(&config.h_lines, height, config.line_mode);
+        let v_spans = Self::pixel_spans(&config.v_lines, width, config.line_mode);
+
+        let mut regions = Vec::with_capacity(h_spans.len() * v_spans.len());
+        let bleed = match config.line_mode {
+            LineMode::SharedEdge | LineMode::Gutters | LineMode::Lossless => 0,
+            LineMode::TrimWithBleed => config.bleed_px,
+        };
+
+        for (row, &(top, bottom)) in h_spans.iter().enumerate() {
+            let (upper, lower) = Self::expand_by_bleed(top, bottom, height, bleed);
+            let (upper, lower) = Self::shrink_by_gutter(upper, lower, height, config.gutter_px);
+
+            for (col, &(start, end)) in v_spans.iter().enumerate() {
+                let (left, right) = Self::expand_by_bleed(start, end, width, bleed);
+                let (left, right) = Self::shrink_by_gutter(left, right, width, config.gutter_px);
+
+                regions.push(Region {
+                    row,
+                    col,
+                    x: offset_x + left,
+                    y: offset_y + upper,
+                    width: right - left,
+                    height: lower - upper,
+                });
+            }
+        }
+
+        // 图片过小、部分分割线被合并时行列号已错位，不再应用分块合并
+        if (h_spans.len(), v_spans.len()) == config.grid_size() {
+            regions = Self::apply_merges(regions, v_spans.len(), &config.cell_merges);
+        }
+
+        // 换算像素边界时已合并重合的分割线，这里兜底丢弃空分块，避免裁出空图片后保存失败
+        let before = regions.len();
+        regions.retain(|r| r.width > 0 && r.height > 0);
+        let dropped = before - regions.len();
+        (regions, dropped)
+    }
+
+    /// 把合并区域内的分块并为一个以锚点分块行列号命名的区域
+    ///
+    /// 超出网格或与前面的合并重叠的条目被忽略（例如删除分割线后残留的合并）。
+    fn apply_merges(mut regions: Vec<Region>, cols: usize, merges: &[CellMerge]) -> Vec<Region> {
+        let mut owner: Vec<Option<&CellMerge>> = vec![None; regions.len()];
+        let mut accepted = Vec::new();
+        let rows = regions.len() / cols.max(1);
+        for merge in merges {
+            if merge.row + merge.rows > rows || merge.col + merge.cols > cols {
+                continue;
+            }
+            if merge.cells().any(|(row, col)| owner[row * cols + col].is_some()) {
+                continue;
+            }
+            for (row, col) in merge.cells() {
+                owner[row * cols + col] = Some(merge);
+            }
+            accepted.push(merge);
+        }
+
+        for merge in accepted {
+            let anchor = merge.row * cols + merge.col;
+            let last = &regions[(merge.row + merge.rows - 1) * cols + merge.col + merge.cols - 1];
+            let (right, bottom) = (last.x + last.width, last.y + last.height);
+            let region = &mut regions[anchor];
+            region.width = right - region.x;
+            region.height = bottom - region.y;
+        }
+        regions
+            .into_iter()
+            .zip(owner)
+            .filter(|(region, merge)| merge.is_none_or(|m| (m.row, m.col) == (region.row, region.col)))
+            .map(|(region, _)| region)
+            .collect()
+    }
+
+    /// 越过内部裁切线向外扩展出血，图片边缘不扩展
+    fn expand_by_bleed(start: u32, end: u32, size: u32, bleed: u32) -> (u32, u32) {
+        let start = if start > 0 { start.saturating_sub(bleed) } else { start };
+        let end = if end < size { end.saturating_add(bleed).min(size) } else { end };
+        (start, end)
+    }
+
+    /// 在内部边上向内收缩，图片边缘不收缩；收缩量按两侧比例压缩，分块至少保留 1 像素
+    fn shrink_by_gutter(start: u32, end: u32, size: u32, gutter: u32) -> (u32, u32) {
+        let front = if start > 0 { gutter } else { 0 };
+        let back = if end < size { gutter } else { 0 };
+        let total = front.saturating_add(back);
+        if total == 0 || end <= start {
+            return (start, end);
+        }
+        let avail = (end - start - 1).min(total);
+        let front = (avail as u64 * front as u64 / total as u64) as u32;
+        (start + front, end - (avail - front))
+    }
+
+    /// 分割线换算为像素边界（含首尾）
+    ///
+    /// 图片过小时，无法产生至少 1 像素分块的线被合并掉，因此返回的分块数可能少于配置的数量；
+    /// 所有线都被合并时整张图片作为一个分块。
+    fn pixel_boundaries(lines: &[f32], size: u32) -> Vec<u32> {
+        let mut positions = vec![0];
+        for &p in lines {
+            let pos = (size as f32 * p) as u32;
+            if pos > *positions.last().unwrap() && pos < size {
+                positions.push(pos);
+            }
+        }
+        positions.push(size);
+        positions
+    }
+
+    /// 一个方向上各分块的像素区间（不含出血）
+    ///
+    /// 间隔带模式下丢弃成对分割线之间的像素；其余模式相邻区间首尾相接，过小的分块按
+    /// [`Self::pixel_boundaries`] 合并。
+    fn pixel_spans(lines: &[f32], size: u32, mode: LineMode) -> Vec<(u32, u32)> {
+        match mode {
+            LineMode::Gutters => {
+                // 使用截断方式与 Python 版本保持一致
+                let cuts: Vec<u32> = lines.iter().map(|&p| ((size as f32 * p) as u32).min(size)).collect();
+                content_spans(&cuts, 0, size)
+            }
+            LineMode::Lossless => Self::lossless_boundaries(lines, size).windows(2).map(|w| (w[0], w[1])).collect(),
+            LineMode::SharedEdge | LineMode::TrimWithBleed => {
+                Self::pixel_boundaries(lines, size).windows(2).map(|w| (w[0], w[1])).collect()
+            }
+        }
+    }
+
+    /// 无损拼回模式的像素边界（含首尾）
+    ///
+    /// 均匀网格每块取 `size / n`，余数像素各加 1 给前面的分块；否则线位置四舍五入。
+    /// 与 [`Self::pixel_boundaries`] 一样合并宽度为 0 的分块，相邻分块首尾相接。
+    fn lossless_boundaries(lines: &[f32], size: u32) -> Vec<u32> {
+        if is_uniform_lines(lines) {
+            let n = lines.len() as u32 + 1;
+            let (base, remainder) = (size / n, size % n);
+            let mut positions = vec![0];
+            for i in 0..n {
+                let next = positions.last().unwrap() + base + u32::from(i < remainder);
+                if next > *positions.last().unwrap() {
+                    positions.push(next);
+                }
+            }
+            return positions;
+        }
+        let mut positions = vec![0];
+        for &p in lines {
+            let pos = (size as f32 * p).round() as u32;
+            if pos > *positions.last().unwrap() && pos < size {
+                positions.push(pos);
+            }
+        }
+        positions.push(size);
+        positions
+    }
+
+    /// 图片尺寸是否足以容纳配置的全部分块
+    pub fn fits_grid(width: u32, height: u32, config: &SplitConfig) -> bool {
+        let (_, _, width, height) = config.content_rect(width, height);
+        // 按行列区间计数，合并分块减少的区域数不算作分割线被合并
+        let rows = Self::pixel_spans(&config.h_lines, height, config.line_mode).len();
+        let cols = Self::pixel_spans(&config.v_lines, width, config.line_mode).len();
+        width > 0 && height > 0 && (rows, cols) == config.grid_size()
+    }
+
+    /// 分割图片
+    pub fn split_image(
+        img: &DynamicImage,
+        config: &SplitConfig,
+    ) -> anyhow::Result<Vec<Vec<DynamicImage>>> {
+        let mut result: Vec<Vec<DynamicImage>> = Vec::new();
+        let mut current_row = None;
+
+        // 按行号分组：合并分块时某些行不以第 0 列开头
+        for (region, cropped) in Self::iter_tiles(img, config) {
+            if current_row != Some(region.row) {
+                current_row = Some(region.row);
+                result.push(Vec::new());
+            }
+            if let Some(row_images) = result.last_mut() {
+                row_images.push(cropped);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// 逐个裁剪分块的迭代器，每次 `next()` 时才裁剪，适合大图逐块处理
+    pub fn iter_tiles<'a>(
+        img: &'a DynamicImage,
+        config: &'a SplitConfig,
+    ) -> impl Iterator<Item = (Region, DynamicImage)> + 'a {
+        Self::compute_regions(img.width(), img.height(), config)
+            .into_iter()
+            .map(move |region| {
+                // 使用 crop_imm 代替 crop（不需要可变引用）
+                let cropped = img.crop_imm(region.x, region.y, region.width, region.height);
+                (region, cropped)
+            })
+    }
+
+    /// 渲染标注总览图：在整图上画出分割线（红）和自由裁剪区域（蓝），压暗间隔带和外边距，可选在分块左上角标注行列号
+    ///
+    /// 分割线按原图分辨率换算，线宽随图片尺寸缩放；源图不含透明通道时输出 RGB。
+    pub fn render_overview(img: &DynamicImage, config: &SplitConfig) -> DynamicImage {
+        use imageproc::drawing::{draw_filled_rect_mut, draw_hollow_rect_mut, draw_text_mut, text_size};
+        use imageproc::rect::Rect;
+
+        const LINE_COLOR: image::Rgba<u8> = image::Rgba([230, 40, 40, 255]);
+        const CROP_COLOR: image::Rgba<u8> = image::Rgba([40, 110, 230, 255]);
+        const LABEL_BG: image::Rgba<u8> = image::Rgba([30, 30, 30, 255]);
+        const LABEL_FG: image::Rgba<u8> = image::Rgba([255, 255, 255, 255]);
+
+        let (width, height) = (img.width(), img.height());
+        let mut canvas = img.to_rgba8();
+        if width == 0 || height == 0 {
+            return img.clone();
+        }
+        let thickness = (width.min(height) / 400).max(2);
+        let to_px = |p: f32, size: u32| ((size as f32 * p) as u32).min(size);
+        // 分割线相对于去掉外边距后的内容区域
+        let (ox, oy, cw, ch) = config.content_rect(width, height);
+
+        // 间隔带压暗，表示这部分不会导出
+        let shade = |pixel: &mut image::Rgba<u8>| {
+            for c in &mut pixel.0[..3] {
+                *c = (*c as u16 * 2 / 5 + 60) as u8;
+            }
+        };
+        for (x, y, pixel) in canvas.enumerate_pixels_mut() {
+            if x < ox || x >= ox + cw || y < oy || y >= oy + ch {
+                shade(pixel);
+            }
+        }
+        for (start, end) in config.line_mode.gutters(&config.h_lines) {
+            for y in to_px(start, ch)..to_px(end, ch) {
+                (ox..ox + cw).for_each(|x| shade(canvas.get_pixel_mut(x, oy + y)));
+            }
+        }
+        for (start, end) in config.line_mode.gutters(&config.v_lines) {
+            for x in to_px(start, cw)..to_px(end, cw) {
+                (oy..oy + ch).for_each(|y| shade(canvas.get_pixel_mut(ox + x, y)));
+            }
+        }
+
+        // 只画内部分割线，线条以边界为中心
+        let centered = |pos: u32, size: u32| pos.saturating_sub(thickness / 2).min(size - thickness.min(size));
+        for y in config.h_lines.iter().map(|&p| to_px(p, ch)).filter(|&y| y > 0 && y < ch) {
+            let top = oy + centered(y, ch);
+            draw_filled_rect_mut(&mut canvas, Rect::at(ox as i32, top as i32).of_size(cw, thickness.min(ch)), LINE_COLOR);
+        }
+        for x in config.v_lines.iter().map(|&p| to_px(p, cw)).filter(|&x| x > 0 && x < cw) {
+            let left = ox + centered(x, cw);
+            draw_filled_rect_mut(&mut canvas, Rect::at(left as i32, oy as i32).of_size(thickness.min(cw), ch), LINE_COLOR);
+        }
+
+        for crop in &config.crop_regions {
+            let Some((x, y, w, h)) = crop.to_pixels(width, height) else {
+                continue;
+            };
+            for i in 0..thickness.min(w / 2).min(h / 2) {
+                let rect = Rect::at((x + i) as i32, (y + i) as i32).of_size(w - 2 * i, h - 2 * i);
+                draw_hollow_rect_mut(&mut canvas, rect, CROP_COLOR);
+            }
+        }
+
+        if config.overview_labels {
+            if let Some(font) = overview_font() {
+                let scale = (width.min(height) as f32 / 30.0).clamp(12.0, 96.0);
+                let pad = (scale / 4.0) as u32;
+                let h_spans = Self::pixel_spans(&config.h_lines, ch, config.line_mode);
+                let v_spans = Self::pixel_spans(&config.v_lines, cw, config.line_mode);
+                for (row, &(top, bottom)) in h_spans.iter().enumerate() {
+                    for (col, &(left, right)) in v_spans.iter().enumerate() {
+                        let label = format!("{},{}", row + 1, col + 1);
+                        let (text_w, text_h) = text_size(scale, font, &label);
+                        let (box_w, box_h) = (text_w + pad * 2, text_h + pad * 2);
+                        // 分块放不下标签时不标注，避免遮挡相邻分块
+                        if box_w + thickness > right - left || box_h + thickness > bottom - top {
+                            continue;
+                        }
+                        let x = ox + left + if left > 0 { thickness } else { 0 };
+                        let y = oy + top + if top > 0 { thickness } else { 0 };
+                        draw_filled_rect_mut(&mut canvas, Rect::at(x as i32, y as i32).of_size(box_w, box_h), LABEL_BG);
+                        draw_text_mut(&mut canvas, LABEL_FG, (x + pad) as i32, (y + pad) as i32, scale, font, &label);
+                    }
+                }
+            }
+        }
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(canvas)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(canvas).to_rgb8())
+        }
+    }
+
+    fn overview_file_name(base_name: &str, ext: &str) -> String {
+        format!("{}_grid.{}", base_name, ext)
+    }
+
+    /// 批处理中第 `index` 张图片使用的配置：有独立配置时用独立配置，否则用全局配置
+    fn batch_config<'a>(
+        index: usize,
+        global_config: &'a SplitConfig,
+        overrides: &'a std::collections::HashMap<usize, SplitConfig>,
+    ) -> &'a SplitConfig {
+        overrides.get(&index).unwrap_or(global_config)
+    }
+
+    /// 推算批处理的输出而不写入文件（只读取图片尺寸，不解码像素）
+    ///
+    /// 与 [`Self::batch_process`] 使用相同的配置，逐张给出分块数、各分块尺寸和问题提示。
+    /// 自动网格批处理时按全局分割线推算，实际以检测结果为准。
+    pub fn plan_batch(
+        image_paths: &[PathBuf],
+        global_config: &SplitConfig,
+        overrides: &std::collections::HashMap<usize, SplitConfig>,
+    ) -> BatchPlan {
+        let images = image_paths
+            .iter()
+            .enumerate()
+            .map(|(idx, path)| Self::plan_image(path, Self::batch_config(idx, global_config, overrides)))
+            .collect();
+        BatchPlan { images }
+    }
+
+    /// 单张图片的输出计划
+    fn plan_image(path: &Path, config: &SplitConfig) -> ImagePlan {
+        let mut plan = ImagePlan {
+            path: path.to_path_buf(),
+            ..Default::default()
+        };
+        if config.effective_naming_scheme() != config.naming_scheme {
+            plan.warnings.push(PlanWarning::NamingFallback);
+        }
+        let Some((width, height)) = Self::oriented_dimensions(path, config) else {
+            plan.warnings.push(PlanWarning::Unreadable);
+            return plan;
+        };
+        if !Self::fits_grid(width, height, config) {
+            plan.warnings.push(PlanWarning::Collapsed);
+        }
+
+        let (format, _) = config.output_format_for(path);
+        let scales = config.effective_scales();
+        // 各尺寸面积之和相对 1 倍的比例
+        let area_factor: f64 = scales.iter().map(|&s| (s as f64).powi(2)).sum();
+        let bytes_per_pixel = estimated_bytes_per_pixel(format, config) * area_factor;
+        // 统一画布时每个分块都是画布大小
+        let tile_area = |w: u32, h: u32| match config.canvas_size() {
+            Some((cw, ch)) => cw as f64 * ch as f64,
+            None => w as f64 * h as f64,
+        };
+
+        let regions = Self::compute_regions(width, height, config);
+        let crops: Vec<_> = config.crop_regions.iter().filter_map(|c| c.to_pixels(width, height)).collect();
+        plan.tile_sizes = regions
+            .iter()
+            .map(|r| (r.width, r.height))
+            .chain(crops.iter().map(|&(_, _, w, h)| (w, h)))
+            .collect();
+        for &(w, h) in &plan.tile_sizes {
+            plan.files += scales.len();
+            plan.bytes += (tile_area(w, h) * bytes_per_pixel) as u64;
+        }
+        if config.export_overview {
+            plan.files += 1;
+            plan.bytes += (width as f64 * height as f64 * estimated_bytes_per_pixel(format, config)) as u64;
+        }
+
+        let tiny = plan.tile_sizes.iter().filter(|&&(w, h)| w.min(h) < MIN_USEFUL_TILE_PX).count();
+        if tiny > 0 {
+            plan.warnings.push(PlanWarning::TinyTiles(tiny));
+        }
+        let empty_crops = config.crop_regions.len() - crops.len();
+        if empty_crops > 0 {
+            plan.warnings.push(PlanWarning::EmptyCrops(empty_crops));
+        }
+        plan
+    }
+
+    /// 分割内存中的图片并编码为字节，不写入磁盘
+    ///
+    /// 返回 `(名称后缀, 编码数据)` 列表，名称后缀形如 `_1_2`（行、列从 1 开始，与行列命名一样补零）。
+    /// `options` 中只有与 `format` 对应的参数生效。
+    pub fn split_to_encoded(
+        img: &DynamicImage,
+        config: &SplitConfig,
+        format: ImageFormat,
+        options: EncodeOptions,
+    ) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+        let mut result = Vec::new();
+        let (rows, cols) = config.grid_size();
+
+        for (region, part) in Self::iter_tiles(img, config) {
+            let part = Self::apply_cell_rotation(part, config, &region);
+            let mut bytes = Vec::new();
+            Self::encode_tile(&part, &mut std::io::Cursor::new(&mut bytes), format, options)?;
+            result.push((format!("_{}_{}", padded_index(region.row + 1, rows), padded_index(region.col + 1, cols)), bytes));
+        }
+
+        Ok(result)
+    }
+
+    /// 批量处理图片
+    ///
+    /// `progress_callback(已完成数, 总数)` 在调用线程上执行，是 [`Self::run_batch`] 事件的简单封装。
+    /// `cancel` 置位后不再开始处理新的图片，正在处理的图片会完成。
+    pub fn batch_process(
+        image_paths: &[PathBuf],
+        global_config: &SplitConfig,
+        overrides: &std::collections::HashMap<usize, SplitConfig>,
+        location: &OutputLocation,
+        cancel: &std::sync::atomic::AtomicBool,
+        mut progress_callback: impl FnMut(usize, usize),
+    ) -> anyhow::Result<BatchReport> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::scope(|scope| {
+            let worker = scope.spawn(move || {
+                Self::run_batch(image_paths, global_config, overrides, location, cancel, &sender)
+            });
+            let mut done = 0;
+            for event in receiver {
+                if let ProgressEvent::FileDone { .. } = event {
+                    done += 1;
+                    progress_callback(done, image_paths.len());
+                }
+            }
+            worker.join().unwrap_or_else(|panic| {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Err(anyhow::anyhow!("批处理线程异常退出: {}", message))
+            })
+        })
+    }
+
+    /// 在后台线程中批量处理图片，通过通道返回进度事件
+    ///
+    /// 事件依次为 `Started`、每张图片一个 `FileDone`（顺序不定）、`Finished`。
+    /// 出错时（如输出目录无法创建）以 `Failed` 代替 `Finished` 结束。取消后跳过的图片没有 `FileDone` 事件。
+    pub fn batch_process_events(
+        image_paths: Vec<PathBuf>,
+        global_config: SplitConfig,
+        overrides: std::collections::HashMap<usize, SplitConfig>,
+        location: OutputLocation,
+        cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    ) -> std::sync::mpsc::Receiver<ProgressEvent> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            if let Err(e) = Self::run_batch(&image_paths, &global_config, &overrides, &location, &cancel, &sender) {
+                let _ = sender.send(ProgressEvent::Failed(e.to_string()));
+            }
+        });
+        receiver
+    }
+
+    /// 批处理主体，进度以事件形式发送（接收端已关闭时忽略发送失败）
+    ///
+    /// `cancel` 置位后剩余的图片直接跳过，计入 [`BatchReport::cancelled`]。
+    pub fn run_batch(
+        image_paths: &[PathBuf],
+        global_config: &SplitConfig,
+        overrides: &std::collections::HashMap<usize, SplitConfig>,
+        location: &OutputLocation,
+        cancel: &std::sync::atomic::AtomicBool,
+        events: &std::sync::mpsc::Sender<ProgressEvent>,
+    ) -> anyhow::Result<BatchReport> {
+        use rayon::prelude::*;
+        use std::fs;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // 输出到源图片旁时各图片的文件夹在处理时创建
+        if let OutputLocation::Chosen(output_dir) = location {
+            fs::create_dir_all(output_dir)?;
+        }
+
+        let _ = events.send(ProgressEvent::Started { total: image_paths.len() });
+        let processed = AtomicUsize::new(0);
+        let failed = AtomicUsize::new(0);
+        let skipped_images = AtomicUsize::new(0);
+        let skipped_tiles = AtomicUsize::new(0);
+        let written_tiles = AtomicUsize::new(0);
+        let empty_tiles = AtomicUsize::new(0);
+        let collapsed_images = AtomicUsize::new(0);
+        let detected_images = std::sync::Mutex::new(Vec::new());
+        let detection_fallbacks = AtomicUsize::new(0);
+        let failures = std::sync::Mutex::new(Vec::new());
+        let cancelled = AtomicUsize::new(0);
+        let format_fallbacks = std::sync::Mutex::new(Vec::new());
+
+        let base_names = location.base_names(image_paths);
+        image_paths.par_iter().enumerate().for_each(|(idx, path)| {
+            if cancel.load(Ordering::Relaxed) {
+                cancelled.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            let config = Self::batch_config(idx, global_config, overrides);
+            let base_name = &base_names[idx];
+            // 自动网格只用于没有独立配置的图片，独立配置是用户专门调整过的
+            let detect = global_config.auto_detect_grid && !overrides.contains_key(&idx);
+
+            // 续跑时先按图片头部尺寸推算输出文件，全部存在则无需解码（自动网格需解码后才知道输出）
+            let existing = (config.skip_existing && !detect)
+                .then(|| Self::planned_outputs(path, base_name, config, location))
+                .flatten()
+                .filter(|outputs| outputs.iter().all(|p| p.exists()));
+            let result = if let Some(outputs) = existing {
+                skipped_images.fetch_add(1, Ordering::Relaxed);
+                Ok(ImageOutcome { skipped: outputs.len(), ..Default::default() })
+            } else {
+                Self::process_single_image(path, base_name, config, location, detect)
+            };
+
+            let result = match result {
+                Ok(outcome) => {
+                    processed.fetch_add(1, Ordering::Relaxed);
+                    written_tiles.fetch_add(outcome.written, Ordering::Relaxed);
+                    skipped_tiles.fetch_add(outcome.skipped, Ordering::Relaxed);
+                    empty_tiles.fetch_add(outcome.empty, Ordering::Relaxed);
+                    if outcome.collapsed {
+                        collapsed_images.fetch_add(1, Ordering::Relaxed);
+                    }
+                    if !outcome.fallbacks.is_empty() {
+                        format_fallbacks.lock().unwrap().push((idx, outcome.fallbacks));
+                    }
+                    match outcome.detected {
+                        Some(true) => detected_images.lock().unwrap().push((idx, path.clone())),
+                        Some(false) => {
+                            detection_fallbacks.fetch_add(1, Ordering::Relaxed);
+                        }
+                        None => {}
+                    }
+                    Ok(())
+                }
+                Err(e) => {
+                    failed.fetch_add(1, Ordering::Relaxed);
+                    eprintln!("处理失败 {:?}: {:?}", path, e);
+                    let message = e.to_string();
+                    failures.lock().unwrap().push((idx, path.clone(), message.clone()));
+                    Err(message)
+                }
+            };
+
+            let _ = events.send(ProgressEvent::FileDone { index: idx, path: path.clone(), result });
+        });
+
+        let report = BatchReport {
+            processed: processed.load(Ordering::Relaxed),
+            failed: failed.load(Ordering::Relaxed),
+            failures: {
+                let mut failures = failures.into_inner().unwrap();
+                failures.sort_by_key(|(idx, _, _)| *idx);
+                failures.into_iter().map(|(_, path, message)| (path, message)).collect()
+            },
+            cancelled: cancelled.load(Ordering::Relaxed),
+            format_fallbacks: {
+                let mut fallbacks = format_fallbacks.into_inner().unwrap();
+                fallbacks.sort_by_key(|(idx, _)| *idx);
+                fallbacks.into_iter().flat_map(|(_, files)| files).collect()
+            },
+            skipped_images: skipped_images.load(Ordering::Relaxed),
+            skipped_tiles: skipped_tiles.load(Ordering::Relaxed),
+            written_tiles: written_tiles.load(Ordering::Relaxed),
+            empty_tiles: empty_tiles.load(Ordering::Relaxed),
+            collapsed_images: collapsed_images.load(Ordering::Relaxed),
+            detected_images: {
+                let mut detected = detected_images.into_inner().unwrap();
+                detected.sort_by_key(|(idx, _)| *idx);
+                detected.into_iter().map(|(_, path)| path).collect()
+            },
+            detection_fallbacks: detection_fallbacks.load(Ordering::Relaxed),
+        };
+        let _ = events.send(ProgressEvent::Finished { processed: report.processed, failed: report.failed });
+        Ok(report)
+    }
+
+    /// 只处理列表中的一张图片，输出文件名与批量处理一致
+    pub fn process_image(
+        image_paths: &[PathBuf],
+        index: usize,
+        config: &SplitConfig,
+        location: &OutputLocation,
+    ) -> anyhow::Result<BatchReport> {
+        let path = image_paths
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("图片索引越界: {}", index))?;
+        if let OutputLocation::Chosen(output_dir) = location {
+            std::fs::create_dir_all(output_dir)?;
+        }
+        let base_name = location.base_name(image_paths, index);
+        let outcome = Self::process_single_image(path, &base_name, config, location, false)?;
+        Ok(BatchReport {
+            processed: 1,
+            skipped_tiles: outcome.skipped,
+            written_tiles: outcome.written,
+            empty_tiles: outcome.empty,
+            collapsed_images: usize::from(outcome.collapsed),
+            format_fallbacks: outcome.fallbacks,
+            ..Default::default()
+        })
+    }
+
+    /// 根据图片头部尺寸推算该图片的全部输出路径，无法读取尺寸时返回 `None`
+    pub fn planned_outputs(
+        path: &Path,
+        base_name: &str,
+        config: &SplitConfig,
+        location: &OutputLocation,
+    ) -> Option<Vec<PathBuf>> {
+        let (width, height) = Self::oriented_dimensions(path, config)?;
+        let (format, ext) = config.output_format_for(path);
+        let output_dir = &location.image_dir(config, path, base_name);
+        let tiles = Self::compute_regions(width, height, config).into_iter().map(|region| {
+            let size = Self::tile_output_size(config, region.width, region.height, config.cell_rotation(region.row, region.col));
+            (output_dir.join(Self::tile_file_name(base_name, &region, ext, config)), size)
+        });
+        let crops = config.crop_regions.iter().enumerate().filter_map(|(i, crop)| {
+            let (_, _, w, h) = crop.to_pixels(width, height)?;
+            Some((output_dir.join(Self::crop_file_name(base_name, i, ext)), Self::tile_output_size(config, w, h, None)))
+        });
+        let scales = config.effective_scales();
+        // 总览图按原图尺寸绘制，不放入统一画布、不缩放
+        let overview = config
+            .export_overview
+            .then(|| Self::written_path(&output_dir.join(Self::overview_file_name(base_name, ext)), format, (width, height)));
+        Some(
+            tiles
+                .chain(crops)
+                .flat_map(|(path, size)| {
+                    scales
+                        .iter()
+                        .map(|&s| Self::written_path(&config.scaled_path(&path, s), format, scaled_size(size, s)))
+                        .collect::<Vec<_>>()
+                })
+                .chain(overview)
+                .collect(),
+        )
+    }
+
+    /// 分块相对输出目录的路径，z/x/y 命名时包含子文件夹
+    fn tile_file_name(base_name: &str, region: &Region, ext: &str, config: &SplitConfig) -> PathBuf {
+        let (rows, cols) = config.grid_size();
+        let (rows, cols) = (rows as u32, cols as u32);
+        let (x, y) = (region.col as u32, region.row as u32);
+        let row = padded_index(region.row + 1, rows as usize);
+        let col = padded_index(region.col + 1, cols as usize);
+        let row_col = || format!("{}_{}_{}.{}", base_name, row, col, ext);
+        match config.effective_naming_scheme() {
+            NamingScheme::RowCol => row_col().into(),
+            // 模板在处理前已校验，这里出错只可能来自预估等路径，按行列命名即可
+            NamingScheme::Template => render_file_template(&config.filename_template, |token| match token {
+                "name" => base_name.to_string(),
+                "row" => row.clone(),
+                "col" => col.clone(),
+                "index" => padded_index(
+                    config.tile_order.index(region.row, region.col, rows as usize, cols as usize),
+                    (rows * cols) as usize,
+                ),
+                _ => ext.to_string(),
+            })
+            .unwrap_or_else(|_| row_col())
+            .into(),
+            NamingScheme::ZoomXY => {
+                let zoom = rows.max(cols).next_power_of_two().trailing_zeros();
+                Path::new(base_name)
+                    .join(zoom.to_string())
+                    .join(x.to_string())
+                    .join(format!("{}.{}", y, ext))
+            }
+            NamingScheme::Morton => {
+                let width = morton_index(cols - 1, rows - 1).to_string().len();
+                format!("{}_{:0width$}.{}", base_name, morton_index(x, y), ext, width = width).into()
+            }
+        }
+    }
+
+    fn crop_file_name(base_name: &str, index: usize, ext: &str) -> String {
+        format!("{}_crop_{}.{}", base_name, index + 1, ext)
+    }
+
+    /// 输出文件名前缀：取源文件名，同名出现多次（同一文件重复添加、不同文件夹或扩展名不同）时
+    /// 追加副本序号以免覆盖
+    pub fn output_base_name(image_paths: &[PathBuf], idx: usize) -> String {
+        Self::output_base_names(image_paths).swap_remove(idx)
+    }
+
+    /// 列表中每张图片的输出文件名前缀，见 [`Self::output_base_name`]
+    pub fn output_base_names(image_paths: &[PathBuf]) -> Vec<String> {
+        Self::unique_base_names(image_paths, |_| ())
+    }
+
+    /// 为每张图片分配输出文件名前缀，`group` 相同的图片之间互不重名
+    ///
+    /// 每组中第一次出现的文件名保持不变，之后的同名图片依次尝试 `_2`、`_3`…，
+    /// 跳过组内已分配的名称以及组内其他图片本身的文件名（如 `a/photo`、`b/photo` 与 `c/photo_2`）。
+    fn unique_base_names<K: Eq + std::hash::Hash>(image_paths: &[PathBuf], group: impl Fn(&Path) -> K) -> Vec<String> {
+        let stem = |path: &Path| {
+            path.file_stem()
+                .map_or_else(|| "image".to_string(), |s| s.to_string_lossy().into_owned())
+        };
+        let mut reserved: std::collections::HashMap<K, std::collections::HashSet<String>> = std::collections::HashMap::new();
+        for path in image_paths {
+            reserved.entry(group(path)).or_default().insert(stem(path));
+        }
+        let mut assigned: std::collections::HashMap<K, std::collections::HashSet<String>> = std::collections::HashMap::new();
+        image_paths
+            .iter()
+            .map(|path| {
+                let stem = stem(path);
+                let reserved = &reserved[&group(path)];
+                let used = assigned.entry(group(path)).or_default();
+                let name = if used.contains(&stem) {
+                    (2..)
+                        .map(|n| format!("{}_{}", stem, n))
+                        .find(|name| !used.contains(name) && !reserved.contains(name))
+                        .unwrap_or_default()
+                } else {
+                    stem
+                };
+                used.insert(name.clone());
+                name
+            })
+            .collect()
+    }
+
+    /// 处理单张图片；`detect` 为真时先检测网格，检测失败才使用 `config` 的分割线
+    fn process_single_image(
+        path: &Path,
+        base_name: &str,
+        config: &SplitConfig,
+        location: &OutputLocation,
+        detect: bool,
+    ) -> anyhow::Result<ImageOutcome> {
+        if config.naming_scheme == NamingScheme::Template {
+            config.validate_template()?;
+        }
+        let img = Self::open_image_for(path, config)?;
+        let detected_config;
+        let mut detected = None;
+        let config = match detect.then(|| Self::detect_grid(&img, config)) {
+            Some(Some(lines)) => {
+                detected = Some(true);
+                detected_config = Self::with_detected_lines(config, lines);
+                &detected_config
+            }
+            Some(None) => {
+                detected = Some(false);
+                config
+            }
+            None => config,
+        };
+        let (format, ext) = config.output_format_for(path);
+        let output_dir = &location.image_dir(config, path, base_name);
+        std::fs::create_dir_all(output_dir)?;
+        let mut outcome = ImageOutcome {
+            collapsed: !Self::fits_grid(img.width(), img.height(), config),
+            detected,
+            ..Default::default()
+        };
+        let source_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let source_info = |cell: String| {
+            config.embed_source_info.then(|| format!("source={};{}", source_name, cell))
+        };
+
+        let scales = config.effective_scales();
+
+        // 逐块裁剪并写入，避免同时持有所有分块；各尺寸都已存在的分块不裁剪
+        let (mut regions, empty) = Self::compute_regions_counted(img.width(), img.height(), config);
+        outcome.empty = empty;
+        if config.tile_order == TileOrder::ColumnMajor {
+            regions.sort_by_key(|region| (region.col, region.row));
+        }
+        for region in regions {
+            let output_path = output_dir.join(Self::tile_file_name(base_name, &region, ext, config));
+            let size = Self::tile_output_size(config, region.width, region.height, config.cell_rotation(region.row, region.col));
+            let targets = Self::pending_targets(config, &scales, &output_path, format, size, &mut outcome);
+            if targets.is_empty() {
+                continue;
+            }
+
+            let part = img.crop_imm(region.x, region.y, region.width, region.height);
+            let part = Self::apply_cell_rotation(part, config, &region);
+            let part = Self::apply_canvas(part, config);
+            let info = source_info(format!("cell={},{}", region.row + 1, region.col + 1));
+            Self::save_scaled(&part, &targets, format, config, info.as_deref(), &mut outcome)?;
+            outcome.written += targets.len();
+        }
+
+        // 自由裁剪区域按添加顺序编号
+        for (i, crop) in config.crop_regions.iter().enumerate() {
+            let Some((x, y, w, h)) = crop.to_pixels(img.width(), img.height()) else {
+                continue;
+            };
+            let output_path = output_dir.join(Self::crop_file_name(base_name, i, ext));
+            let size = Self::tile_output_size(config, w, h, None);
+            let targets = Self::pending_targets(config, &scales, &output_path, format, size, &mut outcome);
+            if targets.is_empty() {
+                continue;
+            }
+            let info = source_info(format!("crop={}", i + 1));
+            let part = Self::apply_canvas(img.crop_imm(x, y, w, h), config);
+            Self::save_scaled(&part, &targets, format, config, info.as_deref(), &mut outcome)?;
+            outcome.written += targets.len();
+        }
+
+        // 总览图只按原尺寸导出一份
+        if config.export_overview {
+            let output_path = output_dir.join(Self::overview_file_name(base_name, ext));
+            if config.skip_existing && Self::written_path(&output_path, format, (img.width(), img.height())).exists() {
+                outcome.skipped += 1;
+            } else {
+                let overview = Self::render_overview(&img, config);
+                let info = source_info("overview".to_string());
+                let fallback = Self::save_tile(&overview, &output_path, format, config.encode_options(), info.as_deref())?;
+                outcome.fallbacks.extend(fallback);
+                outcome.written += 1;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// 分块各尺寸中需要写入的 `(倍数, 路径)`，已存在而跳过的计入 `outcome.skipped`
+    ///
+    /// `size` 为未缩放的输出尺寸，用于判断 WebP 放不下、实际写成 PNG 的文件。
+    fn pending_targets(
+        config: &SplitConfig,
+        scales: &[f32],
+        output_path: &Path,
+        format: ImageFormat,
+        size: (u32, u32),
+        outcome: &mut ImageOutcome,
+    ) -> Vec<(f32, PathBuf)> {
+        let mut targets = Vec::with_capacity(scales.len());
+        for &scale in scales {
+            let path = config.scaled_path(output_path, scale);
+            if config.skip_existing && Self::written_path(&path, format, scaled_size(size, scale)).exists() {
+                outcome.skipped += 1;
+            } else {
+                targets.push((scale, path));
+            }
+        }
+        targets
+    }
+
+    /// 按各倍数缩放分块后保存，尺寸以分块像素乘以倍数后四舍五入
+    fn save_scaled(
+        part: &DynamicImage,
+        targets: &[(f32, PathBuf)],
+        format: ImageFormat,
+        config: &SplitConfig,
+        comment: Option<&str>,
+        outcome: &mut ImageOutcome,
+    ) -> anyhow::Result<()> {
+        for (scale, path) in targets {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let fallback = if *scale == 1.0 {
+                Self::save_tile(part, path, format, config.encode_options(), comment)?
+            } else {
+                let (width, height) = scaled_size((part.width(), part.height()), *scale);
+                let scaled = part.resize_exact(width, height, image::imageops::FilterType::Lanczos3);
+                Self::save_tile(&scaled, path, format, config.encode_options(), comment)?
+            };
+            outcome.fallbacks.extend(fallback);
+        }
+        Ok(())
+    }
+
+    /// 分块写入时的像素尺寸（分块旋转、统一画布之后，缩放之前），与实际裁剪保存的结果一致
+    fn tile_output_size(config: &SplitConfig, width: u32, height: u32, rotation: Option<Rotation>) -> (u32, u32) {
+        let size = match rotation {
+            Some(rotation) if rotation.swaps_dimensions() => (height, width),
+            _ => (width, height),
+        };
+        config.canvas_size().unwrap_or(size)
+    }
+
+    /// 按输出尺寸推算文件实际写入的路径：WebP 放不下时 [`Self::save_tile`] 改存为同名 PNG
+    fn written_path(path: &Path, format: ImageFormat, (width, height): (u32, u32)) -> PathBuf {
+        if exceeds_webp(format, width, height) {
+            path.with_extension("png")
+        } else {
+            path.to_path_buf()
+        }
+    }
+
+    /// 开启统一画布时把分块放到固定尺寸的透明画布上
+    fn apply_canvas(part: DynamicImage, config: &SplitConfig) -> DynamicImage {
+        match config.canvas_size() {
+            Some((width, height)) => Self::fit_to_canvas(&part, width, height),
+            None => part,
+        }
+    }
+
+    /// 把图片居中放到 `width` x `height` 的透明画布上，超出画布时先等比缩小到刚好放下
+    pub fn fit_to_canvas(part: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+        let part = if part.width() > width || part.height() > height {
+            // resize 保持宽高比，结果不超过给定尺寸
+            part.resize(width, height, image::imageops::FilterType::Lanczos3)
+        } else {
+            part.clone()
+        };
+        let mut canvas = image::RgbaImage::new(width, height);
+        let x = (width - part.width()) / 2;
+        let y = (height - part.height()) / 2;
+        image::imageops::overlay(&mut canvas, &part.to_rgba8(), x as i64, y as i64);
+        DynamicImage::ImageRgba8(canvas)
+    }
+
+    /// 按配置旋转裁剪后的分块
+    fn apply_cell_rotation(part: DynamicImage, config: &SplitConfig, region: &Region) -> DynamicImage {
+        match config.cell_rotation(region.row, region.col) {
+            Some(rotation) => rotation.apply(&part),
+            None => part,
+        }
+    }
+
+    /// 保存单个分块
+    ///
+    /// WebP 放不下的分块（宽或高超过 [`WEBP_MAX_DIMENSION`]）改存为同名 PNG，返回 `(实际路径, 原因)`。
+    fn save_tile(
+        part: &DynamicImage,
+        output_path: &Path,
+        format: ImageFormat,
+        options: EncodeOptions,
+        comment: Option<&str>,
+    ) -> anyhow::Result<Option<(PathBuf, String)>> {
+        if exceeds_webp(format, part.width(), part.height()) {
+            let png_path = output_path.with_extension("png");
+            Self::save_tile(part, &png_path, ImageFormat::Png, options, comment)?;
+            let reason = format!(
+                "{}x{} 超出 WebP 的最大尺寸 {}，已改存为 PNG",
+                part.width(),
+                part.height(),
+                WEBP_MAX_DIMENSION
+            );
+            return Ok(Some((png_path, reason)));
+        }
+        match comment {
+            // 需要写入注释时先编码到内存，插入元数据后再落盘
+            Some(comment) => {
+                let mut bytes = Vec::new();
+                Self::encode_tile(part, &mut std::io::Cursor::new(&mut bytes), format, options)?;
+                embed_comment(&mut bytes, format, comment);
+                std::fs::write(output_path, bytes)?;
+            }
+            None => {
+                let mut writer = std::io::BufWriter::new(std::fs::File::create(output_path)?);
+                Self::encode_tile(part, &mut writer, format, options)?;
+            }
+        }
+        Ok(None)
+    }
+
+    /// 编码单个分块，JPEG 仅支持 8 位 RGB/灰度，其余颜色类型先转换为 RGB；
+    /// BMP/WebP 仅支持 8 位颜色，其余颜色类型先转换为 RGBA（有损 WebP 不带透明时转换为 RGB）
+    fn encode_tile<W: std::io::Write + std::io::Seek>(
+        part: &DynamicImage,
+        writer: &mut W,
+        format: ImageFormat,
+        options: EncodeOptions,
+    ) -> anyhow::Result<()> {
+        match format {
+            ImageFormat::Jpeg => {
+                let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(writer, valid_jpeg_quality(options.jpeg_quality));
+                match part {
+                    DynamicImage::ImageRgb8(_) | DynamicImage::ImageLuma8(_) => part.write_with_encoder(encoder)?,
+                    _ => DynamicImage::ImageRgb8(part.to_rgb8()).write_with_encoder(encoder)?,
+                }
+            }
+            ImageFormat::Png if options.png_max_colors.is_some() => {
+                Self::encode_indexed_png(part, writer, options)?;
+            }
+            ImageFormat::Png => {
+                let encoder = image::codecs::png::PngEncoder::new_with_quality(
+                    writer,
+                    options.png_compression.compression_type(),
+                    options.png_filter.filter_type(),
+                );
+                part.write_with_encoder(encoder)?;
+            }
+            ImageFormat::WebP if !options.webp_lossless => {
+                let quality = valid_jpeg_quality(options.jpeg_quality) as f32;
+                let (width, height) = (part.width(), part.height());
+                let encoded = if part.color().has_alpha() {
+                    let rgba = part.to_rgba8();
+                    webp::Encoder::from_rgba(rgba.as_raw(), width, height).encode_simple(false, quality)
+                } else {
+                    let rgb = part.to_rgb8();
+                    webp::Encoder::from_rgb(rgb.as_raw(), width, height).encode_simple(false, quality)
+                }
+                .map_err(|e| anyhow::anyhow!("WebP 编码失败: {:?}", e))?;
+                writer.write_all(&encoded)?;
+            }
+            ImageFormat::Bmp | ImageFormat::WebP => match part {
+                DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) | DynamicImage::ImageLuma8(_) | DynamicImage::ImageLumaA8(_) => {
+                    part.write_to(writer, format)?
+                }
+                _ => DynamicImage::ImageRgba8(part.to_rgba8()).write_to(writer, format)?,
+            },
+            _ => part.write_to(writer, format)?,
+        }
+        Ok(())
+    }
+
+    /// 量化为 8 位调色板 PNG，保留透明度（写入 tRNS）
+    ///
+    /// 颜色数不超过上限时直接使用原有颜色（无损），否则用 NeuQuant 量化。
+    fn encode_indexed_png<W: std::io::Write>(
+        part: &DynamicImage,
+        writer: &mut W,
+        options: EncodeOptions,
+    ) -> anyhow::Result<()> {
+        let max_colors = options.png_max_colors.unwrap_or(256).clamp(2, 256) as usize;
+        let rgba = part.to_rgba8();
+        let pixels = rgba.as_raw();
+
+        // 先尝试精确调色板
+        let mut exact: std::collections::HashMap<[u8; 4], u8> = std::collections::HashMap::new();
+        let mut palette_rgba: Vec<[u8; 4]> = Vec::new();
+        let mut overflow = false;
+        for px in pixels.chunks_exact(4) {
+            let color = [px[0], px[1], px[2], px[3]];
+            if let std::collections::hash_map::Entry::Vacant(entry) = exact.entry(color) {
+                if palette_rgba.len() == max_colors {
+                    overflow = true;
+                    break;
+                }
+                entry.insert(palette_rgba.len() as u8);
+                palette_rgba.push(color);
+            }
+        }
+
+        let indices: Vec<u8> = if !overflow {
+            pixels
+                .chunks_exact(4)
+                .map(|px| exact[&[px[0], px[1], px[2], px[3]]])
+                .collect()
+        } else {
+            let quant = color_quant::NeuQuant::new(10, max_colors, pixels);
+            palette_rgba = quant
+                .color_map_rgba()
+                .chunks_exact(4)
+                .map(|c| [c[0], c[1], c[2], c[3]])
+                .collect();
+            pixels.chunks_exact(4).map(|px| quant.index_of(px) as u8).collect()
+        };
+
+        let palette: Vec<u8> = palette_rgba.iter().flat_map(|c| [c[0], c[1], c[2]]).collect();
+        let mut encoder = png::Encoder::new(writer, rgba.width(), rgba.height());
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(palette);
+        if palette_rgba.iter().any(|c| c[3] != 255) {
+            encoder.set_trns(palette_rgba.iter().map(|c| c[3]).collect::<Vec<u8>>());
+        }
+        encoder.set_compression(options.png_compression.png_compression());
+        encoder.set_filter(options.png_filter.png_filter());
+        let mut png_writer = encoder.write_header()?;
+        png_writer.write_image_data(&indices)?;
+        png_writer.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    /// 测试专用的空临时文件夹，按测试名和进程号区分
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("batch-image-splitter-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 各分块放回原位后每个像素恰好被覆盖一次
+    fn assert_exact_cover(regions: &[Region], width: u32, height: u32) {
+        let mut hits = vec![0u8; (width * height) as usize];
+        for r in regions {
+            assert!(r.x + r.width <= width && r.y + r.height <= height, "分块超出图片: {:?}", r);
+            for y in r.y..r.y + r.height {
+                for x in r.x..r.x + r.width {
+                    hits[(y * width + x) as usize] += 1;
+                }
+            }
+        }
+        if let Some(i) = hits.iter().position(|&n| n != 1) {
+            panic!("像素 ({}, {}) 被覆盖 {} 次", i as u32 % width, i as u32 / width, hits[i]);
+        }
+    }
+
+    #[test]
+    fn lossless_tiles_cover_odd_image_exactly_once() {
+        let (width, height) = (101, 67);
+        let mut uneven = SplitConfig::new(1, 1);
+        uneven.h_lines = vec![0.333, 0.5, 0.71];
+        uneven.v_lines = vec![0.1, 0.45, 0.9];
+        for mut config in [SplitConfig::new(3, 4), SplitConfig::new(7, 9), uneven] {
+            config.line_mode = LineMode::Lossless;
+            let regions = ImageSplitter::compute_regions(width, height, &config);
+            let (rows, cols) = (config.h_lines.len() + 1, config.v_lines.len() + 1);
+            assert_eq!(regions.len(), rows * cols);
+
+            // 每行宽度之和、每列高度之和等于图片尺寸
+            for row in 0..rows {
+                let sum: u32 = regions.iter().filter(|r| r.row == row).map(|r| r.width).sum();
+                assert_eq!(sum, width, "第 {} 行", row);
+            }
+            for col in 0..cols {
+                let sum: u32 = regions.iter().filter(|r| r.col == col).map(|r| r.height).sum();
+                assert_eq!(sum, height, "第 {} 列", col);
+            }
+            assert_exact_cover(&regions, width, height);
+        }
+    }
+
+    /// 覆盖各分割线模式、出血、间隔、外边距和分块合并的配置
+    fn tiny_image_configs() -> Vec<SplitConfig> {
+        let mut configs = Vec::new();
+        for (rows, cols) in [(1, 1), (1, 4), (4, 1), (3, 3), (16, 16)] {
+            for mode in [LineMode::SharedEdge, LineMode::TrimWithBleed, LineMode::Gutters, LineMode::Lossless] {
+                let mut config = SplitConfig::new(rows, cols);
+                config.line_mode = mode;
+                config.bleed_px = 2;
+                configs.push(config.clone());
+                config.gutter_px = 3;
+                config.margin_left = 1;
+                config.margin_bottom = 1;
+                configs.push(config.clone());
+                config.cell_merges = vec![CellMerge { row: 0, col: 0, rows: 2, cols: 2 }];
+                configs.push(config);
+            }
+        }
+        configs
+    }
+
+    #[test]
+    fn tiny_images_split_without_panicking() {
+        for (width, height) in [(1, 1), (1, 2), (1, 37), (2, 1), (37, 1)] {
+            for config in tiny_image_configs() {
+                let regions = ImageSplitter::compute_regions(width, height, &config);
+                assert!(!regions.is_empty(), "{}x{} {:?} 没有分块", width, height, config.line_mode);
+                for r in &regions {
+                    assert!(r.width > 0 && r.height > 0, "{}x{} 出现空分块 {:?}", width, height, r);
+                    assert!(r.x + r.width <= width && r.y + r.height <= height, "{}x{} 分块越界 {:?}", width, height, r);
+                }
+                let img = DynamicImage::new_rgba8(width, height);
+                assert_eq!(ImageSplitter::iter_tiles(&img, &config).count(), regions.len());
+            }
+        }
+    }
+
+    #[test]
+    fn unsplittable_tiny_images_become_one_whole_tile() {
+        let whole = |width, height| Region { row: 0, col: 0, x: 0, y: 0, width, height };
+        for config in tiny_image_configs() {
+            // 1x1 无论怎样配置都只能整张导出
+            assert_eq!(ImageSplitter::compute_regions(1, 1, &config), vec![whole(1, 1)]);
+        }
+        for mode in [LineMode::SharedEdge, LineMode::TrimWithBleed, LineMode::Gutters, LineMode::Lossless] {
+            // 宽 1 像素时无法按列分割，高 1 像素时无法按行分割
+            let mut cols_only = SplitConfig::new(1, 5);
+            cols_only.line_mode = mode;
+            assert_eq!(ImageSplitter::compute_regions(1, 40, &cols_only), vec![whole(1, 40)]);
+            let mut rows_only = SplitConfig::new(5, 1);
+            rows_only.line_mode = mode;
+            assert_eq!(ImageSplitter::compute_regions(40, 1, &rows_only), vec![whole(40, 1)]);
+        }
+    }
+
+    #[test]
+    fn iter_tiles_matches_compute_regions() {
+        // 每个像素的颜色由坐标决定，用于确认分块取自正确位置
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_fn(53, 31, |x, y| image::Rgb([x as u8, y as u8, 0])));
+        let mut uneven = SplitConfig::new(1, 1);
+        uneven.h_lines = vec![0.2, 0.75];
+        uneven.v_lines = vec![0.5];
+        let mut bleed = SplitConfig::new(3, 3);
+        bleed.line_mode = LineMode::TrimWithBleed;
+        bleed.bleed_px = 2;
+        for config in [SplitConfig::new(1, 1), SplitConfig::new(4, 5), uneven, bleed] {
+            let regions = ImageSplitter::compute_regions(img.width(), img.height(), &config);
+            let tiles: Vec<(Region, DynamicImage)> = ImageSplitter::iter_tiles(&img, &config).collect();
+            assert_eq!(tiles.len(), regions.len());
+            for ((region, tile), expected) in tiles.iter().zip(&regions) {
+                assert_eq!(region, expected);
+                assert_eq!((tile.width(), tile.height()), (region.width, region.height));
+                assert_eq!(tile.to_rgb8().get_pixel(0, 0).0, [region.x as u8, region.y as u8, 0]);
+            }
+        }
+    }
+
+    #[test]
+    fn encoded_tiles_decode_to_region_sizes() {
+        let img = DynamicImage::new_rgb8(10, 7);
+        let mut config = SplitConfig::new(2, 3);
+        config.set_cell_rotation(1, 2, Some(Rotation::Rotate90));
+        let regions = ImageSplitter::compute_regions(10, 7, &config);
+        for format in [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::Bmp, ImageFormat::WebP] {
+            let encoded = ImageSplitter::split_to_encoded(&img, &config, format, EncodeOptions::default()).unwrap();
+            assert_eq!(encoded.len(), regions.len());
+            for ((suffix, bytes), region) in encoded.iter().zip(&regions) {
+                assert_eq!(suffix, &format!("_{}_{}", region.row + 1, region.col + 1));
+                let decoded = image::load_from_memory_with_format(bytes, format).unwrap();
+                // 旋转 90° 的分块宽高互换
+                let expected = match config.cell_rotation(region.row, region.col) {
+                    Some(_) => (region.height, region.width),
+                    None => (region.width, region.height),
+                };
+                assert_eq!((decoded.width(), decoded.height()), expected, "{:?} {}", format, suffix);
+            }
+        }
+    }
+
+    #[test]
+    fn batch_skips_image_without_decoding_when_all_outputs_exist() {
+        let dir = temp_dir("skip-existing");
+        // 截断在像素数据（IDAT）开头：能读出尺寸，但无法解码像素
+        let source = dir.join("source.png");
+        let mut bytes = Vec::new();
+        DynamicImage::new_rgb8(40, 30).write_to(&mut std::io::Cursor::new(&mut bytes), ImageFormat::Png).unwrap();
+        let idat = bytes.windows(4).position(|w| w == b"IDAT").unwrap();
+        std::fs::write(&source, &bytes[..idat + 4]).unwrap();
+
+        let paths = vec![source.clone()];
+        let location = OutputLocation::Chosen(dir.join("out"));
+        let mut config = SplitConfig::new(2, 3);
+        config.multi_scale = true;
+        config.output_scales = vec![1.0, 2.0];
+        config.export_overview = true;
+        let run = |config: &SplitConfig| {
+            ImageSplitter::batch_process(&paths, config, &Default::default(), &location, &AtomicBool::new(false), |_, _| {}).unwrap()
+        };
+        assert_eq!(run(&config).failed, 1, "源文件应无法解码");
+
+        let outputs = ImageSplitter::planned_outputs(&source, &location.base_name(&paths, 0), &config, &location).unwrap();
+        assert_eq!(outputs.len(), 2 * 3 * 2 + 1);
+        for output in &outputs {
+            std::fs::create_dir_all(output.parent().unwrap()).unwrap();
+            std::fs::write(output, b"").unwrap();
+        }
+
+        config.skip_existing = true;
+        let report = run(&config);
+        assert_eq!((report.processed, report.failed), (1, 0));
+        assert_eq!(report.skipped_images, 1);
+        assert_eq!(report.skipped_tiles, outputs.len());
+        assert_eq!(report.written_tiles, 0);
+        assert!(outputs.iter().all(|p| std::fs::metadata(p).unwrap().len() == 0), "已存在的文件不应被改写");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn batch_events_arrive_in_order() {
+        let dir = temp_dir("batch-events");
+        let paths: Vec<PathBuf> = ["a.png", "b.png"]
+            .iter()
+            .map(|name| {
+                let path = dir.join(name);
+                DynamicImage::new_rgb8(20, 10).save(&path).unwrap();
+                path
+            })
+            .collect();
+        let receiver = ImageSplitter::batch_process_events(
+            paths.clone(),
+            SplitConfig::new(1, 2),
+            Default::default(),
+            OutputLocation::Chosen(dir.join("out")),
+            std::sync::Arc::new(AtomicBool::new(false)),
+        );
+        // 发送端在批处理结束后释放，迭代随之结束
+        let events: Vec<ProgressEvent> = receiver.iter().collect();
+
+        assert_eq!(events.len(), 4, "{:?}", events);
+        assert_eq!(events[0], ProgressEvent::Started { total: 2 });
+        let mut done: Vec<usize> = events[1..3]
+            .iter()
+            .map(|event| match event {
+                ProgressEvent::FileDone { index, path, result } => {
+                    assert_eq!(path, &paths[*index]);
+                    assert_eq!(result, &Ok(()));
+                    *index
+                }
+                other => panic!("应为 FileDone，得到 {:?}", other),
+            })
+            .collect();
+        done.sort_unstable();
+        assert_eq!(done, vec![0, 1]);
+        assert_eq!(events[3], ProgressEvent::Finished { processed: 2, failed: 0 });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn batch_events_end_with_failed_when_output_dir_is_unusable() {
+        let dir = temp_dir("batch-events-failed");
+        let source = dir.join("a.png");
+        DynamicImage::new_rgb8(20, 10).save(&source).unwrap();
+        // 输出目录的位置已有同名文件，无法创建
+        let blocked = dir.join("out");
+        std::fs::write(&blocked, b"").unwrap();
+        let receiver = ImageSplitter::batch_process_events(
+            vec![source],
+            SplitConfig::new(1, 2),
+            Default::default(),
+            OutputLocation::Chosen(blocked),
+            std::sync::Arc::new(AtomicBool::new(false)),
+        );
+        let events: Vec<ProgressEvent> = receiver.iter().collect();
+
+        assert!(matches!(events.as_slice(), [ProgressEvent::Failed(_)]), "{:?}", events);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn indexed_png_is_paletted_within_color_limit() {
+        // 3 种颜色（可精确保留）与远超上限的渐变（需量化），均带透明度
+        let few = image::RgbaImage::from_fn(12, 9, |x, _| match x % 3 {
+            0 => image::Rgba([255, 0, 0, 255]),
+            1 => image::Rgba([0, 255, 0, 128]),
+            _ => image::Rgba([0, 0, 255, 0]),
+        });
+        let many = image::RgbaImage::from_fn(64, 64, |x, y| image::Rgba([(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8, 255]));
+        for (img, max_colors, exact) in [(few, 16u16, Some(3)), (many, 16, None)] {
+            let options = EncodeOptions { png_max_colors: Some(max_colors), ..Default::default() };
+            let mut bytes = Vec::new();
+            ImageSplitter::encode_indexed_png(&DynamicImage::ImageRgba8(img.clone()), &mut bytes, options).unwrap();
+
+            let reader = png::Decoder::new(std::io::Cursor::new(&bytes)).read_info().unwrap();
+            let info = reader.info();
+            assert_eq!(info.color_type, png::ColorType::Indexed);
+            let palette_len = info.palette.as_ref().unwrap().len();
+            assert_eq!(palette_len % 3, 0);
+            assert!(palette_len / 3 <= max_colors as usize, "调色板有 {} 种颜色", palette_len / 3);
+            if let Some(colors) = exact {
+                assert_eq!(palette_len / 3, colors);
+                // 颜色不多时无损
+                let decoded = image::load_from_memory_with_format(&bytes, ImageFormat::Png).unwrap().to_rgba8();
+                assert_eq!(decoded, img);
+            }
+        }
+    }
+
+    #[test]
+    fn equalize_drops_lines_to_honor_min_cell() {
+        let mut config = SplitConfig::new(10, 10);
+        assert_eq!(config.equalize_with_min_cell(50, 30, 16), Some((10, 10)));
+        assert_eq!((config.rows, config.cols), (1, 3));
+        assert!(config.h_lines.is_empty());
+        assert_eq!(config.v_lines.len(), 2);
+        for region in ImageSplitter::compute_regions(50, 30, &config) {
+            assert!(region.width >= 16 && region.height >= 16, "分块过小: {:?}", region);
+        }
+
+        // 外边距之内的内容区域才参与计算
+        let mut config = SplitConfig::new(4, 4);
+        config.margin_left = 20;
+        assert_eq!(config.equalize_with_min_cell(64, 64, 16), Some((4, 4)));
+        assert_eq!((config.rows, config.cols), (4, 2));
+
+        // 放得下时不改变
+        let mut config = SplitConfig::new(2, 3);
+        assert_eq!(config.equalize_with_min_cell(300, 200, 16), None);
+        assert_eq!((config.h_lines.len(), config.v_lines.len()), (1, 2));
+
+        // 比最小尺寸还小的图片保留一个分块
+        let mut config = SplitConfig::new(3, 3);
+        config.equalize_with_min_cell(5, 5, 16);
+        assert_eq!(ImageSplitter::compute_regions(5, 5, &config).len(), 1);
+    }
+
+    #[test]
+    fn every_scale_is_written_at_its_size() {
+        let mut config = SplitConfig::new(2, 2);
+        config.multi_scale = true;
+        // 无效、重复的倍数被忽略，其余按从小到大导出
+        config.output_scales = vec![2.0, 0.5, 1.0, 2.0, -1.0, f32::NAN];
+        let scales = config.effective_scales();
+        assert_eq!(scales, vec![0.5, 1.0, 2.0]);
+
+        let dir = temp_dir("multi-scale");
+        let source = dir.join("source.png");
+        DynamicImage::new_rgb8(30, 21).save(&source).unwrap();
+        let out = dir.join("out");
+        let report = split_to_dir(&source, &config, &out).unwrap();
+        assert_eq!(report.written_tiles, 4 * scales.len());
+
+        let paths = [source.clone()];
+        let location = OutputLocation::Chosen(out);
+        let outputs = ImageSplitter::planned_outputs(&source, &location.base_name(&paths, 0), &config, &location).unwrap();
+        let regions = ImageSplitter::compute_regions(30, 21, &config);
+        assert_eq!(outputs.len(), regions.len() * scales.len());
+        // 每个分块的各倍数依次排列
+        for (output, (region, &scale)) in outputs.iter().zip(regions.iter().flat_map(|r| scales.iter().map(move |s| (r, s)))) {
+            let (width, height) = image::image_dimensions(output).unwrap();
+            let expected = (
+                ((region.width as f32 * scale).round() as u32).max(1),
+                ((region.height as f32 * scale).round() as u32).max(1),
+            );
+            assert_eq!((width, height), expected, "{}", output.display());
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn canvas_outputs_share_size_and_center_content() {
+        let dir = temp_dir("uniform-canvas");
+        let source = dir.join("source.png");
+        image::RgbaImage::from_pixel(50, 30, image::Rgba([200, 100, 50, 255])).save(&source).unwrap();
+        // 分块大小各不相同，最右一列宽于画布，需要缩小
+        let mut config = SplitConfig::new(1, 1);
+        config.h_lines = vec![0.4];
+        config.v_lines = vec![0.1, 0.3];
+        config.uniform_canvas = true;
+        config.canvas_width = 32;
+        config.canvas_height = 24;
+        let out = dir.join("out");
+        split_to_dir(&source, &config, &out).unwrap();
+
+        let paths = [source.clone()];
+        let location = OutputLocation::Chosen(out);
+        let outputs = ImageSplitter::planned_outputs(&source, &location.base_name(&paths, 0), &config, &location).unwrap();
+        assert_eq!(outputs.len(), 6);
+        for output in &outputs {
+            let tile = image::open(output).unwrap().to_rgba8();
+            assert_eq!(tile.dimensions(), (32, 24), "{}", output.display());
+            // 不透明内容的外接矩形四周留白相差不超过 1 像素
+            let opaque: Vec<(u32, u32)> = tile.enumerate_pixels().filter(|(_, _, p)| p[3] > 0).map(|(x, y, _)| (x, y)).collect();
+            assert!(!opaque.is_empty());
+            let left = opaque.iter().map(|p| p.0).min().unwrap();
+            let right = 31 - opaque.iter().map(|p| p.0).max().unwrap();
+            let top = opaque.iter().map(|p| p.1).min().unwrap();
+            let bottom = 23 - opaque.iter().map(|p| p.1).max().unwrap();
+            assert!(left.abs_diff(right) <= 1 && top.abs_diff(bottom) <= 1, "{}: {:?}", output.display(), (left, right, top, bottom));
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn adobe_cmyk_jpeg_is_not_inverted() {
+        // 16x8：左半纯红 (C0 M255 Y255 K0)，右半无墨；带 Adobe APP14 标记，按惯例存储反相值
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/cmyk_adobe.jpg");
+        let loaded = ImageSplitter::open_image_detailed(&path).unwrap();
+        assert!(loaded.cmyk_converted);
+        let rgb = loaded.image.to_rgb8();
+        assert_eq!(rgb.dimensions(), (16, 8));
+
+        let [r, g, b] = rgb.get_pixel(2, 4).0;
+        assert!(r > 220 && g < 40 && b < 40, "左半应为红色，得到 {:?}", [r, g, b]);
+        let [r, g, b] = rgb.get_pixel(13, 4).0;
+        assert!(r > 220 && g > 220 && b > 220, "右半应为白色，得到 {:?}", [r, g, b]);
+    }
+
+    #[test]
+    fn duplicate_names_skip_suffixes_taken_by_other_images() {
+        let paths: Vec<PathBuf> = ["a/photo.png", "b/photo.png", "c/photo_2.png"].iter().map(PathBuf::from).collect();
+        assert_eq!(ImageSplitter::output_base_names(&paths), ["photo", "photo_3", "photo_2"]);
+        // 输出到源图片旁时不同文件夹互不影响
+        assert_eq!(OutputLocation::AlongsideSource.base_names(&paths), ["photo", "photo", "photo_2"]);
+    }
 }
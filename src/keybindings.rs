@@ -0,0 +1,166 @@
+//! 可配置的快捷键
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// 可绑定快捷键的操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    OpenFiles,
+    SaveConfig,
+    StartBatch,
+    ProcessCurrent,
+    PreviousImage,
+    NextImage,
+    Delete,
+    ToggleDebugHud,
+    Undo,
+    Redo,
+    ZoomIn,
+    ZoomOut,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::OpenFiles,
+        Action::SaveConfig,
+        Action::StartBatch,
+        Action::ProcessCurrent,
+        Action::PreviousImage,
+        Action::NextImage,
+        Action::Delete,
+        Action::ToggleDebugHud,
+        Action::Undo,
+        Action::Redo,
+        Action::ZoomIn,
+        Action::ZoomOut,
+    ];
+
+    /// 界面显示名称
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::OpenFiles => "打开图片文件",
+            Action::SaveConfig => "保存当前分割线配置",
+            Action::StartBatch => "开始批量处理",
+            Action::ProcessCurrent => "只处理当前图片",
+            Action::PreviousImage => "上一张",
+            Action::NextImage => "下一张",
+            Action::Delete => "删除选中分割线",
+            Action::ToggleDebugHud => "显示/隐藏性能面板",
+            Action::Undo => "撤销分割线修改",
+            Action::Redo => "重做分割线修改",
+            Action::ZoomIn => "放大预览",
+            Action::ZoomOut => "缩小预览",
+        }
+    }
+}
+
+/// 单个按键组合
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: egui::Key,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyBinding {
+    pub const fn plain(key: egui::Key) -> Self {
+        Self { key, ctrl: false, shift: false, alt: false }
+    }
+
+    pub const fn ctrl(key: egui::Key) -> Self {
+        Self { key, ctrl: true, shift: false, alt: false }
+    }
+
+    /// 本帧是否按下了该组合（修饰键需完全一致）
+    pub fn pressed(&self, input: &egui::InputState) -> bool {
+        let m = input.modifiers;
+        input.key_pressed(self.key) && m.ctrl == self.ctrl && m.shift == self.shift && m.alt == self.alt
+    }
+
+    /// 不带 Ctrl/Alt 的方向键，会与分割线微调冲突
+    pub fn is_plain_arrow(&self) -> bool {
+        !self.ctrl
+            && !self.alt
+            && matches!(
+                self.key,
+                egui::Key::ArrowUp | egui::Key::ArrowDown | egui::Key::ArrowLeft | egui::Key::ArrowRight
+            )
+    }
+}
+
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl { write!(f, "Ctrl + ")?; }
+        if self.shift { write!(f, "Shift + ")?; }
+        if self.alt { write!(f, "Alt + ")?; }
+        write!(f, "{}", self.key.symbol_or_name())
+    }
+}
+
+/// 操作到按键组合的映射
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub bindings: BTreeMap<Action, KeyBinding>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .map(|&a| (a, Self::default_for(a)))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl KeyBindings {
+    /// 各操作的默认快捷键
+    pub fn default_for(action: Action) -> KeyBinding {
+        match action {
+            Action::OpenFiles => KeyBinding::ctrl(egui::Key::O),
+            Action::SaveConfig => KeyBinding::ctrl(egui::Key::S),
+            Action::StartBatch => KeyBinding::ctrl(egui::Key::Enter),
+            Action::ProcessCurrent => KeyBinding { shift: true, ..KeyBinding::ctrl(egui::Key::Enter) },
+            Action::PreviousImage => KeyBinding::ctrl(egui::Key::ArrowLeft),
+            Action::NextImage => KeyBinding::ctrl(egui::Key::ArrowRight),
+            Action::Delete => KeyBinding::plain(egui::Key::Delete),
+            Action::ToggleDebugHud => KeyBinding::plain(egui::Key::F12),
+            Action::Undo => KeyBinding::ctrl(egui::Key::Z),
+            Action::Redo => KeyBinding::ctrl(egui::Key::Y),
+            Action::ZoomIn => KeyBinding::ctrl(egui::Key::Plus),
+            Action::ZoomOut => KeyBinding::ctrl(egui::Key::Minus),
+        }
+    }
+
+    /// 操作当前的快捷键，旧存档中缺失的操作回落到默认值
+    pub fn get(&self, action: Action) -> KeyBinding {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| Self::default_for(action))
+    }
+
+    /// 已被其他操作占用时返回该操作
+    pub fn conflict(&self, action: Action, binding: &KeyBinding) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&other| other != action && self.get(other) == *binding)
+    }
+
+    /// 设置快捷键，冲突时不修改并返回冲突的操作
+    pub fn set(&mut self, action: Action, binding: KeyBinding) -> Result<(), Action> {
+        if let Some(other) = self.conflict(action, &binding) {
+            return Err(other);
+        }
+        self.bindings.insert(action, binding);
+        Ok(())
+    }
+
+    pub fn reset_to_default(&mut self) {
+        *self = Self::default();
+    }
+}
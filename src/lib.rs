@@ -0,0 +1,29 @@
+//! 批量图片分割工具的核心库
+//!
+//! 分割逻辑不依赖 GUI，可直接在其他程序中使用。只需要库时关闭默认的 `gui` 特性，
+//! 不会引入 eframe、文件对话框和剪贴板等界面依赖：
+//!
+//! ```toml
+//! batch-image-splitter = { version = "1", default-features = false }
+//! ```
+//!
+//! 按 2 行 3 列分割一张图片：
+//!
+//! ```no_run
+//! use batch_image_splitter::{split_to_dir, SplitConfig};
+//!
+//! let mut config = SplitConfig::default();
+//! config.rows = 2;
+//! config.cols = 3;
+//! config.reset_to_default();
+//!
+//! let report = split_to_dir("photo.png", &config, "tiles")?;
+//! println!("写入 {} 个分块", report.written_tiles);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! 批量处理、进度回调、取消等见 [`ImageSplitter`]。
+
+pub mod image_splitter;
+
+pub use image_splitter::{split_to_dir, BatchReport, ImageSplitter, OutputFormat, OutputLocation, SplitConfig};
@@ -1,10 +1,19 @@
 use eframe::egui;
 
 mod app;
+mod cli;
+mod dialogs;
 mod icons;
-mod image_splitter;
+mod keybindings;
+mod settings;
+mod sidecar;
+mod thumbnail_cache;
 
 use app::BatchImageSplitterApp;
+use batch_image_splitter::image_splitter;
+
+/// 程序名称，同时决定 eframe 保存设置和缓存的数据目录
+pub const APP_NAME: &str = "Batch Image Splitter";
 
 /// 加载图标
 fn load_icon() -> Option<egui::IconData> {
@@ -53,6 +62,11 @@ fn load_icon() -> Option<egui::IconData> {
 }
 
 fn main() -> eframe::Result<()> {
+    // 命令行模式：直接批量处理后退出，不创建窗口
+    if std::env::args().any(|arg| arg == cli::HEADLESS_FLAG) {
+        std::process::exit(cli::run(std::env::args().skip(1)));
+    }
+
     // 图标加载很快，直接在主线程加载以确保 ViewportBuilder 能立即使用它
     let icon = load_icon();
     
@@ -83,7 +97,7 @@ fn main() -> eframe::Result<()> {
     };
 
     eframe::run_native(
-        "Batch Image Splitter",
+        APP_NAME,
         options,
         Box::new(move |cc| {
             // 配置字体
@@ -102,27 +116,34 @@ fn main() -> eframe::Result<()> {
             }
             
             // 加载图标字体（直接嵌入到二进制文件中，确保便携性）
+            // 字体无效时不注册，图标改为显示文字替代
             let icon_font_data = include_bytes!("../MaterialIcons-Regular.ttf");
-            fonts.font_data.insert(
-                "material_icons".to_owned(),
-                egui::FontData::from_static(icon_font_data),
-            );
-            
-            // 插入到 Proportional 和 Monospace 家族
-            for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
-                let family_fonts = fonts.families.entry(family).or_default();
-                // 紧跟在中文字体后面，或者放在第一位
-                if family_fonts.contains(&"chinese".to_owned()) {
-                    family_fonts.insert(1, "material_icons".to_owned());
-                } else {
-                    family_fonts.insert(0, "material_icons".to_owned());
+            if icons::init_icon_font(icon_font_data) {
+                fonts.font_data.insert(
+                    "material_icons".to_owned(),
+                    egui::FontData::from_static(icon_font_data),
+                );
+                
+                // 插入到 Proportional 和 Monospace 家族
+                for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+                    let family_fonts = fonts.families.entry(family).or_default();
+                    // 紧跟在中文字体后面，或者放在第一位
+                    if family_fonts.contains(&"chinese".to_owned()) {
+                        family_fonts.insert(1, "material_icons".to_owned());
+                    } else {
+                        family_fonts.insert(0, "material_icons".to_owned());
+                    }
                 }
+            } else {
+                eprintln!("图标字体加载失败，图标将以文字显示");
             }
             
             cc.egui_ctx.set_fonts(fonts);
             
             // 应用现代化全局样式
             configure_custom_style(&cc.egui_ctx);
+            // Ctrl + +/- 用于缩放预览图片，关闭 egui 自带的界面缩放
+            cc.egui_ctx.options_mut(|o| o.zoom_with_keyboard = false);
             
             Ok(Box::new(BatchImageSplitterApp::new(cc)))
         }),
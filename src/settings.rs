@@ -0,0 +1,212 @@
+//! 用户偏好设置
+
+use serde::{Deserialize, Serialize};
+
+use crate::image_splitter::{NamingScheme, OutputFormat, PngCompression, PngFilter, SplitConfig, TileOrder, DEFAULT_DETECT_SENSITIVITY, DEFAULT_FILENAME_TEMPLATE, DEFAULT_JPEG_QUALITY, DEFAULT_SCALE_SUFFIX, valid_jpeg_quality};
+use crate::keybindings::KeyBindings;
+
+/// 偏好设置在 eframe 存储中的键
+pub const SETTINGS_KEY: &str = "app_settings";
+
+/// 开启吸附时的默认步长 (5%)
+pub const DEFAULT_SNAP_STEP: f32 = 0.05;
+
+/// 应用偏好设置
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// 未选中分割线时按 Delete 移除当前图片
+    pub delete_removes_image: bool,
+    /// 移除图片前弹出确认
+    pub confirm_image_removal: bool,
+    /// 显示底部缩略图栏
+    pub show_gallery: bool,
+    /// 缩略图栏占中央区域高度的比例
+    pub gallery_fraction: f32,
+    /// 快捷键映射
+    pub key_bindings: KeyBindings,
+    /// 上次使用的输出选项
+    pub output: OutputDefaults,
+    /// 背景参考色 (RGB)，用于裁边和空白检测
+    pub background_color: [u8; 3],
+    /// 扫描文件夹时忽略隐藏文件
+    pub ignore_hidden_files: bool,
+    /// 选择文件夹时同时扫描子文件夹
+    pub scan_subfolders: bool,
+    /// 均分分割线时每个分块的最小宽高（像素）
+    pub min_cell_size: u32,
+    /// 预览中叠加标出实际裁切像素边界的细参考线
+    pub precise_line_guides: bool,
+    /// 拖动分割线时吸附的步长（相对位置，如 0.05 即 5%），`None` 表示不吸附
+    pub snap_step: Option<f32>,
+    /// 切换图片时保留分割线选择（新图片有相同序号的线时）
+    pub keep_selection_on_switch: bool,
+    /// 上次选择的输出文件夹，「处理当前图片」直接使用
+    pub last_output_dir: Option<std::path::PathBuf>,
+    /// 分块输出到每张源图片旁的 `{名称}_split` 文件夹，而不是输出文件夹
+    pub output_alongside_source: bool,
+    /// 独立配置自动保存到图片旁的 `.split.json` 文件，添加图片时自动读取
+    pub sidecar_overrides: bool,
+    /// 内存中最多保留的缩略图纹理数量，超出时淘汰最久未显示的
+    pub max_thumbnails: usize,
+    /// 缩略图纹理的显存预算（MB）
+    pub thumbnail_memory_mb: u32,
+}
+
+/// 输出选项，各格式的参数分开保存，切换格式时恢复该格式上次的设置
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputDefaults {
+    pub format: OutputFormat,
+    pub keep_source_format: bool,
+    pub skip_existing: bool,
+    pub embed_source_info: bool,
+    pub jpeg_quality: u8,
+    pub png_compression: PngCompression,
+    pub png_filter: PngFilter,
+    pub png_quantize: bool,
+    pub png_max_colors: u16,
+    pub webp_lossless: bool,
+    pub naming_scheme: NamingScheme,
+    pub tile_order: TileOrder,
+    pub per_image_subfolder: bool,
+    pub filename_template: String,
+    pub multi_scale: bool,
+    pub output_scales: Vec<f32>,
+    pub scale_suffix: String,
+    pub export_overview: bool,
+    pub overview_labels: bool,
+    pub auto_detect_grid: bool,
+    pub detect_sensitivity: u8,
+    pub uniform_canvas: bool,
+    pub canvas_width: u32,
+    pub canvas_height: u32,
+}
+
+impl Default for OutputDefaults {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::default(),
+            keep_source_format: false,
+            skip_existing: false,
+            embed_source_info: false,
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            png_compression: PngCompression::default(),
+            png_filter: PngFilter::default(),
+            png_quantize: false,
+            png_max_colors: 256,
+            webp_lossless: true,
+            naming_scheme: NamingScheme::default(),
+            tile_order: TileOrder::default(),
+            per_image_subfolder: false,
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            multi_scale: false,
+            output_scales: vec![1.0, 2.0, 3.0],
+            scale_suffix: DEFAULT_SCALE_SUFFIX.to_string(),
+            export_overview: false,
+            overview_labels: true,
+            auto_detect_grid: false,
+            detect_sensitivity: DEFAULT_DETECT_SENSITIVITY,
+            uniform_canvas: false,
+            canvas_width: 256,
+            canvas_height: 256,
+        }
+    }
+}
+
+impl OutputDefaults {
+    /// 从配置中记录输出选项
+    pub fn capture(config: &SplitConfig) -> Self {
+        Self {
+            format: config.output_format,
+            keep_source_format: config.keep_source_format,
+            skip_existing: config.skip_existing,
+            embed_source_info: config.embed_source_info,
+            jpeg_quality: config.jpeg_quality,
+            png_compression: config.png_compression,
+            png_filter: config.png_filter,
+            png_quantize: config.png_quantize,
+            png_max_colors: config.png_max_colors,
+            webp_lossless: config.webp_lossless,
+            naming_scheme: config.naming_scheme,
+            tile_order: config.tile_order,
+            per_image_subfolder: config.per_image_subfolder,
+            filename_template: config.filename_template.clone(),
+            multi_scale: config.multi_scale,
+            output_scales: config.output_scales.clone(),
+            scale_suffix: config.scale_suffix.clone(),
+            export_overview: config.export_overview,
+            overview_labels: config.overview_labels,
+            auto_detect_grid: config.auto_detect_grid,
+            detect_sensitivity: config.detect_sensitivity,
+            uniform_canvas: config.uniform_canvas,
+            canvas_width: config.canvas_width,
+            canvas_height: config.canvas_height,
+        }
+    }
+
+    /// 将输出选项写入配置
+    pub fn apply_to(&self, config: &mut SplitConfig) {
+        config.output_format = self.format;
+        config.keep_source_format = self.keep_source_format;
+        config.skip_existing = self.skip_existing;
+        config.embed_source_info = self.embed_source_info;
+        config.jpeg_quality = valid_jpeg_quality(self.jpeg_quality);
+        config.png_compression = self.png_compression;
+        config.png_filter = self.png_filter;
+        config.png_quantize = self.png_quantize;
+        config.png_max_colors = self.png_max_colors.clamp(2, 256);
+        config.webp_lossless = self.webp_lossless;
+        config.naming_scheme = self.naming_scheme;
+        config.tile_order = self.tile_order;
+        config.per_image_subfolder = self.per_image_subfolder;
+        config.filename_template = self.filename_template.clone();
+        config.multi_scale = self.multi_scale;
+        config.output_scales = self.output_scales.clone();
+        config.scale_suffix = self.scale_suffix.clone();
+        config.export_overview = self.export_overview;
+        config.overview_labels = self.overview_labels;
+        config.auto_detect_grid = self.auto_detect_grid;
+        config.detect_sensitivity = self.detect_sensitivity.min(100);
+        config.uniform_canvas = self.uniform_canvas;
+        config.canvas_width = self.canvas_width.max(1);
+        config.canvas_height = self.canvas_height.max(1);
+    }
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            delete_removes_image: false,
+            confirm_image_removal: true,
+            show_gallery: true,
+            gallery_fraction: 0.3,
+            key_bindings: KeyBindings::default(),
+            output: OutputDefaults::default(),
+            background_color: [255, 255, 255],
+            ignore_hidden_files: true,
+            scan_subfolders: false,
+            min_cell_size: 16,
+            precise_line_guides: true,
+            snap_step: None,
+            keep_selection_on_switch: false,
+            last_output_dir: None,
+            output_alongside_source: false,
+            sidecar_overrides: false,
+            max_thumbnails: 300,
+            thumbnail_memory_mb: 256,
+        }
+    }
+}
+
+impl AppSettings {
+    /// 缩略图栏高度比例的允许范围
+    pub const GALLERY_FRACTION_RANGE: std::ops::RangeInclusive<f32> = 0.1..=0.6;
+
+    /// 从 eframe 存储中读取设置
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|s| eframe::get_value(s, SETTINGS_KEY))
+            .unwrap_or_default()
+    }
+}
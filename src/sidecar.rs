@@ -0,0 +1,122 @@
+//! 独立配置的旁车文件
+//!
+//! 开启后每张图片的独立配置自动写入同目录下的 `<文件名>.split.json`，添加图片时自动读取，
+//! 调整随图片文件一起保存，程序异常退出也不会丢失。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::image_splitter::SplitConfig;
+
+/// 旁车文件名后缀，追加在完整的图片文件名之后
+pub const SIDECAR_SUFFIX: &str = ".split.json";
+
+/// 发现修改后等待这么久再写盘，连续调整时最多每个间隔写一次
+pub const WRITE_DELAY: Duration = Duration::from_millis(800);
+
+/// 图片对应的旁车文件路径，如 `a.png` → `a.png.split.json`
+pub fn sidecar_path(image: &Path) -> PathBuf {
+    let mut name = image.file_name().unwrap_or_default().to_os_string();
+    name.push(SIDECAR_SUFFIX);
+    image.with_file_name(name)
+}
+
+/// 读取图片的旁车文件并规范化分割线，不存在或无法解析时返回 `None`
+pub fn load(image: &Path) -> Option<SplitConfig> {
+    let text = std::fs::read_to_string(sidecar_path(image)).ok()?;
+    let mut config: SplitConfig = serde_json::from_str(&text).ok()?;
+    config.normalize();
+    Some(config)
+}
+
+/// 旁车文件的写入状态，按图片路径记录，不受列表索引变化影响
+#[derive(Default)]
+pub struct SidecarSync {
+    /// 已写入（或读取）的内容，与当前独立配置比较得出需要写入的图片
+    written: HashMap<PathBuf, SplitConfig>,
+    /// 从旁车文件恢复了独立配置的图片
+    loaded: HashSet<PathBuf>,
+    /// 首次发现未写入修改的时间
+    dirty_since: Option<Instant>,
+}
+
+impl SidecarSync {
+    /// 记录从旁车文件读取的配置，读取后无需立即回写
+    pub fn mark_loaded(&mut self, image: &Path, config: &SplitConfig) {
+        self.written.insert(image.to_path_buf(), config.clone());
+        self.loaded.insert(image.to_path_buf());
+    }
+
+    /// 该图片的独立配置是否来自旁车文件
+    pub fn is_loaded(&self, image: &Path) -> bool {
+        self.loaded.contains(image)
+    }
+
+    /// 清空记录（不删除磁盘上的文件），用于清空图片列表
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 把有变化的独立配置写入旁车文件，返回失败信息
+    ///
+    /// 同一文件在列表中出现多次时只同步第一个条目；独立配置被删除时同时删除其旁车文件。
+    /// 发现修改后等待 [`WRITE_DELAY`] 再写，期间的修改合并为一次写入，等待时请求重绘以便按时写盘。
+    pub fn sync(
+        &mut self,
+        ctx: &eframe::egui::Context,
+        image_paths: &[PathBuf],
+        overrides: &HashMap<usize, SplitConfig>,
+    ) -> Vec<String> {
+        let mut current: HashMap<&Path, Option<&SplitConfig>> = HashMap::new();
+        for (idx, path) in image_paths.iter().enumerate() {
+            current.entry(path.as_path()).or_insert_with(|| overrides.get(&idx));
+        }
+        // 已移出列表的图片不再跟踪，旁车文件保留
+        self.written.retain(|path, _| current.contains_key(path.as_path()));
+
+        let changed: Vec<(&Path, Option<&SplitConfig>)> = current
+            .into_iter()
+            .filter(|(path, config)| self.written.get(*path) != *config)
+            .collect();
+        if changed.is_empty() {
+            self.dirty_since = None;
+            return Vec::new();
+        }
+
+        let since = *self.dirty_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < WRITE_DELAY {
+            ctx.request_repaint_after(WRITE_DELAY - since.elapsed());
+            return Vec::new();
+        }
+        self.dirty_since = None;
+
+        let mut errors = Vec::new();
+        for (path, config) in changed {
+            let sidecar = sidecar_path(path);
+            let result = match config {
+                Some(config) => serde_json::to_string_pretty(config)
+                    .map_err(std::io::Error::other)
+                    .and_then(|json| std::fs::write(&sidecar, json)),
+                None => match std::fs::remove_file(&sidecar) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                    _ => Ok(()),
+                },
+            };
+            // 写入失败时也记为已处理，避免每帧重试并重复报错
+            if let Err(e) = result {
+                errors.push(format!("{}: {}", sidecar.display(), e));
+            }
+            match config {
+                Some(config) => {
+                    self.written.insert(path.to_path_buf(), config.clone());
+                }
+                None => {
+                    self.written.remove(path);
+                    self.loaded.remove(path);
+                }
+            }
+        }
+        errors
+    }
+}
@@ -0,0 +1,286 @@
+//! 缩略图的磁盘缓存
+//!
+//! 缩略图按源文件路径、修改时间、大小以及影响画面方向的配置（EXIF 校正、整图旋转和翻转）生成键，
+//! 缩小后的图片以 PNG 保存在程序数据目录的 `thumbnails` 文件夹中。源文件未变时直接读取缓存，
+//! 重新打开大文件夹时不必逐张解码原图。
+//!
+//! 缩略图由 [`ThumbnailLoader`] 在限定线程数的后台线程池中生成，界面只取回已完成的结果，
+//! 滚动大列表时不会因为解码而卡顿。上传后的纹理由 [`ThumbnailTextures`] 按最近使用顺序保留，
+//! 超出数量或显存预算时淘汰最久未显示的，再次滚动到时重新生成（通常直接读磁盘缓存）。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use eframe::egui;
+use image::{DynamicImage, ImageFormat};
+
+use crate::image_splitter::{ImageSplitter, Rotation, SplitConfig};
+
+/// 缩略图最长边（像素），预留缩放空间
+pub const THUMBNAIL_SIZE: u32 = 512;
+
+/// 同时解码缩略图的线程数上限
+pub const MAX_THUMBNAIL_THREADS: usize = 4;
+
+/// 超过这么久未重新生成的缓存文件在启动时删除
+pub const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// 缓存文件夹，无法确定程序数据目录时返回 `None`（不使用缓存）
+fn cache_dir() -> Option<PathBuf> {
+    eframe::storage_dir(crate::APP_NAME).map(|dir| dir.join("thumbnails"))
+}
+
+/// 缓存键：源文件、缩略图尺寸或方向相关配置变化时随之改变，无法读取文件信息时返回 `None`
+///
+/// 缓存跨版本保留在磁盘上，因此逐字节计算 FNV-1a，不依赖标准库哈希算法和 `Hash` 实现的细节。
+fn cache_key(path: &Path, config: &SplitConfig) -> Option<u64> {
+    let meta = std::fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    let mut key = Fnv1a::default();
+    key.write(path.as_os_str().as_encoded_bytes());
+    key.write(&modified.as_secs().to_le_bytes());
+    key.write(&modified.subsec_nanos().to_le_bytes());
+    key.write(&meta.len().to_le_bytes());
+    key.write(&THUMBNAIL_SIZE.to_le_bytes());
+    key.write(&[
+        u8::from(config.apply_exif_orientation),
+        u8::from(config.image_flip_h),
+        u8::from(config.image_flip_v),
+    ]);
+    key.write(&config.image_rotation.map_or(0, Rotation::degrees).to_le_bytes());
+    Some(key.0)
+}
+
+/// 64 位 FNV-1a 哈希，结果只取决于输入字节
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// 按配置得到图片的缩略图：缓存有效时直接读取，否则解码原图生成并写入缓存
+///
+/// 由 [`ThumbnailLoader`] 的线程池调用，缓存在当前线程写入，不另开线程。
+pub fn load(path: &Path, config: &SplitConfig) -> anyhow::Result<DynamicImage> {
+    let cached = cache_dir()
+        .zip(cache_key(path, config))
+        .map(|(dir, key)| dir.join(format!("{:016x}.png", key)));
+    if let Some(file) = &cached {
+        if let Ok(thumb) = image::open(file) {
+            return Ok(thumb);
+        }
+    }
+
+    let thumb = ImageSplitter::open_image_for(path, config)?.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    if let Some(file) = cached {
+        // 先写临时文件再改名，避免读到写了一半的缓存；写入失败只是下次重新生成
+        let tmp = file.with_extension("tmp");
+        let written = file.parent().is_some_and(|dir| std::fs::create_dir_all(dir).is_ok())
+            && thumb.save_with_format(&tmp, ImageFormat::Png).is_ok()
+            && std::fs::rename(&tmp, &file).is_ok();
+        if !written {
+            let _ = std::fs::remove_file(&tmp);
+        }
+    }
+    Ok(thumb)
+}
+
+/// 在后台删除过期的缓存文件，源图片改动或不再使用后留下的旧缩略图由此清理
+pub fn prune_in_background() {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+    std::thread::spawn(move || {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return;
+        };
+        let now = SystemTime::now();
+        for entry in entries.flatten() {
+            let expired = entry
+                .metadata()
+                .and_then(|meta| meta.modified())
+                .is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() > MAX_AGE);
+            if expired {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    });
+}
+
+/// 后台生成完成的缩略图
+pub struct FinishedThumbnail {
+    /// 请求时图片在列表中的索引
+    pub index: usize,
+    /// 请求时的图片路径，取回时用于确认该索引仍是同一张图片
+    pub path: PathBuf,
+    /// 缩略图像素，无法读取图片时为 `None`
+    pub image: Option<egui::ColorImage>,
+    epoch: u64,
+}
+
+/// 后台缩略图生成：任务在限定线程数的线程池中执行，结果经通道取回
+pub struct ThumbnailLoader {
+    /// 创建失败时退回 rayon 全局线程池
+    pool: Option<rayon::ThreadPool>,
+    sender: Sender<FinishedThumbnail>,
+    receiver: Receiver<FinishedThumbnail>,
+    /// 已提交、尚未取回的图片索引
+    pending: HashSet<usize>,
+    /// 每次 [`Self::invalidate`] 加一，之前提交的任务的结果被丢弃
+    epoch: u64,
+}
+
+impl Default for ThumbnailLoader {
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, |n| (n.get() / 2).clamp(1, MAX_THUMBNAIL_THREADS));
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("thumbnail-{}", i))
+            .build()
+            .ok();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        Self { pool, sender, receiver, pending: HashSet::new(), epoch: 0 }
+    }
+}
+
+impl ThumbnailLoader {
+    /// 在后台生成第 `index` 张图片的缩略图，该图片已在生成中时忽略；完成后请求重绘
+    pub fn request(&mut self, ctx: &egui::Context, index: usize, path: &Path, config: SplitConfig) {
+        if !self.pending.insert(index) {
+            return;
+        }
+        let (ctx, path) = (ctx.clone(), path.to_path_buf());
+        let sender = self.sender.clone();
+        let epoch = self.epoch;
+        let job = move || {
+            let image = load(&path, &config).ok().map(|thumb| {
+                let size = [thumb.width() as usize, thumb.height() as usize];
+                egui::ColorImage::from_rgba_unmultiplied(size, thumb.to_rgba8().as_raw())
+            });
+            if sender.send(FinishedThumbnail { index, path, image, epoch }).is_ok() {
+                ctx.request_repaint();
+            }
+        };
+        match &self.pool {
+            Some(pool) => pool.spawn(job),
+            None => rayon::spawn(job),
+        }
+    }
+
+    /// 第 `index` 张图片的缩略图是否正在生成
+    pub fn is_pending(&self, index: usize) -> bool {
+        self.pending.contains(&index)
+    }
+
+    /// 取出已完成、且在上次 [`Self::invalidate`] 之后提交的缩略图
+    pub fn finished(&mut self) -> Vec<FinishedThumbnail> {
+        let mut finished = Vec::new();
+        for thumbnail in self.receiver.try_iter() {
+            if thumbnail.epoch == self.epoch {
+                self.pending.remove(&thumbnail.index);
+                finished.push(thumbnail);
+            }
+        }
+        finished
+    }
+
+    /// 放弃所有进行中的任务的结果，用于列表索引变化或图片方向配置改变之后
+    pub fn invalidate(&mut self) {
+        self.epoch += 1;
+        self.pending.clear();
+    }
+}
+
+/// 已上传的缩略图纹理，超出数量或显存预算时按最近显示的顺序淘汰
+#[derive(Default)]
+pub struct ThumbnailTextures {
+    /// 图片索引 -> (纹理, 最近一次显示的帧号)
+    entries: HashMap<usize, (egui::TextureHandle, u64)>,
+    /// 纹理按 RGBA8 估算的总字节数
+    bytes: u64,
+}
+
+/// RGBA8 纹理每像素 4 字节，不含 mipmap 和驱动开销
+pub fn texture_bytes(texture: &egui::TextureHandle) -> u64 {
+    (texture.size()[0] * texture.size()[1] * 4) as u64
+}
+
+impl ThumbnailTextures {
+    pub fn get(&self, index: usize) -> Option<&egui::TextureHandle> {
+        self.entries.get(&index).map(|(texture, _)| texture)
+    }
+
+    /// 记录第 `index` 张图片的缩略图在第 `frame` 帧显示过
+    pub fn touch(&mut self, index: usize, frame: u64) {
+        if let Some((_, used)) = self.entries.get_mut(&index) {
+            *used = frame;
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, texture: egui::TextureHandle, frame: u64) {
+        self.bytes += texture_bytes(&texture);
+        if let Some((old, _)) = self.entries.insert(index, (texture, frame)) {
+            self.bytes -= texture_bytes(&old);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if let Some((old, _)) = self.entries.remove(&index) {
+            self.bytes -= texture_bytes(&old);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+
+    /// 按映射函数重排索引，返回 `None` 的条目被丢弃
+    pub fn remap(&mut self, map: impl Fn(usize) -> Option<usize>) {
+        self.entries = std::mem::take(&mut self.entries)
+            .into_iter()
+            .filter_map(|(i, entry)| map(i).map(|i| (i, entry)))
+            .collect();
+        self.bytes = self.entries.values().map(|(texture, _)| texture_bytes(texture)).sum();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// 超出 `max_count` 个或 `max_bytes` 字节时淘汰最久未显示的纹理，返回淘汰数量
+    ///
+    /// 本帧和上一帧显示过的纹理不淘汰：同时可见的缩略图多于预算时暂时超出，避免反复生成。
+    pub fn evict(&mut self, max_count: usize, max_bytes: u64, frame: u64) -> usize {
+        if self.entries.len() <= max_count && self.bytes <= max_bytes {
+            return 0;
+        }
+        let mut by_age: Vec<(u64, usize)> = self.entries.iter().map(|(&i, &(_, used))| (used, i)).collect();
+        by_age.sort_unstable();
+        let mut evicted = 0;
+        for (used, index) in by_age {
+            if (self.entries.len() <= max_count && self.bytes <= max_bytes) || used + 1 >= frame {
+                break;
+            }
+            self.remove(index);
+            evicted += 1;
+        }
+        evicted
+    }
+}